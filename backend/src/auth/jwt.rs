@@ -9,6 +9,14 @@ use shared::UserRole;
 const JWT_ISSUER: &str = "rust-ecommerce";
 /// JWT audience identifier
 const JWT_AUDIENCE: &str = "rust-ecommerce-api";
+/// Audience of the intermediate token issued between password and 2FA code
+const CHALLENGE_AUDIENCE: &str = "rust-ecommerce-2fa";
+/// How long the user has to enter their 2FA code after the password step
+pub const CHALLENGE_TTL_SECONDS: i64 = 300;
+/// Access tokens are short-lived; clients renew them with a refresh token
+const ACCESS_TOKEN_TTL_MINUTES: i64 = 15;
+/// Lifetime of a refresh token; each rotation starts a new period
+pub const REFRESH_TOKEN_TTL_DAYS: i64 = 30;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
@@ -19,11 +27,35 @@ pub struct Claims {
     pub iat: i64,        // issued at
     pub iss: String,     // issuer
     pub aud: String,     // audience
+    pub jti: Uuid,       // session id, checked against the sessions table
+    #[serde(default)]
+    pub mfa: bool,       // session was started with a second factor
 }
 
-pub fn create_token(user_id: Uuid, email: &str, role: &UserRole, secret: &str) -> Result<String> {
+/// Proof that the password step of a 2FA login succeeded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeClaims {
+    pub sub: Uuid,
+    pub exp: i64,
+    pub iat: i64,
+    pub iss: String,
+    pub aud: String,
+}
+
+pub fn refresh_token_ttl() -> Duration {
+    Duration::days(REFRESH_TOKEN_TTL_DAYS)
+}
+
+pub fn create_token(
+    user_id: Uuid,
+    email: &str,
+    role: &UserRole,
+    session_id: Uuid,
+    mfa: bool,
+    secret: &str,
+) -> Result<String> {
     let now = Utc::now();
-    let exp = now + Duration::hours(24);
+    let exp = now + Duration::minutes(ACCESS_TOKEN_TTL_MINUTES);
 
     let claims = Claims {
         sub: user_id,
@@ -33,6 +65,8 @@ pub fn create_token(user_id: Uuid, email: &str, role: &UserRole, secret: &str) -
         iat: now.timestamp(),
         iss: JWT_ISSUER.to_string(),
         aud: JWT_AUDIENCE.to_string(),
+        jti: session_id,
+        mfa,
     };
 
     let token = encode(
@@ -44,6 +78,8 @@ pub fn create_token(user_id: Uuid, email: &str, role: &UserRole, secret: &str) -
     Ok(token)
 }
 
+/// Validate signature, expiry, issuer and audience. Tokens without a `jti`
+/// (issued before sessions existed) fail to decode and are rejected.
 pub fn verify_token(token: &str, secret: &str) -> Result<Claims> {
     let mut validation = Validation::default();
     validation.set_issuer(&[JWT_ISSUER]);
@@ -57,3 +93,29 @@ pub fn verify_token(token: &str, secret: &str) -> Result<Claims> {
 
     Ok(token_data.claims)
 }
+
+pub fn create_challenge_token(user_id: Uuid, secret: &str) -> Result<String> {
+    let now = Utc::now();
+
+    let claims = ChallengeClaims {
+        sub: user_id,
+        exp: (now + Duration::seconds(CHALLENGE_TTL_SECONDS)).timestamp(),
+        iat: now.timestamp(),
+        iss: JWT_ISSUER.to_string(),
+        aud: CHALLENGE_AUDIENCE.to_string(),
+    };
+
+    Ok(encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes()))?)
+}
+
+/// Challenge tokens use their own audience, so they are never accepted as
+/// access tokens and vice versa
+pub fn verify_challenge_token(token: &str, secret: &str) -> Result<ChallengeClaims> {
+    let mut validation = Validation::default();
+    validation.set_issuer(&[JWT_ISSUER]);
+    validation.set_audience(&[CHALLENGE_AUDIENCE]);
+
+    let token_data = decode::<ChallengeClaims>(token, &DecodingKey::from_secret(secret.as_bytes()), &validation)?;
+
+    Ok(token_data.claims)
+}
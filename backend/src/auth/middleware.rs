@@ -6,8 +6,8 @@ use axum::{
     Json,
 };
 use std::sync::Arc;
-use shared::ApiError;
-use crate::AppState;
+use shared::{ApiError, UserRole};
+use crate::{AppState, db::SessionRepository};
 use super::jwt::{verify_token, Claims};
 
 pub async fn auth_middleware(
@@ -37,6 +37,18 @@ pub async fn auth_middleware(
         )
     })?;
 
+    // Logout and refresh token reuse revoke the session behind the token
+    let active = SessionRepository::is_active(&state.db.pool, claims.jti)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if !active {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ApiError::unauthorized("Session has been revoked")),
+        ));
+    }
+
     // Store claims in request extensions for later use
     request.extensions_mut().insert(claims);
 
@@ -70,3 +82,39 @@ where
             })
     }
 }
+
+/// Reject the request unless the authenticated user holds `role`.
+/// Admins implicitly satisfy every role check.
+pub fn require_role(claims: &Claims, role: UserRole) -> Result<(), (StatusCode, Json<ApiError>)> {
+    if claims.role == role || claims.role == UserRole::Admin {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiError::forbidden("Insufficient privileges")),
+        ))
+    }
+}
+
+/// Route layer for admin-only groups; must be layered inside `auth_middleware`.
+/// When the 2FA policy is on, the session must also have passed a second factor.
+pub async fn require_admin(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    request: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, Json<ApiError>)> {
+    require_role(&claims, UserRole::Admin)?;
+
+    if state.require_admin_2fa && !claims.mfa {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiError::new(
+                "TWO_FACTOR_REQUIRED",
+                "Admin access requires two-factor authentication; enable it and sign in again",
+            )),
+        ));
+    }
+
+    Ok(next.run(request).await)
+}
@@ -1,7 +1,10 @@
 mod jwt;
 mod middleware;
 mod password;
+mod tokens;
+pub mod totp;
 
 pub use jwt::*;
 pub use middleware::*;
 pub use password::*;
+pub use tokens::*;
@@ -0,0 +1,39 @@
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+/// Random opaque token (256 bits, hex encoded) handed to the client once
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Hash of an opaque token as stored in the database. Tokens are random and
+/// high-entropy, so a plain SHA-256 is enough; no salt or slow hash needed.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_tokens_are_unique_hex() {
+        let a = generate_token();
+        let b = generate_token();
+
+        assert_eq!(a.len(), 64);
+        assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn hash_is_stable_and_differs_from_token() {
+        let token = generate_token();
+
+        assert_eq!(hash_token(&token), hash_token(&token));
+        assert_ne!(hash_token(&token), token);
+        assert_ne!(hash_token(&token), hash_token(&generate_token()));
+    }
+}
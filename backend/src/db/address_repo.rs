@@ -0,0 +1,229 @@
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+use shared::{Address, SavedAddress, SaveAddressRequest};
+
+/// (id, user_id, label, name, line1, line2, city, region, postal_code, country, phone, is_default, created_at, updated_at)
+type AddressRow = (
+    String, String, Option<String>, String, String, Option<String>, String,
+    Option<String>, String, String, Option<String>, i32, String, String,
+);
+
+const ADDRESS_COLUMNS: &str = "id, user_id, label, name, line1, line2, city, region, postal_code, country, phone, is_default, created_at, updated_at";
+
+pub struct AddressRepository;
+
+impl AddressRepository {
+    pub async fn list_by_user(pool: &SqlitePool, user_id: Uuid) -> Result<Vec<SavedAddress>> {
+        let rows: Vec<AddressRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM addresses WHERE user_id = ? ORDER BY is_default DESC, created_at ASC",
+            ADDRESS_COLUMNS
+        ))
+        .bind(user_id.to_string())
+        .fetch_all(pool)
+        .await?;
+
+        let addresses: Vec<SavedAddress> = rows
+            .into_iter()
+            .filter_map(|row| Self::row_to_address(row).ok())
+            .collect();
+
+        Ok(addresses)
+    }
+
+    /// Fetch an address only if it belongs to `user_id`
+    pub async fn get(pool: &SqlitePool, user_id: Uuid, id: Uuid) -> Result<Option<SavedAddress>> {
+        let row: Option<AddressRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM addresses WHERE id = ? AND user_id = ?",
+            ADDRESS_COLUMNS
+        ))
+        .bind(id.to_string())
+        .bind(user_id.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(Self::row_to_address(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get_default(pool: &SqlitePool, user_id: Uuid) -> Result<Option<SavedAddress>> {
+        let row: Option<AddressRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM addresses WHERE user_id = ? AND is_default = 1",
+            ADDRESS_COLUMNS
+        ))
+        .bind(user_id.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(Self::row_to_address(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Create an address; the user's first address always becomes the default
+    pub async fn create(
+        pool: &SqlitePool,
+        user_id: Uuid,
+        req: &SaveAddressRequest,
+    ) -> Result<SavedAddress> {
+        let mut tx = pool.begin().await?;
+
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+
+        let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM addresses WHERE user_id = ?")
+            .bind(user_id.to_string())
+            .fetch_one(&mut *tx)
+            .await?;
+        let is_default = req.is_default || count.0 == 0;
+
+        if is_default {
+            sqlx::query("UPDATE addresses SET is_default = 0 WHERE user_id = ?")
+                .bind(user_id.to_string())
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let a = &req.address;
+        sqlx::query(
+            r#"
+            INSERT INTO addresses (id, user_id, label, name, line1, line2, city, region, postal_code, country, phone, is_default, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(id.to_string())
+        .bind(user_id.to_string())
+        .bind(&req.label)
+        .bind(&a.name)
+        .bind(&a.line1)
+        .bind(&a.line2)
+        .bind(&a.city)
+        .bind(&a.region)
+        .bind(&a.postal_code)
+        .bind(&a.country)
+        .bind(&a.phone)
+        .bind(is_default as i32)
+        .bind(now.to_rfc3339())
+        .bind(now.to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(SavedAddress {
+            id,
+            user_id,
+            label: req.label.clone(),
+            address: a.clone(),
+            is_default,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    /// Replace an address owned by `user_id`; returns None if it doesn't exist
+    pub async fn update(
+        pool: &SqlitePool,
+        user_id: Uuid,
+        id: Uuid,
+        req: &SaveAddressRequest,
+    ) -> Result<Option<SavedAddress>> {
+        let mut tx = pool.begin().await?;
+
+        if req.is_default {
+            sqlx::query("UPDATE addresses SET is_default = 0 WHERE user_id = ?")
+                .bind(user_id.to_string())
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        // Unsetting the flag is ignored so the user always keeps a default
+        let a = &req.address;
+        let result = sqlx::query(
+            r#"
+            UPDATE addresses
+            SET label = ?, name = ?, line1 = ?, line2 = ?, city = ?, region = ?, postal_code = ?,
+                country = ?, phone = ?, is_default = MAX(is_default, ?), updated_at = ?
+            WHERE id = ? AND user_id = ?
+            "#,
+        )
+        .bind(&req.label)
+        .bind(&a.name)
+        .bind(&a.line1)
+        .bind(&a.line2)
+        .bind(&a.city)
+        .bind(&a.region)
+        .bind(&a.postal_code)
+        .bind(&a.country)
+        .bind(&a.phone)
+        .bind(req.is_default as i32)
+        .bind(Utc::now().to_rfc3339())
+        .bind(id.to_string())
+        .bind(user_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        tx.commit().await?;
+
+        Self::get(pool, user_id, id).await
+    }
+
+    /// Delete an address; if it was the default, the oldest remaining one takes over
+    pub async fn delete(pool: &SqlitePool, user_id: Uuid, id: Uuid) -> Result<bool> {
+        let mut tx = pool.begin().await?;
+
+        let result = sqlx::query("DELETE FROM addresses WHERE id = ? AND user_id = ?")
+            .bind(id.to_string())
+            .bind(user_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE addresses SET is_default = 1
+            WHERE id = (SELECT id FROM addresses WHERE user_id = ? ORDER BY created_at ASC LIMIT 1)
+            AND NOT EXISTS (SELECT 1 FROM addresses WHERE user_id = ? AND is_default = 1)
+            "#,
+        )
+        .bind(user_id.to_string())
+        .bind(user_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    fn row_to_address(row: AddressRow) -> Result<SavedAddress> {
+        Ok(SavedAddress {
+            id: row.0.parse()?,
+            user_id: row.1.parse()?,
+            label: row.2,
+            address: Address {
+                name: row.3,
+                line1: row.4,
+                line2: row.5,
+                city: row.6,
+                region: row.7,
+                postal_code: row.8,
+                country: row.9,
+                phone: row.10,
+            },
+            is_default: row.11 == 1,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.12)?.with_timezone(&Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.13)?.with_timezone(&Utc),
+        })
+    }
+}
@@ -1,18 +1,32 @@
 use anyhow::Result;
 use sqlx::SqlitePool;
 use uuid::Uuid;
-use shared::{Cart, CartItem, CartItemWithProduct};
+use shared::{Cart, CartItem, CartItemWithProduct, Currency, Money};
+use super::CouponRepository;
+
+/// (product_id, variant_id, name, variant_title, category_id, price_cents, currency, image_url, quantity)
+type CartRow = (String, String, String, Option<String>, Option<String>, i64, String, Option<String>, i32);
+
+/// `cart_items.variant_id` holds '' for products without variants, as it is
+/// part of the primary key
+fn variant_key(variant_id: Option<Uuid>) -> String {
+    variant_id.map(|id| id.to_string()).unwrap_or_default()
+}
 
 pub struct CartRepository;
 
 impl CartRepository {
-    pub async fn get_cart(pool: &SqlitePool, user_id: Uuid) -> Result<Cart> {
-        let rows: Vec<(String, String, f64, Option<String>, i32)> = sqlx::query_as(
+    /// The cart priced with the applied coupon, if it still gives a discount,
+    /// and a flat `shipping_fee` in minor units of the cart's currency
+    pub async fn get_cart(pool: &SqlitePool, user_id: Uuid, shipping_fee: i64) -> Result<Cart> {
+        let rows: Vec<CartRow> = sqlx::query_as(
             r#"
-            SELECT p.id, p.name, p.price, p.image_url, c.quantity
+            SELECT p.id, c.variant_id, p.name, v.title, p.category_id, COALESCE(v.price_cents, p.price_cents), p.currency,
+                   COALESCE(p.thumbnail_url, p.image_url), c.quantity
             FROM cart_items c
             JOIN products p ON c.product_id = p.id
-            WHERE c.user_id = ? AND p.is_active = 1
+            LEFT JOIN product_variants v ON v.id = c.variant_id
+            WHERE c.user_id = ? AND p.is_active = 1 AND (c.variant_id = '' OR v.is_active = 1)
             "#,
         )
         .bind(user_id.to_string())
@@ -21,43 +35,72 @@ impl CartRepository {
 
         let items: Vec<CartItemWithProduct> = rows
             .into_iter()
-            .map(|(product_id, name, price, image_url, quantity)| {
-                CartItemWithProduct {
+            .map(|(product_id, variant_id, name, variant_title, category_id, price_cents, currency, image_url, quantity)| {
+                let currency = Currency::new(&currency)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid currency code: {}", currency))?;
+                let price = Money::new(price_cents, currency);
+
+                Ok(CartItemWithProduct {
                     product_id: product_id.parse().unwrap_or_default(),
+                    variant_id: variant_id.parse().ok(),
                     product_name: name,
+                    variant_title,
+                    category_id: category_id.and_then(|id| id.parse().ok()),
                     product_price: price,
                     product_image_url: image_url,
                     quantity,
-                    subtotal: price * quantity as f64,
-                }
+                    subtotal: price.checked_mul(quantity as i64)?,
+                })
             })
-            .collect();
+            .collect::<Result<_>>()?;
 
-        let total = items.iter().map(|i| i.subtotal).sum();
+        let currency = items.first().map(|item| item.product_price.currency).unwrap_or_default();
+        // Nothing to ship yet
+        let shipping = if items.is_empty() { Money::zero(currency) } else { Money::new(shipping_fee, currency) };
 
-        Ok(Cart {
+        let mut cart = Cart {
             user_id,
             items,
-            total,
-        })
+            coupon_code: None,
+            subtotal: Money::zero(currency),
+            discounts: Vec::new(),
+            shipping,
+            total: Money::zero(currency),
+            coupon_error: None,
+        };
+
+        if let Some(coupon) = CouponRepository::for_cart(pool, user_id).await? {
+            cart.coupon_code = Some(coupon.coupon.code.clone());
+            if !cart.is_empty() {
+                match coupon.apply(&cart.items, cart.shipping) {
+                    Ok(applied) => cart.discounts.push(applied.line),
+                    Err(e) => cart.coupon_error = Some(e.to_string()),
+                }
+            }
+        }
+        cart.calculate_total()?;
+
+        Ok(cart)
     }
 
     pub async fn add_item(
         pool: &SqlitePool,
         user_id: Uuid,
         product_id: Uuid,
+        variant_id: Option<Uuid>,
         quantity: i32,
     ) -> Result<()> {
         // Use INSERT OR REPLACE to handle duplicates
         sqlx::query(
             r#"
-            INSERT INTO cart_items (user_id, product_id, quantity)
-            VALUES (?, ?, ?)
-            ON CONFLICT(user_id, product_id) DO UPDATE SET quantity = quantity + excluded.quantity
+            INSERT INTO cart_items (user_id, product_id, variant_id, quantity)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT(user_id, product_id, variant_id) DO UPDATE SET quantity = quantity + excluded.quantity
             "#,
         )
         .bind(user_id.to_string())
         .bind(product_id.to_string())
+        .bind(variant_key(variant_id))
         .bind(quantity)
         .execute(pool)
         .await?;
@@ -69,14 +112,16 @@ impl CartRepository {
         pool: &SqlitePool,
         user_id: Uuid,
         product_id: Uuid,
+        variant_id: Option<Uuid>,
         quantity: i32,
     ) -> Result<bool> {
         let result = sqlx::query(
-            "UPDATE cart_items SET quantity = ? WHERE user_id = ? AND product_id = ?",
+            "UPDATE cart_items SET quantity = ? WHERE user_id = ? AND product_id = ? AND variant_id = ?",
         )
         .bind(quantity)
         .bind(user_id.to_string())
         .bind(product_id.to_string())
+        .bind(variant_key(variant_id))
         .execute(pool)
         .await?;
 
@@ -87,12 +132,14 @@ impl CartRepository {
         pool: &SqlitePool,
         user_id: Uuid,
         product_id: Uuid,
+        variant_id: Option<Uuid>,
     ) -> Result<bool> {
         let result = sqlx::query(
-            "DELETE FROM cart_items WHERE user_id = ? AND product_id = ?",
+            "DELETE FROM cart_items WHERE user_id = ? AND product_id = ? AND variant_id = ?",
         )
         .bind(user_id.to_string())
         .bind(product_id.to_string())
+        .bind(variant_key(variant_id))
         .execute(pool)
         .await?;
 
@@ -112,8 +159,8 @@ impl CartRepository {
         pool: &SqlitePool,
         user_id: Uuid,
     ) -> Result<Vec<CartItem>> {
-        let rows: Vec<(String, String, i32)> = sqlx::query_as(
-            "SELECT user_id, product_id, quantity FROM cart_items WHERE user_id = ?",
+        let rows: Vec<(String, String, String, i32)> = sqlx::query_as(
+            "SELECT user_id, product_id, variant_id, quantity FROM cart_items WHERE user_id = ?",
         )
         .bind(user_id.to_string())
         .fetch_all(pool)
@@ -121,10 +168,11 @@ impl CartRepository {
 
         let items: Vec<CartItem> = rows
             .into_iter()
-            .filter_map(|(user_id, product_id, quantity)| {
+            .filter_map(|(user_id, product_id, variant_id, quantity)| {
                 Some(CartItem {
                     user_id: user_id.parse().ok()?,
                     product_id: product_id.parse().ok()?,
+                    variant_id: variant_id.parse().ok(),
                     quantity,
                 })
             })
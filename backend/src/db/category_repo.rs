@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+use shared::{CategoryNode, CreateCategoryRequest, ProductCategory, UpdateCategoryRequest};
+
+/// (id, parent_id, name, slug, description, sort_order, image_url)
+type CategoryRow = (String, Option<String>, String, String, String, i32, Option<String>);
+
+const CATEGORY_COLUMNS: &str = "id, parent_id, name, slug, description, sort_order, image_url";
+
+pub struct CategoryRepository;
+
+impl CategoryRepository {
+    /// All categories in display order
+    pub async fn list(pool: &SqlitePool) -> Result<Vec<ProductCategory>> {
+        let rows: Vec<CategoryRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM categories ORDER BY sort_order, name",
+            CATEGORY_COLUMNS
+        ))
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_category).collect()
+    }
+
+    /// The whole tree with active product counts rolled up from descendants
+    pub async fn tree(pool: &SqlitePool) -> Result<Vec<CategoryNode>> {
+        let categories = Self::list(pool).await?;
+
+        let counts: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT category_id, COUNT(*) FROM products WHERE is_active = 1 AND category_id IS NOT NULL GROUP BY category_id",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let counts = counts
+            .into_iter()
+            .filter_map(|(id, count)| Some((id.parse().ok()?, count as u32)))
+            .collect();
+
+        Ok(build_tree(categories, &counts))
+    }
+
+    pub async fn get_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<ProductCategory>> {
+        let row: Option<CategoryRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM categories WHERE id = ?",
+            CATEGORY_COLUMNS
+        ))
+        .bind(id.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(Self::row_to_category).transpose()
+    }
+
+    /// Look a category up by slug, or by name as used on products
+    pub async fn find(pool: &SqlitePool, slug_or_name: &str) -> Result<Option<ProductCategory>> {
+        let row: Option<CategoryRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM categories WHERE slug = ? OR name = ? ORDER BY slug = ? DESC LIMIT 1",
+            CATEGORY_COLUMNS
+        ))
+        .bind(slug_or_name)
+        .bind(slug_or_name)
+        .bind(slug_or_name)
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(Self::row_to_category).transpose()
+    }
+
+    /// Whether another category already uses this name or slug
+    pub async fn name_or_slug_taken(pool: &SqlitePool, name: &str, slug: &str, except: Option<Uuid>) -> Result<bool> {
+        let row: Option<(String,)> = sqlx::query_as(
+            "SELECT id FROM categories WHERE (name = ? OR slug = ?) AND id != ? LIMIT 1",
+        )
+        .bind(name)
+        .bind(slug)
+        .bind(except.map(|id| id.to_string()).unwrap_or_default())
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.is_some())
+    }
+
+    /// Whether `id` is `ancestor` itself or lies somewhere below it
+    pub async fn is_within(pool: &SqlitePool, id: Uuid, ancestor: Uuid) -> Result<bool> {
+        let row: Option<(String,)> = sqlx::query_as(
+            r#"
+            WITH RECURSIVE subtree(id) AS (
+                SELECT ?
+                UNION SELECT c.id FROM categories c JOIN subtree s ON c.parent_id = s.id
+            )
+            SELECT id FROM subtree WHERE id = ?
+            "#,
+        )
+        .bind(ancestor.to_string())
+        .bind(id.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.is_some())
+    }
+
+    pub async fn create(pool: &SqlitePool, slug: &str, req: &CreateCategoryRequest) -> Result<ProductCategory> {
+        let id = Uuid::new_v4();
+        let now = Utc::now().to_rfc3339();
+
+        sqlx::query(
+            r#"
+            INSERT INTO categories (id, parent_id, name, slug, description, sort_order, image_url, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(id.to_string())
+        .bind(req.parent_id.map(|id| id.to_string()))
+        .bind(req.name.trim())
+        .bind(slug)
+        .bind(&req.description)
+        .bind(req.sort_order)
+        .bind(&req.image_url)
+        .bind(&now)
+        .bind(&now)
+        .execute(pool)
+        .await?;
+
+        Ok(ProductCategory {
+            id,
+            parent_id: req.parent_id,
+            name: req.name.trim().to_string(),
+            slug: slug.to_string(),
+            description: req.description.clone(),
+            sort_order: req.sort_order,
+            image_url: req.image_url.clone(),
+        })
+    }
+
+    /// Apply a partial update; fields left as `None` keep their current value.
+    /// A rename relabels the category's products.
+    pub async fn update(pool: &SqlitePool, id: Uuid, req: &UpdateCategoryRequest) -> Result<Option<ProductCategory>> {
+        let mut category = match Self::get_by_id(pool, id).await? {
+            Some(category) => category,
+            None => return Ok(None),
+        };
+
+        if let Some(name) = &req.name {
+            category.name = name.trim().to_string();
+        }
+        if let Some(slug) = &req.slug {
+            category.slug = slug.clone();
+        }
+        if req.clear_parent {
+            category.parent_id = None;
+        }
+        if let Some(parent_id) = req.parent_id {
+            category.parent_id = Some(parent_id);
+        }
+        if let Some(description) = &req.description {
+            category.description = description.clone();
+        }
+        if let Some(sort_order) = req.sort_order {
+            category.sort_order = sort_order;
+        }
+        if let Some(image_url) = &req.image_url {
+            category.image_url = Some(image_url.clone());
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE categories
+            SET parent_id = ?, name = ?, slug = ?, description = ?, sort_order = ?, image_url = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(category.parent_id.map(|id| id.to_string()))
+        .bind(&category.name)
+        .bind(&category.slug)
+        .bind(&category.description)
+        .bind(category.sort_order)
+        .bind(&category.image_url)
+        .bind(Utc::now().to_rfc3339())
+        .bind(id.to_string())
+        .execute(pool)
+        .await?;
+
+        Ok(Some(category))
+    }
+
+    /// (subcategories, products) still attached to a category, active or not
+    pub async fn usage(pool: &SqlitePool, id: Uuid) -> Result<(i64, i64)> {
+        let (children,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM categories WHERE parent_id = ?")
+            .bind(id.to_string())
+            .fetch_one(pool)
+            .await?;
+
+        let (products,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM products WHERE category_id = ?")
+            .bind(id.to_string())
+            .fetch_one(pool)
+            .await?;
+
+        Ok((children, products))
+    }
+
+    pub async fn delete(pool: &SqlitePool, id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM categories WHERE id = ?")
+            .bind(id.to_string())
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    fn row_to_category(row: CategoryRow) -> Result<ProductCategory> {
+        let (id, parent_id, name, slug, description, sort_order, image_url) = row;
+
+        Ok(ProductCategory {
+            id: id.parse()?,
+            parent_id: parent_id.map(|id| id.parse()).transpose()?,
+            name,
+            slug,
+            description,
+            sort_order,
+            image_url,
+        })
+    }
+}
+
+/// Lowercase ASCII letters and digits joined by single hyphens,
+/// e.g. "Home & Garden" becomes "home-garden"
+pub fn slugify(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| part.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Arrange categories (already in display order) into a tree. `counts` holds
+/// each category's own active products; a node's count also includes its
+/// descendants. Categories whose parent is missing are shown at the top level.
+pub fn build_tree(categories: Vec<ProductCategory>, counts: &HashMap<Uuid, u32>) -> Vec<CategoryNode> {
+    fn attach(
+        parent: Option<Uuid>,
+        by_parent: &mut HashMap<Option<Uuid>, Vec<ProductCategory>>,
+        counts: &HashMap<Uuid, u32>,
+    ) -> Vec<CategoryNode> {
+        by_parent
+            .remove(&parent)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|category| {
+                let children = attach(Some(category.id), by_parent, counts);
+                let product_count = counts.get(&category.id).copied().unwrap_or(0)
+                    + children.iter().map(|child| child.product_count).sum::<u32>();
+                CategoryNode { category, product_count, children }
+            })
+            .collect()
+    }
+
+    let known: Vec<Uuid> = categories.iter().map(|c| c.id).collect();
+    let mut by_parent: HashMap<Option<Uuid>, Vec<ProductCategory>> = HashMap::new();
+    for category in categories {
+        let parent = category.parent_id.filter(|id| known.contains(id));
+        by_parent.entry(parent).or_default().push(category);
+    }
+
+    attach(None, &mut by_parent, counts)
+}
+
+/// Find a category by slug, returning it with its ancestors from the top down
+pub fn find_in_tree<'a>(tree: &'a [CategoryNode], slug: &str) -> Option<(&'a CategoryNode, Vec<ProductCategory>)> {
+    for node in tree {
+        if node.category.slug == slug {
+            return Some((node, Vec::new()));
+        }
+        if let Some((found, mut ancestors)) = find_in_tree(&node.children, slug) {
+            ancestors.insert(0, node.category.clone());
+            return Some((found, ancestors));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn category(name: &str, parent: Option<&ProductCategory>) -> ProductCategory {
+        ProductCategory {
+            id: Uuid::new_v4(),
+            parent_id: parent.map(|p| p.id),
+            name: name.to_string(),
+            slug: slugify(name),
+            description: String::new(),
+            sort_order: 0,
+            image_url: None,
+        }
+    }
+
+    #[test]
+    fn slugify_collapses_punctuation() {
+        assert_eq!(slugify("Home & Garden"), "home-garden");
+        assert_eq!(slugify("  USB-C / Thunderbolt  "), "usb-c-thunderbolt");
+    }
+
+    #[test]
+    fn tree_rolls_up_counts_and_finds_paths() {
+        let electronics = category("Electronics", None);
+        let audio = category("Audio", Some(&electronics));
+        let headphones = category("Headphones", Some(&audio));
+        let office = category("Office", None);
+        let counts = HashMap::from([(electronics.id, 1), (headphones.id, 3), (office.id, 2)]);
+
+        let tree = build_tree(vec![electronics.clone(), audio.clone(), headphones, office], &counts);
+
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].product_count, 4);
+        assert_eq!(tree[0].children[0].product_count, 3);
+        assert_eq!(tree[1].product_count, 2);
+
+        let (node, ancestors) = find_in_tree(&tree, "headphones").unwrap();
+        assert_eq!(node.category.name, "Headphones");
+        assert_eq!(ancestors, vec![electronics, audio]);
+        assert!(find_in_tree(&tree, "garden").is_none());
+    }
+}
@@ -0,0 +1,503 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{Sqlite, SqliteConnection, SqlitePool, Transaction};
+use uuid::Uuid;
+use shared::{
+    AppliedCoupon, CartItemWithProduct, Coupon, CouponError, CouponKind, CreateCouponRequest, Currency, DiscountLine,
+    Money, UpdateCouponRequest,
+};
+
+/// (id, code, description, kind, percent_off, amount_off_cents, min_order_cents, currency, restrictions,
+/// max_uses, max_uses_per_user, times_used, starts_at, ends_at, is_active, created_at)
+type CouponRow = (
+    String, String, String, String, Option<i32>, Option<i64>, Option<i64>, String, String,
+    Option<i32>, Option<i32>, i32, Option<String>, Option<String>, i32, String,
+);
+
+/// Restrictions are selected as one JSON array `[product_ids, category_ids]`
+/// to stay within the 16 columns a row tuple can hold
+const COUPON_COLUMNS: &str = "id, code, description, kind, percent_off, amount_off_cents, min_order_cents, currency, \
+    json_array(json(product_ids), json(category_ids)), max_uses, max_uses_per_user, times_used, starts_at, ends_at, \
+    is_active, created_at";
+
+/// A cart's coupon with what is needed to price it for that customer
+pub struct CartCoupon {
+    pub coupon: Coupon,
+    /// The coupon's categories and all their subcategories
+    pub categories: Vec<Uuid>,
+    /// Orders this customer has already placed with the coupon
+    pub uses_by_user: i64,
+}
+
+impl CartCoupon {
+    pub fn apply(&self, items: &[CartItemWithProduct], shipping: Money) -> Result<AppliedCoupon, CouponError> {
+        self.coupon.apply(items, shipping, &self.categories, self.uses_by_user, Utc::now())
+    }
+}
+
+pub struct CouponRepository;
+
+impl CouponRepository {
+    /// Insert a coupon checked by the caller; fails on a duplicate code
+    pub async fn create(pool: &SqlitePool, req: &CreateCouponRequest) -> Result<Coupon> {
+        let id = Uuid::new_v4();
+        let now = Utc::now().to_rfc3339();
+        let currency = req.amount_off.or(req.min_order_total).map(|m| m.currency).unwrap_or_default();
+
+        sqlx::query(
+            r#"
+            INSERT INTO coupons (id, code, description, kind, percent_off, amount_off_cents, min_order_cents, currency,
+                                 product_ids, category_ids, max_uses, max_uses_per_user, starts_at, ends_at,
+                                 is_active, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 1, ?, ?)
+            "#,
+        )
+        .bind(id.to_string())
+        .bind(req.code.trim().to_uppercase())
+        .bind(req.description.trim())
+        .bind(req.kind.as_str())
+        .bind(req.percent_off)
+        .bind(req.amount_off.map(|m| m.amount))
+        .bind(req.min_order_total.map(|m| m.amount))
+        .bind(currency.code())
+        .bind(serde_json::to_string(&req.product_ids)?)
+        .bind(serde_json::to_string(&req.category_ids)?)
+        .bind(req.max_uses)
+        .bind(req.max_uses_per_user)
+        .bind(req.starts_at.map(|at| at.to_rfc3339()))
+        .bind(req.ends_at.map(|at| at.to_rfc3339()))
+        .bind(&now)
+        .bind(&now)
+        .execute(pool)
+        .await?;
+
+        Self::get(pool, id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Coupon {} vanished after insert", id))
+    }
+
+    /// All coupons, newest first
+    pub async fn list(pool: &SqlitePool) -> Result<Vec<Coupon>> {
+        let rows: Vec<CouponRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM coupons ORDER BY created_at DESC",
+            COUPON_COLUMNS
+        ))
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_coupon).collect()
+    }
+
+    pub async fn get(pool: &SqlitePool, id: Uuid) -> Result<Option<Coupon>> {
+        let row: Option<CouponRow> = sqlx::query_as(&format!("SELECT {} FROM coupons WHERE id = ?", COUPON_COLUMNS))
+            .bind(id.to_string())
+            .fetch_optional(pool)
+            .await?;
+
+        row.map(Self::row_to_coupon).transpose()
+    }
+
+    /// Look a coupon up by code, ignoring case
+    pub async fn find_by_code(pool: &SqlitePool, code: &str) -> Result<Option<Coupon>> {
+        let row: Option<CouponRow> = sqlx::query_as(&format!("SELECT {} FROM coupons WHERE code = ?", COUPON_COLUMNS))
+            .bind(code.trim())
+            .fetch_optional(pool)
+            .await?;
+
+        row.map(Self::row_to_coupon).transpose()
+    }
+
+    /// The coupon as `req` would leave it, so the result can be validated
+    /// before it is stored
+    pub fn merge_update(mut coupon: Coupon, req: &UpdateCouponRequest) -> Coupon {
+        if let Some(description) = &req.description {
+            coupon.description = description.trim().to_string();
+        }
+        if let Some(is_active) = req.is_active {
+            coupon.is_active = is_active;
+        }
+        if req.clear_limits {
+            coupon.max_uses = None;
+            coupon.max_uses_per_user = None;
+            coupon.ends_at = None;
+        }
+        if let Some(max_uses) = req.max_uses {
+            coupon.max_uses = Some(max_uses);
+        }
+        if let Some(max_uses_per_user) = req.max_uses_per_user {
+            coupon.max_uses_per_user = Some(max_uses_per_user);
+        }
+        if let Some(starts_at) = req.starts_at {
+            coupon.starts_at = Some(starts_at);
+        }
+        if let Some(ends_at) = req.ends_at {
+            coupon.ends_at = Some(ends_at);
+        }
+        coupon
+    }
+
+    /// Apply a partial update; fields left as `None` keep their current value
+    pub async fn update(pool: &SqlitePool, id: Uuid, req: &UpdateCouponRequest) -> Result<Option<Coupon>> {
+        let coupon = match Self::get(pool, id).await? {
+            Some(coupon) => Self::merge_update(coupon, req),
+            None => return Ok(None),
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE coupons SET description = ?, is_active = ?, max_uses = ?, max_uses_per_user = ?,
+                               starts_at = ?, ends_at = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&coupon.description)
+        .bind(coupon.is_active as i32)
+        .bind(coupon.max_uses)
+        .bind(coupon.max_uses_per_user)
+        .bind(coupon.starts_at.map(|at| at.to_rfc3339()))
+        .bind(coupon.ends_at.map(|at| at.to_rfc3339()))
+        .bind(Utc::now().to_rfc3339())
+        .bind(id.to_string())
+        .execute(pool)
+        .await?;
+
+        Ok(Some(coupon))
+    }
+
+    /// Price `coupon` for a customer who wants to apply it
+    pub async fn for_user(pool: &SqlitePool, coupon: Coupon, user_id: Uuid) -> Result<CartCoupon> {
+        let mut conn = pool.acquire().await?;
+        Self::with_usage(&mut conn, coupon, user_id).await
+    }
+
+    /// The coupon applied to a customer's cart, if any
+    pub async fn for_cart(pool: &SqlitePool, user_id: Uuid) -> Result<Option<CartCoupon>> {
+        let mut conn = pool.acquire().await?;
+        Self::for_cart_on(&mut conn, user_id).await
+    }
+
+    /// `for_cart` on a connection or open transaction
+    pub(super) async fn for_cart_on(conn: &mut SqliteConnection, user_id: Uuid) -> Result<Option<CartCoupon>> {
+        let row: Option<CouponRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM coupons WHERE id = (SELECT coupon_id FROM cart_coupons WHERE user_id = ?)",
+            COUPON_COLUMNS
+        ))
+        .bind(user_id.to_string())
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(Self::with_usage(conn, Self::row_to_coupon(row)?, user_id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn with_usage(conn: &mut SqliteConnection, coupon: Coupon, user_id: Uuid) -> Result<CartCoupon> {
+        let categories: Vec<(String,)> = sqlx::query_as(
+            r#"
+            WITH RECURSIVE subtree(id) AS (
+                SELECT value FROM json_each(?)
+                UNION SELECT c.id FROM categories c JOIN subtree s ON c.parent_id = s.id
+            )
+            SELECT id FROM subtree
+            "#,
+        )
+        .bind(serde_json::to_string(&coupon.category_ids)?)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let (uses_by_user,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM coupon_redemptions WHERE coupon_id = ? AND user_id = ?",
+        )
+        .bind(coupon.id.to_string())
+        .bind(user_id.to_string())
+        .fetch_one(&mut *conn)
+        .await?;
+
+        Ok(CartCoupon {
+            categories: categories.into_iter().map(|(id,)| id.parse()).collect::<Result<_, _>>()?,
+            coupon,
+            uses_by_user,
+        })
+    }
+
+    /// Apply a coupon to a customer's cart, replacing any other
+    pub async fn add_to_cart(pool: &SqlitePool, user_id: Uuid, coupon_id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO cart_coupons (user_id, coupon_id, applied_at) VALUES (?, ?, ?)
+            ON CONFLICT(user_id) DO UPDATE SET coupon_id = excluded.coupon_id, applied_at = excluded.applied_at
+            "#,
+        )
+        .bind(user_id.to_string())
+        .bind(coupon_id.to_string())
+        .bind(Utc::now().to_rfc3339())
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_from_cart(pool: &SqlitePool, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM cart_coupons WHERE user_id = ?")
+            .bind(user_id.to_string())
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Use up one redemption of the coupon for a new order and record its
+    /// discount line. The usage limits are enforced by the writes themselves,
+    /// so concurrent checkouts can't redeem a coupon more often than allowed.
+    pub(super) async fn redeem_in_tx(
+        tx: &mut Transaction<'_, Sqlite>,
+        coupon: &Coupon,
+        user_id: Uuid,
+        order_id: Uuid,
+        line: &DiscountLine,
+    ) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+
+        let result = sqlx::query(
+            r#"
+            UPDATE coupons SET times_used = times_used + 1, updated_at = ?
+            WHERE id = ? AND is_active = 1 AND (max_uses IS NULL OR times_used < max_uses)
+            "#,
+        )
+        .bind(&now)
+        .bind(coupon.id.to_string())
+        .execute(&mut **tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            anyhow::bail!("Coupon {} cannot be used: {}", coupon.code, CouponError::UsedUp);
+        }
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO coupon_redemptions (id, coupon_id, user_id, order_id, discount_cents, created_at)
+            SELECT ?, ?, ?, ?, ?, ?
+            WHERE ? IS NULL OR (SELECT COUNT(*) FROM coupon_redemptions WHERE coupon_id = ? AND user_id = ?) < ?
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(coupon.id.to_string())
+        .bind(user_id.to_string())
+        .bind(order_id.to_string())
+        .bind(line.amount.amount)
+        .bind(&now)
+        .bind(coupon.max_uses_per_user)
+        .bind(coupon.id.to_string())
+        .bind(user_id.to_string())
+        .bind(coupon.max_uses_per_user)
+        .execute(&mut **tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            anyhow::bail!("Coupon {} cannot be used: {}", coupon.code, CouponError::AlreadyUsed);
+        }
+
+        sqlx::query(
+            "INSERT INTO order_discounts (id, order_id, coupon_id, code, description, amount_cents) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(order_id.to_string())
+        .bind(coupon.id.to_string())
+        .bind(&line.code)
+        .bind(&line.description)
+        .bind(line.amount.amount)
+        .execute(&mut **tx)
+        .await?;
+
+        sqlx::query("DELETE FROM cart_coupons WHERE user_id = ?")
+            .bind(user_id.to_string())
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Take the use back for an order whose redemption was given up when its
+    /// stock holds expired unpaid. Before a charge `enforce_limits` fails it
+    /// like a new redemption if the coupon has since been used up; once the
+    /// order is paid the redemption is recorded regardless.
+    pub(super) async fn reclaim_in_tx(
+        tx: &mut Transaction<'_, Sqlite>,
+        order_id: Uuid,
+        enforce_limits: bool,
+    ) -> Result<()> {
+        let row: Option<(String, String, String, i64)> = sqlx::query_as(
+            r#"
+            SELECT d.coupon_id, d.code, o.user_id, d.amount_cents
+            FROM order_discounts d
+            JOIN orders o ON o.id = d.order_id
+            JOIN coupons c ON c.id = d.coupon_id
+            WHERE d.order_id = ?
+              AND NOT EXISTS (SELECT 1 FROM coupon_redemptions r WHERE r.order_id = d.order_id)
+            "#,
+        )
+        .bind(order_id.to_string())
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        let Some((coupon_id, code, user_id, discount_cents)) = row else {
+            return Ok(());
+        };
+        let now = Utc::now().to_rfc3339();
+
+        let result = sqlx::query(
+            "UPDATE coupons SET times_used = times_used + 1, updated_at = ? WHERE id = ? AND (max_uses IS NULL OR times_used < max_uses)",
+        )
+        .bind(&now)
+        .bind(&coupon_id)
+        .execute(&mut **tx)
+        .await?;
+
+        if enforce_limits && result.rows_affected() == 0 {
+            anyhow::bail!("Coupon {} cannot be used: {}", code, CouponError::UsedUp);
+        }
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO coupon_redemptions (id, coupon_id, user_id, order_id, discount_cents, created_at)
+            SELECT ?1, ?2, ?3, ?4, ?5, ?6
+            WHERE NOT ?7 OR (
+                SELECT max_uses_per_user IS NULL
+                    OR (SELECT COUNT(*) FROM coupon_redemptions WHERE coupon_id = ?2 AND user_id = ?3) < max_uses_per_user
+                FROM coupons WHERE id = ?2
+            )
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&coupon_id)
+        .bind(&user_id)
+        .bind(order_id.to_string())
+        .bind(discount_cents)
+        .bind(&now)
+        .bind(enforce_limits)
+        .execute(&mut **tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            anyhow::bail!("Coupon {} cannot be used: {}", code, CouponError::AlreadyUsed);
+        }
+
+        Ok(())
+    }
+
+    /// Give a cancelled order's redemption back to the coupon, or that of an
+    /// unpaid order whose stock holds expired. The order keeps its discount
+    /// lines.
+    pub(super) async fn release_in_tx(tx: &mut Transaction<'_, Sqlite>, order_id: Uuid) -> Result<()> {
+        let redemption: Option<(String,)> = sqlx::query_as("SELECT coupon_id FROM coupon_redemptions WHERE order_id = ?")
+            .bind(order_id.to_string())
+            .fetch_optional(&mut **tx)
+            .await?;
+
+        if let Some((coupon_id,)) = redemption {
+            sqlx::query("DELETE FROM coupon_redemptions WHERE order_id = ?")
+                .bind(order_id.to_string())
+                .execute(&mut **tx)
+                .await?;
+            sqlx::query("UPDATE coupons SET times_used = MAX(times_used - 1, 0), updated_at = ? WHERE id = ?")
+                .bind(Utc::now().to_rfc3339())
+                .bind(coupon_id)
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Discount lines recorded on an order
+    pub async fn order_discounts(pool: &SqlitePool, order_id: Uuid, currency: Currency) -> Result<Vec<DiscountLine>> {
+        let rows: Vec<(String, String, i64)> = sqlx::query_as(
+            "SELECT code, description, amount_cents FROM order_discounts WHERE order_id = ? ORDER BY rowid",
+        )
+        .bind(order_id.to_string())
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(code, description, amount_cents)| DiscountLine {
+                code,
+                description,
+                amount: Money::new(amount_cents, currency),
+            })
+            .collect())
+    }
+
+    fn row_to_coupon(row: CouponRow) -> Result<Coupon> {
+        let (
+            id, code, description, kind, percent_off, amount_off_cents, min_order_cents, currency, restrictions,
+            max_uses, max_uses_per_user, times_used, starts_at, ends_at, is_active, created_at,
+        ) = row;
+        let (product_ids, category_ids): (Vec<Uuid>, Vec<Uuid>) = serde_json::from_str(&restrictions)?;
+        let currency = Currency::new(&currency)
+            .ok_or_else(|| anyhow::anyhow!("Invalid currency code: {}", currency))?;
+        let parse_time = |at: String| DateTime::parse_from_rfc3339(&at).map(|at| at.with_timezone(&Utc));
+
+        Ok(Coupon {
+            id: id.parse()?,
+            code,
+            description,
+            kind: CouponKind::parse(&kind).ok_or_else(|| anyhow::anyhow!("Invalid coupon kind: {}", kind))?,
+            percent_off,
+            amount_off: amount_off_cents.map(|cents| Money::new(cents, currency)),
+            min_order_total: min_order_cents.map(|cents| Money::new(cents, currency)),
+            product_ids,
+            category_ids,
+            max_uses,
+            max_uses_per_user,
+            times_used,
+            starts_at: starts_at.map(parse_time).transpose()?,
+            ends_at: ends_at.map(parse_time).transpose()?,
+            is_active: is_active == 1,
+            created_at: parse_time(created_at)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_update_keeps_unchanged_fields() {
+        let starts_at = Utc::now();
+        let coupon = Coupon {
+            id: Uuid::new_v4(),
+            code: "SAVE".to_string(),
+            description: "Spring sale".to_string(),
+            kind: CouponKind::Percentage,
+            percent_off: Some(10),
+            amount_off: None,
+            min_order_total: None,
+            product_ids: Vec::new(),
+            category_ids: Vec::new(),
+            max_uses: Some(100),
+            max_uses_per_user: Some(1),
+            times_used: 5,
+            starts_at: Some(starts_at),
+            ends_at: Some(starts_at + chrono::Duration::days(7)),
+            is_active: true,
+            created_at: starts_at,
+        };
+
+        let req = UpdateCouponRequest { max_uses_per_user: Some(2), ..Default::default() };
+        let merged = CouponRepository::merge_update(coupon.clone(), &req);
+        assert_eq!(merged.max_uses, Some(100));
+        assert_eq!(merged.max_uses_per_user, Some(2));
+        assert_eq!(merged.ends_at, coupon.ends_at);
+
+        // Clearing limits drops them unless the request sets new ones
+        let req = UpdateCouponRequest { clear_limits: true, max_uses: Some(50), ..Default::default() };
+        let merged = CouponRepository::merge_update(coupon.clone(), &req);
+        assert_eq!(merged.max_uses, Some(50));
+        assert_eq!(merged.max_uses_per_user, None);
+        assert_eq!(merged.ends_at, None);
+        assert_eq!(merged.starts_at, Some(starts_at));
+    }
+}
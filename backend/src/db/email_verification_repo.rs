@@ -0,0 +1,79 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+use crate::auth::hash_token;
+
+pub struct EmailVerificationRepository;
+
+impl EmailVerificationRepository {
+    /// Store the hash of a new verification token, invalidating earlier ones
+    pub async fn create(pool: &SqlitePool, user_id: Uuid, token: &str, ttl: Duration) -> Result<()> {
+        let now = Utc::now();
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("UPDATE email_verification_tokens SET used_at = ? WHERE user_id = ? AND used_at IS NULL")
+            .bind(now.to_rfc3339())
+            .bind(user_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO email_verification_tokens (id, user_id, token_hash, created_at, expires_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(user_id.to_string())
+        .bind(hash_token(token))
+        .bind(now.to_rfc3339())
+        .bind((now + ttl).to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Spend a verification token and mark the user's email verified.
+    /// Returns the user id, or None if the token is unknown, expired or used.
+    pub async fn consume(pool: &SqlitePool, token: &str) -> Result<Option<Uuid>> {
+        let now = Utc::now();
+        let mut tx = pool.begin().await?;
+
+        let row: Option<(String, String, String)> = sqlx::query_as(
+            "SELECT id, user_id, expires_at FROM email_verification_tokens WHERE token_hash = ? AND used_at IS NULL",
+        )
+        .bind(hash_token(token))
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some((id, user_id, expires_at)) = row else {
+            return Ok(None);
+        };
+
+        if DateTime::parse_from_rfc3339(&expires_at)?.with_timezone(&Utc) <= now {
+            return Ok(None);
+        }
+
+        let result = sqlx::query("UPDATE email_verification_tokens SET used_at = ? WHERE id = ? AND used_at IS NULL")
+            .bind(now.to_rfc3339())
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        sqlx::query("UPDATE users SET email_verified_at = COALESCE(email_verified_at, ?) WHERE id = ?")
+            .bind(now.to_rfc3339())
+            .bind(&user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(Some(user_id.parse()?))
+    }
+}
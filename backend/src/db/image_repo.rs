@@ -0,0 +1,254 @@
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use uuid::Uuid;
+use shared::{ProductImage, UpdateProductImageRequest};
+
+/// (id, product_id, url, thumbnail_url, alt_text, position, is_primary, width, height)
+type ImageRow = (String, String, String, String, String, i32, i32, Option<i64>, Option<i64>);
+
+const IMAGE_COLUMNS: &str = "id, product_id, url, thumbnail_url, alt_text, position, is_primary, width, height";
+
+/// An uploaded image and its thumbnail, already written to the blob store
+pub struct NewProductImage {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub url: String,
+    pub thumbnail_url: String,
+    pub storage_key: String,
+    pub thumbnail_key: String,
+    pub alt_text: String,
+    pub width: u32,
+    pub height: u32,
+    pub make_primary: bool,
+}
+
+pub struct ImageRepository;
+
+impl ImageRepository {
+    /// A product's gallery in display order
+    pub async fn list(pool: &SqlitePool, product_id: Uuid) -> Result<Vec<ProductImage>> {
+        let rows: Vec<ImageRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM product_images WHERE product_id = ? ORDER BY position, created_at",
+            IMAGE_COLUMNS
+        ))
+        .bind(product_id.to_string())
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_image).collect()
+    }
+
+    pub async fn get_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<ProductImage>> {
+        let row: Option<ImageRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM product_images WHERE id = ?",
+            IMAGE_COLUMNS
+        ))
+        .bind(id.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(Self::row_to_image).transpose()
+    }
+
+    /// Append an image to the end of the gallery. The first image of a
+    /// product becomes its primary image.
+    pub async fn create(pool: &SqlitePool, image: &NewProductImage) -> Result<ProductImage> {
+        let mut tx = pool.begin().await?;
+
+        let (count, next_position): (i64, i32) = sqlx::query_as(
+            "SELECT COUNT(*), COALESCE(MAX(position) + 1, 0) FROM product_images WHERE product_id = ?",
+        )
+        .bind(image.product_id.to_string())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let is_primary = image.make_primary || count == 0;
+        if is_primary {
+            Self::clear_primary_in_tx(&mut tx, image.product_id).await?;
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO product_images (id, product_id, url, thumbnail_url, storage_key, thumbnail_key, alt_text,
+                                        position, is_primary, width, height, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(image.id.to_string())
+        .bind(image.product_id.to_string())
+        .bind(&image.url)
+        .bind(&image.thumbnail_url)
+        .bind(&image.storage_key)
+        .bind(&image.thumbnail_key)
+        .bind(&image.alt_text)
+        .bind(next_position)
+        .bind(is_primary as i32)
+        .bind(image.width as i64)
+        .bind(image.height as i64)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+        Self::sync_product_in_tx(&mut tx, image.product_id).await?;
+        tx.commit().await?;
+
+        Ok(ProductImage {
+            id: image.id,
+            product_id: image.product_id,
+            url: image.url.clone(),
+            thumbnail_url: image.thumbnail_url.clone(),
+            alt_text: image.alt_text.clone(),
+            position: next_position,
+            is_primary,
+            width: Some(image.width),
+            height: Some(image.height),
+        })
+    }
+
+    /// Change alt text, move the image within the gallery or make it primary
+    pub async fn update(pool: &SqlitePool, id: Uuid, req: &UpdateProductImageRequest) -> Result<Option<ProductImage>> {
+        let image = match Self::get_by_id(pool, id).await? {
+            Some(image) => image,
+            None => return Ok(None),
+        };
+
+        let mut tx = pool.begin().await?;
+
+        if let Some(alt_text) = &req.alt_text {
+            sqlx::query("UPDATE product_images SET alt_text = ? WHERE id = ?")
+                .bind(alt_text.trim())
+                .bind(id.to_string())
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        if let Some(position) = req.position {
+            let mut ids = Self::ordered_ids_in_tx(&mut tx, image.product_id).await?;
+            ids.retain(|other| *other != id.to_string());
+            ids.insert((position.max(0) as usize).min(ids.len()), id.to_string());
+            Self::renumber_in_tx(&mut tx, &ids).await?;
+        }
+
+        if req.make_primary {
+            Self::clear_primary_in_tx(&mut tx, image.product_id).await?;
+            sqlx::query("UPDATE product_images SET is_primary = 1 WHERE id = ?")
+                .bind(id.to_string())
+                .execute(&mut *tx)
+                .await?;
+            Self::sync_product_in_tx(&mut tx, image.product_id).await?;
+        }
+
+        tx.commit().await?;
+        Self::get_by_id(pool, id).await
+    }
+
+    /// Remove an image, closing the gap it leaves and promoting the next image
+    /// if it was the primary one. Returns the blob keys to delete.
+    pub async fn delete(pool: &SqlitePool, id: Uuid) -> Result<Option<Vec<String>>> {
+        let mut tx = pool.begin().await?;
+
+        let row: Option<(String, Option<String>, Option<String>)> = sqlx::query_as(
+            "SELECT product_id, storage_key, thumbnail_key FROM product_images WHERE id = ?",
+        )
+        .bind(id.to_string())
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some((product_id, storage_key, thumbnail_key)) = row else {
+            return Ok(None);
+        };
+        let product_id: Uuid = product_id.parse()?;
+
+        sqlx::query("DELETE FROM product_images WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        let ids = Self::ordered_ids_in_tx(&mut tx, product_id).await?;
+        Self::renumber_in_tx(&mut tx, &ids).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE product_images SET is_primary = 1
+            WHERE id = ? AND NOT EXISTS (SELECT 1 FROM product_images WHERE product_id = ? AND is_primary = 1)
+            "#,
+        )
+        .bind(ids.first())
+        .bind(product_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        Self::sync_product_in_tx(&mut tx, product_id).await?;
+        tx.commit().await?;
+
+        Ok(Some(storage_key.into_iter().chain(thumbnail_key).collect()))
+    }
+
+    async fn ordered_ids_in_tx(tx: &mut Transaction<'_, Sqlite>, product_id: Uuid) -> Result<Vec<String>> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            "SELECT id FROM product_images WHERE product_id = ? ORDER BY position, created_at",
+        )
+        .bind(product_id.to_string())
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Ok(rows.into_iter().map(|(id,)| id).collect())
+    }
+
+    async fn renumber_in_tx(tx: &mut Transaction<'_, Sqlite>, ids: &[String]) -> Result<()> {
+        for (position, id) in ids.iter().enumerate() {
+            sqlx::query("UPDATE product_images SET position = ? WHERE id = ?")
+                .bind(position as i32)
+                .bind(id)
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn clear_primary_in_tx(tx: &mut Transaction<'_, Sqlite>, product_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE product_images SET is_primary = 0 WHERE product_id = ? AND is_primary = 1")
+            .bind(product_id.to_string())
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Mirror the primary image onto the product for listings
+    async fn sync_product_in_tx(tx: &mut Transaction<'_, Sqlite>, product_id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE products SET
+                image_url = (SELECT url FROM product_images WHERE product_id = products.id AND is_primary = 1),
+                thumbnail_url = (SELECT thumbnail_url FROM product_images WHERE product_id = products.id AND is_primary = 1),
+                updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(Utc::now().to_rfc3339())
+        .bind(product_id.to_string())
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    fn row_to_image(row: ImageRow) -> Result<ProductImage> {
+        let (id, product_id, url, thumbnail_url, alt_text, position, is_primary, width, height) = row;
+
+        Ok(ProductImage {
+            id: id.parse()?,
+            product_id: product_id.parse()?,
+            url,
+            thumbnail_url,
+            alt_text,
+            position,
+            is_primary: is_primary == 1,
+            width: width.map(|w| w as u32),
+            height: height.map(|h| h as u32),
+        })
+    }
+}
@@ -0,0 +1,343 @@
+//! Versioned schema migrations
+//!
+//! Migrations are SQL files embedded in the binary and applied in version
+//! order. Every applied version is recorded in `schema_migrations` together
+//! with a checksum of its SQL, so an edited or unknown migration stops the
+//! server instead of silently diverging from the database.
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use sqlx::{SqliteConnection, SqlitePool};
+
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+impl Migration {
+    pub fn checksum(&self) -> String {
+        hex::encode(Sha256::digest(self.sql.as_bytes()))
+    }
+}
+
+/// All migrations, oldest first. Never edit one that has shipped; add a new one.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        sql: include_str!("migrations/0001_initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "payments",
+        sql: include_str!("migrations/0002_payments.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "webhook_events",
+        sql: include_str!("migrations/0003_webhook_events.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "returns",
+        sql: include_str!("migrations/0004_returns.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "sessions",
+        sql: include_str!("migrations/0005_sessions.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "password_resets",
+        sql: include_str!("migrations/0006_password_resets.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "email_verification",
+        sql: include_str!("migrations/0007_email_verification.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "two_factor",
+        sql: include_str!("migrations/0008_two_factor.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "rate_limits",
+        sql: include_str!("migrations/0009_rate_limits.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "product_search",
+        sql: include_str!("migrations/0010_product_search.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "product_variants",
+        sql: include_str!("migrations/0011_product_variants.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "categories",
+        sql: include_str!("migrations/0012_categories.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "product_images",
+        sql: include_str!("migrations/0013_product_images.sql"),
+    },
+    Migration {
+        version: 14,
+        name: "inventory_reservations",
+        sql: include_str!("migrations/0014_inventory_reservations.sql"),
+    },
+    Migration {
+        version: 15,
+        name: "stock_movements",
+        sql: include_str!("migrations/0015_stock_movements.sql"),
+    },
+    Migration {
+        version: 16,
+        name: "stock_alerts",
+        sql: include_str!("migrations/0016_stock_alerts.sql"),
+    },
+    Migration {
+        version: 17,
+        name: "coupons",
+        sql: include_str!("migrations/0017_coupons.sql"),
+    },
+    Migration {
+        version: 18,
+        name: "return_completion",
+        sql: include_str!("migrations/0018_return_completion.sql"),
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the embedded SQL no longer matches the recorded checksum
+    Modified,
+    /// Recorded in the database but not known to this binary
+    Unknown,
+}
+
+impl MigrationState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::Modified => "MODIFIED",
+            MigrationState::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: String,
+    pub state: MigrationState,
+    pub applied_at: Option<String>,
+}
+
+/// (version, name, checksum, applied_at)
+type AppliedRow = (i64, String, String, String);
+
+/// Compare embedded migrations with those recorded in the database
+pub async fn status(pool: &SqlitePool) -> Result<Vec<MigrationStatus>> {
+    let applied: Vec<AppliedRow> = if table_exists(pool, "schema_migrations").await? {
+        sqlx::query_as("SELECT version, name, checksum, applied_at FROM schema_migrations ORDER BY version")
+            .fetch_all(pool)
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    let mut statuses: Vec<MigrationStatus> = MIGRATIONS
+        .iter()
+        .map(|m| {
+            let recorded = applied.iter().find(|(version, ..)| *version == m.version);
+            let state = match recorded {
+                None => MigrationState::Pending,
+                Some((_, _, checksum, _)) if *checksum != m.checksum() => MigrationState::Modified,
+                Some(_) => MigrationState::Applied,
+            };
+
+            MigrationStatus {
+                version: m.version,
+                name: m.name.to_string(),
+                state,
+                applied_at: recorded.map(|(.., applied_at)| applied_at.clone()),
+            }
+        })
+        .collect();
+
+    for (version, name, _, applied_at) in applied {
+        if !MIGRATIONS.iter().any(|m| m.version == version) {
+            statuses.push(MigrationStatus {
+                version,
+                name,
+                state: MigrationState::Unknown,
+                applied_at: Some(applied_at),
+            });
+        }
+    }
+
+    statuses.sort_by_key(|s| s.version);
+    Ok(statuses)
+}
+
+/// Apply all pending migrations in order, each in its own transaction.
+/// Fails without changing anything if an applied migration has drifted.
+pub async fn run(pool: &SqlitePool) -> Result<Vec<i64>> {
+    let statuses = status(pool).await?;
+
+    for s in &statuses {
+        match s.state {
+            MigrationState::Modified => bail!(
+                "Migration {:04} ({}) has been modified since it was applied; refusing to continue",
+                s.version,
+                s.name
+            ),
+            MigrationState::Unknown => bail!(
+                "Database has migration {:04} ({}) which this build does not know; refusing to continue",
+                s.version,
+                s.name
+            ),
+            MigrationState::Applied | MigrationState::Pending => {}
+        }
+    }
+
+    // Databases created before versioned migrations have tables but no history
+    let unversioned = !table_exists(pool, "schema_migrations").await?
+        && table_exists(pool, "users").await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            checksum TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+
+    let mut applied = Vec::new();
+
+    for migration in MIGRATIONS {
+        let pending = statuses
+            .iter()
+            .any(|s| s.version == migration.version && s.state == MigrationState::Pending);
+        if !pending {
+            continue;
+        }
+
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(migration.sql).execute(&mut *tx).await?;
+
+        if unversioned && migration.version == 1 {
+            upgrade_unversioned_schema(&mut tx).await?;
+        }
+
+        sqlx::query("INSERT INTO schema_migrations (version, name, checksum, applied_at) VALUES (?, ?, ?, ?)")
+            .bind(migration.version)
+            .bind(migration.name)
+            .bind(migration.checksum())
+            .bind(Utc::now().to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        tracing::info!("Applied migration {:04} ({})", migration.version, migration.name);
+        applied.push(migration.version);
+    }
+
+    Ok(applied)
+}
+
+async fn table_exists(pool: &SqlitePool, table: &str) -> Result<bool> {
+    let row: Option<(String,)> = sqlx::query_as("SELECT name FROM sqlite_master WHERE type = 'table' AND name = ?")
+        .bind(table)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row.is_some())
+}
+
+/// Bring a database created by the old ad-hoc `CREATE TABLE IF NOT EXISTS`
+/// setup up to the initial schema. The tables already exist, so migration
+/// 0001 skipped them; only columns changed since then need patching.
+async fn upgrade_unversioned_schema(conn: &mut SqliteConnection) -> Result<()> {
+    add_column_if_missing(conn, "orders", "shipping_address", "TEXT").await?;
+
+    // Money used to be stored as REAL; convert to integer minor units
+    convert_real_to_cents(conn, "products", "price", "price_cents").await?;
+    convert_real_to_cents(conn, "orders", "total", "total_cents").await?;
+    convert_real_to_cents(conn, "order_items", "price", "price_cents").await?;
+    convert_real_to_cents(conn, "order_items", "subtotal", "subtotal_cents").await?;
+    add_column_if_missing(conn, "products", "currency", "TEXT NOT NULL DEFAULT 'USD'").await?;
+    add_column_if_missing(conn, "orders", "currency", "TEXT NOT NULL DEFAULT 'USD'").await?;
+
+    tracing::info!("Upgraded unversioned database schema");
+    Ok(())
+}
+
+async fn column_exists(conn: &mut SqliteConnection, table: &str, column: &str) -> Result<bool> {
+    let columns: Vec<(String,)> = sqlx::query_as(&format!("SELECT name FROM pragma_table_info('{}')", table))
+        .fetch_all(&mut *conn)
+        .await?;
+
+    Ok(columns.iter().any(|(name,)| name == column))
+}
+
+async fn add_column_if_missing(
+    conn: &mut SqliteConnection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    if !column_exists(conn, table, column).await? {
+        sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Replace a legacy REAL money column with an INTEGER cents column
+async fn convert_real_to_cents(
+    conn: &mut SqliteConnection,
+    table: &str,
+    real_column: &str,
+    cents_column: &str,
+) -> Result<()> {
+    if !column_exists(conn, table, real_column).await? {
+        return Ok(());
+    }
+
+    add_column_if_missing(conn, table, cents_column, "INTEGER NOT NULL DEFAULT 0").await?;
+
+    sqlx::query(&format!(
+        "UPDATE {} SET {} = CAST(ROUND({} * 100) AS INTEGER)",
+        table, cents_column, real_column
+    ))
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(&format!("ALTER TABLE {} DROP COLUMN {}", table, real_column))
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,14 @@
+-- Every webhook delivery, keyed by the provider's event id for deduplication
+CREATE TABLE webhook_events (
+    provider TEXT NOT NULL,
+    event_id TEXT NOT NULL,
+    event_type TEXT NOT NULL,
+    provider_payment_id TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    -- processed | ignored | failed; failed events are retried on redelivery
+    status TEXT NOT NULL,
+    detail TEXT,
+    received_at TEXT NOT NULL,
+    processed_at TEXT,
+    PRIMARY KEY (provider, event_id)
+);
@@ -0,0 +1,33 @@
+-- Running total of partial refunds against a payment
+ALTER TABLE payments ADD COLUMN refunded_cents INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE return_requests (
+    id TEXT PRIMARY KEY,
+    order_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'requested',
+    reason TEXT NOT NULL,
+    refund_cents INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    restocked INTEGER NOT NULL DEFAULT 0,
+    refund_id TEXT,
+    admin_note TEXT,
+    reviewed_by TEXT,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (order_id) REFERENCES orders(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE return_items (
+    return_id TEXT NOT NULL,
+    order_item_id TEXT NOT NULL,
+    quantity INTEGER NOT NULL,
+    refund_cents INTEGER NOT NULL,
+    PRIMARY KEY (return_id, order_item_id),
+    FOREIGN KEY (return_id) REFERENCES return_requests(id) ON DELETE CASCADE,
+    FOREIGN KEY (order_item_id) REFERENCES order_items(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_return_requests_order_id ON return_requests(order_id);
+CREATE INDEX idx_return_requests_status ON return_requests(status);
@@ -0,0 +1,17 @@
+-- One row per refresh token. Rotation inserts a new row in the same family
+-- and marks the old one rotated; presenting a rotated token revokes the family.
+CREATE TABLE sessions (
+    id TEXT PRIMARY KEY,
+    family_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    -- SHA-256 hex of the refresh token; the token itself is never stored
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    rotated_at TEXT,
+    revoked_at TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX idx_sessions_family ON sessions(family_id);
+CREATE INDEX idx_sessions_user ON sessions(user_id);
@@ -0,0 +1,17 @@
+ALTER TABLE users ADD COLUMN email_verified_at TEXT;
+
+-- Accounts created before verification existed are trusted as-is
+UPDATE users SET email_verified_at = created_at;
+
+-- Single-use email verification tokens; only the SHA-256 hash is stored
+CREATE TABLE email_verification_tokens (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    used_at TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX idx_email_verification_tokens_user ON email_verification_tokens(user_id);
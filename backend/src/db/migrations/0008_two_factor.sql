@@ -0,0 +1,20 @@
+-- TOTP shared secret (base32). Set at setup, active once totp_enabled_at is set.
+ALTER TABLE users ADD COLUMN totp_secret TEXT;
+ALTER TABLE users ADD COLUMN totp_enabled_at TEXT;
+-- Last accepted time step, so a code cannot be used twice
+ALTER TABLE users ADD COLUMN totp_last_step INTEGER;
+
+-- Whether the session was started with a second factor
+ALTER TABLE sessions ADD COLUMN mfa INTEGER NOT NULL DEFAULT 0;
+
+-- Single-use recovery codes; only the SHA-256 hash is stored
+CREATE TABLE recovery_codes (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    code_hash TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    used_at TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX idx_recovery_codes_user ON recovery_codes(user_id);
@@ -0,0 +1,11 @@
+-- Snapshot of in-memory rate limiter state, written periodically when
+-- RATE_LIMIT_PERSIST is enabled. Rows from a different algorithm are ignored.
+CREATE TABLE rate_limits (
+    policy TEXT NOT NULL,
+    key TEXT NOT NULL,
+    algorithm TEXT NOT NULL,
+    updated_ms INTEGER NOT NULL,
+    count REAL NOT NULL,
+    previous REAL NOT NULL,
+    PRIMARY KEY (policy, key)
+);
@@ -0,0 +1,27 @@
+-- Full-text index over product names and descriptions. Rows are keyed by
+-- product id rather than rowid, which SQLite may renumber on VACUUM.
+CREATE VIRTUAL TABLE products_fts USING fts5(
+    product_id UNINDEXED,
+    name,
+    description,
+    tokenize = 'unicode61 remove_diacritics 2',
+    prefix = '2 3'
+);
+
+INSERT INTO products_fts (product_id, name, description)
+SELECT id, name, description FROM products;
+
+CREATE TRIGGER products_fts_insert AFTER INSERT ON products BEGIN
+    INSERT INTO products_fts (product_id, name, description)
+    VALUES (new.id, new.name, new.description);
+END;
+
+CREATE TRIGGER products_fts_update AFTER UPDATE OF id, name, description ON products BEGIN
+    DELETE FROM products_fts WHERE product_id = old.id;
+    INSERT INTO products_fts (product_id, name, description)
+    VALUES (new.id, new.name, new.description);
+END;
+
+CREATE TRIGGER products_fts_delete AFTER DELETE ON products BEGIN
+    DELETE FROM products_fts WHERE product_id = old.id;
+END;
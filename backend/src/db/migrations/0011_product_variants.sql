@@ -0,0 +1,74 @@
+-- Options a customer chooses between, e.g. Size = ["S", "M", "L"]
+CREATE TABLE product_options (
+    id TEXT PRIMARY KEY,
+    product_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    -- JSON array of allowed values, in display order
+    option_values TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    UNIQUE (product_id, name),
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+);
+
+-- Purchasable SKUs. `options` is a JSON object of option name to value with
+-- sorted keys, so each combination is stored exactly once per product.
+CREATE TABLE product_variants (
+    id TEXT PRIMARY KEY,
+    product_id TEXT NOT NULL,
+    sku TEXT NOT NULL UNIQUE,
+    options TEXT NOT NULL,
+    -- Option values in option order, e.g. "M / Red"
+    title TEXT NOT NULL,
+    -- Overrides the product price when set
+    price_cents INTEGER,
+    stock INTEGER NOT NULL DEFAULT 0,
+    is_active INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    UNIQUE (product_id, options),
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_product_variants_product ON product_variants(product_id);
+
+-- A product with variants is in stock when any active variant is, so keep
+-- products.stock as the sum for listings and filters
+CREATE TRIGGER product_variants_stock_insert AFTER INSERT ON product_variants BEGIN
+    UPDATE products
+    SET stock = (SELECT COALESCE(SUM(stock), 0) FROM product_variants WHERE product_id = new.product_id AND is_active = 1)
+    WHERE id = new.product_id;
+END;
+
+CREATE TRIGGER product_variants_stock_update AFTER UPDATE OF stock, is_active ON product_variants BEGIN
+    UPDATE products
+    SET stock = (SELECT COALESCE(SUM(stock), 0) FROM product_variants WHERE product_id = new.product_id AND is_active = 1)
+    WHERE id = new.product_id;
+END;
+
+CREATE TRIGGER product_variants_stock_delete AFTER DELETE ON product_variants BEGIN
+    UPDATE products
+    SET stock = (SELECT COALESCE(SUM(stock), 0) FROM product_variants WHERE product_id = old.product_id AND is_active = 1)
+    WHERE id = old.product_id;
+END;
+
+-- The cart key gains the variant. Primary key columns cannot hold a usable
+-- NULL, so products without variants use the empty string.
+CREATE TABLE cart_items_new (
+    user_id TEXT NOT NULL,
+    product_id TEXT NOT NULL,
+    variant_id TEXT NOT NULL DEFAULT '',
+    quantity INTEGER NOT NULL DEFAULT 1,
+    PRIMARY KEY (user_id, product_id, variant_id),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+);
+
+INSERT INTO cart_items_new (user_id, product_id, quantity)
+SELECT user_id, product_id, quantity FROM cart_items;
+
+DROP TABLE cart_items;
+ALTER TABLE cart_items_new RENAME TO cart_items;
+
+-- Order lines remember which variant was bought and what it was called then
+ALTER TABLE order_items ADD COLUMN variant_id TEXT;
+ALTER TABLE order_items ADD COLUMN variant_title TEXT;
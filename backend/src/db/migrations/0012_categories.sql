@@ -0,0 +1,64 @@
+-- Categories form a tree through parent_id. Names are unique store-wide as
+-- they double as filter values and product labels.
+CREATE TABLE categories (
+    id TEXT PRIMARY KEY,
+    parent_id TEXT,
+    name TEXT NOT NULL UNIQUE,
+    slug TEXT NOT NULL UNIQUE,
+    description TEXT NOT NULL DEFAULT '',
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    image_url TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (parent_id) REFERENCES categories(id)
+);
+
+CREATE INDEX idx_categories_parent ON categories(parent_id);
+
+-- Every category name already used by a product becomes a top-level category.
+-- Spellings that differ only by case or surrounding spaces share a slug, so
+-- they are merged into one category named after the first of them.
+WITH names AS MATERIALIZED (
+    SELECT
+        MIN(trim(category)) AS name,
+        lower(replace(replace(replace(trim(category), ' & ', '-'), '/', '-'), ' ', '-')) AS slug,
+        lower(hex(randomblob(16))) AS h
+    FROM products
+    GROUP BY 2
+)
+INSERT INTO categories (id, name, slug, created_at, updated_at)
+SELECT
+    substr(h, 1, 8) || '-' || substr(h, 9, 4) || '-4' || substr(h, 14, 3) || '-8' || substr(h, 18, 3) || '-' || substr(h, 21, 12),
+    name,
+    slug,
+    strftime('%Y-%m-%dT%H:%M:%SZ', 'now'),
+    strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+FROM names;
+
+-- products.category keeps the category name for listings and filters;
+-- category_id is the link and follows the name on insert and update
+ALTER TABLE products ADD COLUMN category_id TEXT REFERENCES categories(id);
+
+UPDATE products SET category_id = (
+    SELECT id FROM categories
+    WHERE slug = lower(replace(replace(replace(trim(products.category), ' & ', '-'), '/', '-'), ' ', '-'))
+);
+
+-- Relabel merged spellings with their category's name
+UPDATE products SET category = (SELECT name FROM categories WHERE id = products.category_id)
+WHERE category <> (SELECT name FROM categories WHERE id = products.category_id);
+
+CREATE INDEX idx_products_category_id ON products(category_id);
+
+CREATE TRIGGER products_category_insert AFTER INSERT ON products BEGIN
+    UPDATE products SET category_id = (SELECT id FROM categories WHERE name = new.category) WHERE id = new.id;
+END;
+
+CREATE TRIGGER products_category_update AFTER UPDATE OF category ON products BEGIN
+    UPDATE products SET category_id = (SELECT id FROM categories WHERE name = new.category) WHERE id = new.id;
+END;
+
+-- Renaming a category relabels its products
+CREATE TRIGGER categories_rename AFTER UPDATE OF name ON categories BEGIN
+    UPDATE products SET category = new.name WHERE category_id = new.id;
+END;
@@ -0,0 +1,37 @@
+-- Ordered gallery images per product. Uploaded files are referenced by their
+-- blob store keys; images linked from elsewhere have no keys.
+CREATE TABLE product_images (
+    id TEXT PRIMARY KEY,
+    product_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    thumbnail_url TEXT NOT NULL,
+    storage_key TEXT,
+    thumbnail_key TEXT,
+    alt_text TEXT NOT NULL DEFAULT '',
+    position INTEGER NOT NULL,
+    is_primary INTEGER NOT NULL DEFAULT 0,
+    width INTEGER,
+    height INTEGER,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_product_images_product ON product_images(product_id, position);
+
+-- At most one primary image per product
+CREATE UNIQUE INDEX idx_product_images_primary ON product_images(product_id) WHERE is_primary = 1;
+
+-- The primary image is mirrored onto the product so listings need no join
+ALTER TABLE products ADD COLUMN thumbnail_url TEXT;
+
+-- Existing external image URLs become each product's primary image
+WITH linked AS MATERIALIZED (
+    SELECT id, name, image_url, lower(hex(randomblob(16))) AS h FROM products WHERE image_url IS NOT NULL AND image_url != ''
+)
+INSERT INTO product_images (id, product_id, url, thumbnail_url, alt_text, position, is_primary, created_at)
+SELECT
+    substr(h, 1, 8) || '-' || substr(h, 9, 4) || '-4' || substr(h, 14, 3) || '-8' || substr(h, 18, 3) || '-' || substr(h, 21, 12),
+    id, image_url, image_url, name, 0, 1, strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+FROM linked;
+
+UPDATE products SET thumbnail_url = image_url WHERE image_url IS NOT NULL AND image_url != '';
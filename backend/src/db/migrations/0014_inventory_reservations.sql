@@ -0,0 +1,40 @@
+-- Stock held for a pending order from checkout until payment. An active hold
+-- counts against available stock until it expires; paying the order converts
+-- it into a real decrement of on-hand stock.
+CREATE TABLE inventory_reservations (
+    id TEXT PRIMARY KEY,
+    order_id TEXT NOT NULL,
+    product_id TEXT NOT NULL,
+    variant_id TEXT,
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    status TEXT NOT NULL DEFAULT 'active' CHECK (status IN ('active', 'converted', 'released', 'expired')),
+    expires_at TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (order_id) REFERENCES orders(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id),
+    FOREIGN KEY (variant_id) REFERENCES product_variants(id)
+);
+
+CREATE INDEX idx_inventory_reservations_order ON inventory_reservations(order_id);
+CREATE INDEX idx_inventory_reservations_active ON inventory_reservations(product_id, variant_id) WHERE status = 'active';
+
+-- Holds that currently reduce available stock; ones past their expiry stop
+-- counting even before the sweeper marks them expired
+CREATE VIEW active_reservations AS
+SELECT * FROM inventory_reservations
+WHERE status = 'active' AND julianday(expires_at) > julianday('now');
+
+-- Pending orders placed before reservations already took their stock at
+-- checkout, so record them as converted
+WITH legacy AS MATERIALIZED (
+    SELECT i.order_id, i.product_id, i.variant_id, i.quantity, o.created_at, lower(hex(randomblob(16))) AS h
+    FROM order_items i
+    JOIN orders o ON o.id = i.order_id
+    WHERE o.status = 'pending'
+)
+INSERT INTO inventory_reservations (id, order_id, product_id, variant_id, quantity, status, expires_at, created_at, updated_at)
+SELECT
+    substr(h, 1, 8) || '-' || substr(h, 9, 4) || '-4' || substr(h, 14, 3) || '-8' || substr(h, 18, 3) || '-' || substr(h, 21, 12),
+    order_id, product_id, variant_id, quantity, 'converted', created_at, created_at, created_at
+FROM legacy;
@@ -0,0 +1,48 @@
+-- Append-only ledger of on-hand stock changes. Inserting a movement applies
+-- it, so stock only changes through the ledger and always equals its sum:
+-- per variant, or per product for products without variants.
+CREATE TABLE stock_movements (
+    id TEXT PRIMARY KEY,
+    product_id TEXT NOT NULL,
+    -- Not a foreign key: deleted variants keep their history
+    variant_id TEXT,
+    quantity INTEGER NOT NULL CHECK (quantity != 0),
+    reason TEXT NOT NULL CHECK (reason IN ('sale', 'cancellation', 'adjustment', 'return', 'import')),
+    note TEXT,
+    actor_id TEXT,
+    reference_id TEXT,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (product_id) REFERENCES products(id)
+);
+
+CREATE INDEX idx_stock_movements_product ON stock_movements(product_id, variant_id, created_at);
+
+-- Opening balances for the stock on hand today
+WITH opening AS MATERIALIZED (
+    SELECT id AS product_id, NULL AS variant_id, stock, lower(hex(randomblob(16))) AS h
+    FROM products p
+    WHERE stock != 0 AND NOT EXISTS (SELECT 1 FROM product_variants v WHERE v.product_id = p.id)
+    UNION ALL
+    SELECT product_id, id, stock, lower(hex(randomblob(16))) FROM product_variants WHERE stock != 0
+)
+INSERT INTO stock_movements (id, product_id, variant_id, quantity, reason, note, created_at)
+SELECT
+    substr(h, 1, 8) || '-' || substr(h, 9, 4) || '-4' || substr(h, 14, 3) || '-8' || substr(h, 18, 3) || '-' || substr(h, 21, 12),
+    product_id, variant_id, stock, 'import', 'Opening balance', strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+FROM opening;
+
+-- Variant stock rolls up into products.stock through the variant triggers
+CREATE TRIGGER stock_movements_apply AFTER INSERT ON stock_movements BEGIN
+    UPDATE product_variants SET stock = stock + new.quantity, updated_at = new.created_at
+    WHERE new.variant_id IS NOT NULL AND id = new.variant_id;
+    UPDATE products SET stock = stock + new.quantity, updated_at = new.created_at
+    WHERE new.variant_id IS NULL AND id = new.product_id;
+END;
+
+CREATE TRIGGER stock_movements_no_update BEFORE UPDATE ON stock_movements BEGIN
+    SELECT RAISE(ABORT, 'stock_movements is append-only');
+END;
+
+CREATE TRIGGER stock_movements_no_delete BEFORE DELETE ON stock_movements BEGIN
+    SELECT RAISE(ABORT, 'stock_movements is append-only');
+END;
@@ -0,0 +1,31 @@
+-- Admins are notified when a product's available stock falls below its
+-- reorder threshold; NULL means no alerts
+ALTER TABLE products ADD COLUMN reorder_threshold INTEGER CHECK (reorder_threshold > 0);
+
+CREATE TABLE admin_notifications (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL CHECK (kind IN ('low_stock')),
+    product_id TEXT,
+    message TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    read_at TEXT,
+    FOREIGN KEY (product_id) REFERENCES products(id)
+);
+
+CREATE INDEX idx_admin_notifications_created ON admin_notifications(created_at);
+
+-- Customers waiting for an out-of-stock product; notified_at is set once the
+-- back-in-stock email has been queued
+CREATE TABLE stock_subscriptions (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    product_id TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    notified_at TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (product_id) REFERENCES products(id)
+);
+
+-- One waiting subscription per customer and product
+CREATE UNIQUE INDEX idx_stock_subscriptions_waiting ON stock_subscriptions(user_id, product_id) WHERE notified_at IS NULL;
+CREATE INDEX idx_stock_subscriptions_product ON stock_subscriptions(product_id) WHERE notified_at IS NULL;
@@ -0,0 +1,66 @@
+-- Discount codes. product_ids and category_ids are JSON arrays restricting
+-- which items are discounted; both empty means the whole cart.
+CREATE TABLE coupons (
+    id TEXT PRIMARY KEY,
+    code TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    description TEXT NOT NULL DEFAULT '',
+    kind TEXT NOT NULL CHECK (kind IN ('percentage', 'fixed_amount', 'free_shipping')),
+    percent_off INTEGER CHECK (percent_off BETWEEN 1 AND 100),
+    amount_off_cents INTEGER CHECK (amount_off_cents > 0),
+    min_order_cents INTEGER CHECK (min_order_cents > 0),
+    currency TEXT NOT NULL DEFAULT 'USD',
+    product_ids TEXT NOT NULL DEFAULT '[]',
+    category_ids TEXT NOT NULL DEFAULT '[]',
+    max_uses INTEGER CHECK (max_uses > 0),
+    max_uses_per_user INTEGER CHECK (max_uses_per_user > 0),
+    times_used INTEGER NOT NULL DEFAULT 0 CHECK (times_used >= 0 AND (max_uses IS NULL OR times_used <= max_uses)),
+    starts_at TEXT,
+    ends_at TEXT,
+    is_active INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    CHECK ((kind = 'percentage') = (percent_off IS NOT NULL)),
+    CHECK ((kind = 'fixed_amount') = (amount_off_cents IS NOT NULL))
+);
+
+-- The coupon a customer has applied to their cart
+CREATE TABLE cart_coupons (
+    user_id TEXT PRIMARY KEY,
+    coupon_id TEXT NOT NULL,
+    applied_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (coupon_id) REFERENCES coupons(id) ON DELETE CASCADE
+);
+
+-- One row per order that used a coupon; removed again if the order is cancelled
+CREATE TABLE coupon_redemptions (
+    id TEXT PRIMARY KEY,
+    coupon_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    order_id TEXT NOT NULL UNIQUE,
+    discount_cents INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (coupon_id) REFERENCES coupons(id),
+    FOREIGN KEY (order_id) REFERENCES orders(id)
+);
+
+CREATE INDEX idx_coupon_redemptions_user ON coupon_redemptions(coupon_id, user_id);
+
+-- Discount lines as shown on the order, kept when the coupon changes
+CREATE TABLE order_discounts (
+    id TEXT PRIMARY KEY,
+    order_id TEXT NOT NULL,
+    coupon_id TEXT,
+    code TEXT NOT NULL,
+    description TEXT NOT NULL,
+    amount_cents INTEGER NOT NULL,
+    FOREIGN KEY (order_id) REFERENCES orders(id)
+);
+
+CREATE INDEX idx_order_discounts_order ON order_discounts(order_id);
+
+-- Each item's share of the discount, so returns refund what was paid
+ALTER TABLE order_items ADD COLUMN discount_cents INTEGER NOT NULL DEFAULT 0;
+
+-- Flat shipping fee charged on the order, included in total_cents
+ALTER TABLE orders ADD COLUMN shipping_cents INTEGER NOT NULL DEFAULT 0;
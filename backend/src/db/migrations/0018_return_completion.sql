@@ -0,0 +1,6 @@
+-- An approval is claimed, refunded and then applied (restock, payment total,
+-- order status); completed_at marks the last step so an interrupted approval
+-- can be finished later. Earlier approvals were applied in one go.
+ALTER TABLE return_requests ADD COLUMN completed_at TEXT;
+
+UPDATE return_requests SET completed_at = updated_at WHERE status = 'approved';
@@ -2,11 +2,44 @@ mod product_repo;
 mod user_repo;
 mod cart_repo;
 mod order_repo;
+mod address_repo;
+mod payment_repo;
+mod webhook_repo;
+mod return_repo;
+mod session_repo;
+mod password_reset_repo;
+mod email_verification_repo;
+mod two_factor_repo;
+mod variant_repo;
+mod category_repo;
+mod image_repo;
+mod reservation_repo;
+mod stock_repo;
+mod notification_repo;
+mod stock_subscription_repo;
+mod coupon_repo;
+pub mod migrations;
 
 pub use product_repo::*;
 pub use user_repo::*;
 pub use cart_repo::*;
 pub use order_repo::*;
+pub use address_repo::*;
+pub use payment_repo::*;
+pub use webhook_repo::*;
+pub use return_repo::*;
+pub use session_repo::*;
+pub use password_reset_repo::*;
+pub use email_verification_repo::*;
+pub use two_factor_repo::*;
+pub use variant_repo::*;
+pub use category_repo::*;
+pub use image_repo::*;
+pub use reservation_repo::*;
+pub use stock_repo::*;
+pub use notification_repo::*;
+pub use stock_subscription_repo::*;
+pub use coupon_repo::*;
 
 use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
 use anyhow::Result;
@@ -25,100 +58,15 @@ impl Database {
         Ok(Self { pool })
     }
 
+    /// Apply pending schema migrations, refusing to continue on drift
     pub async fn migrate(&self) -> Result<()> {
-        // Create tables
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS users (
-                id TEXT PRIMARY KEY,
-                username TEXT NOT NULL UNIQUE,
-                email TEXT NOT NULL UNIQUE,
-                password_hash TEXT NOT NULL,
-                role TEXT NOT NULL DEFAULT 'user',
-                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
+        let applied = migrations::run(&self.pool).await?;
 
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS products (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                description TEXT NOT NULL,
-                price REAL NOT NULL,
-                stock INTEGER NOT NULL DEFAULT 0,
-                category TEXT NOT NULL,
-                image_url TEXT,
-                is_active INTEGER NOT NULL DEFAULT 1,
-                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS cart_items (
-                user_id TEXT NOT NULL,
-                product_id TEXT NOT NULL,
-                quantity INTEGER NOT NULL DEFAULT 1,
-                PRIMARY KEY (user_id, product_id),
-                FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
-                FOREIGN KEY (product_id) REFERENCES products(id) ON DELETE CASCADE
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS orders (
-                id TEXT PRIMARY KEY,
-                user_id TEXT NOT NULL,
-                status TEXT NOT NULL DEFAULT 'pending',
-                total REAL NOT NULL,
-                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS order_items (
-                id TEXT PRIMARY KEY,
-                order_id TEXT NOT NULL,
-                product_id TEXT NOT NULL,
-                product_name TEXT NOT NULL,
-                quantity INTEGER NOT NULL,
-                price REAL NOT NULL,
-                subtotal REAL NOT NULL,
-                FOREIGN KEY (order_id) REFERENCES orders(id) ON DELETE CASCADE
-            )
-            "#,
-        )
-        .execute(&self.pool)
-        .await?;
-
-        // Create indexes
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_products_category ON products(category)")
-            .execute(&self.pool)
-            .await?;
-
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_orders_user_id ON orders(user_id)")
-            .execute(&self.pool)
-            .await?;
-
-        tracing::info!("Database migrations completed");
+        tracing::info!("Database migrations completed ({} applied)", applied.len());
         Ok(())
     }
+
+    pub async fn migration_status(&self) -> Result<Vec<migrations::MigrationStatus>> {
+        migrations::status(&self.pool).await
+    }
 }
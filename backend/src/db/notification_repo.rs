@@ -0,0 +1,140 @@
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use uuid::Uuid;
+use shared::{AdminNotification, AdminNotificationKind};
+
+/// (id, kind, product_id, message, created_at, read_at)
+type NotificationRow = (String, String, Option<String>, String, String, Option<String>);
+
+const NOTIFICATION_COLUMNS: &str = "id, kind, product_id, message, created_at, read_at";
+
+/// Whether taking `ordered` units moved available stock from at or above
+/// the threshold to below it
+fn fell_below(threshold: i32, available: i32, ordered: i32) -> bool {
+    available < threshold && available + ordered >= threshold
+}
+
+pub struct NotificationRepository;
+
+impl NotificationRepository {
+    /// Notify admins of each product the new order took below its reorder
+    /// threshold. Products that were already below it are not raised again.
+    pub(super) async fn raise_low_stock_in_tx(tx: &mut Transaction<'_, Sqlite>, order_id: Uuid) -> Result<()> {
+        // (product_id, name, threshold, available after the order, units the order holds)
+        let products: Vec<(String, String, i32, i32, i32)> = sqlx::query_as(
+            r#"
+            SELECT p.id, p.name, p.reorder_threshold,
+                   p.stock - (SELECT COALESCE(SUM(a.quantity), 0) FROM active_reservations a WHERE a.product_id = p.id),
+                   held.quantity
+            FROM (
+                SELECT product_id, SUM(quantity) AS quantity FROM inventory_reservations
+                WHERE order_id = ? GROUP BY product_id
+            ) AS held
+            JOIN products p ON p.id = held.product_id
+            WHERE p.reorder_threshold IS NOT NULL
+            "#,
+        )
+        .bind(order_id.to_string())
+        .fetch_all(&mut **tx)
+        .await?;
+
+        for (product_id, name, threshold, available, ordered) in products {
+            if !fell_below(threshold, available, ordered) {
+                continue;
+            }
+
+            sqlx::query(
+                "INSERT INTO admin_notifications (id, kind, product_id, message, created_at) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(AdminNotificationKind::LowStock.as_str())
+            .bind(&product_id)
+            .bind(format!(
+                "{} is low on stock: {} available, reorder threshold {}",
+                name,
+                available.max(0),
+                threshold
+            ))
+            .bind(Utc::now().to_rfc3339())
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Newest first, optionally only the unread ones
+    pub async fn list(pool: &SqlitePool, unread_only: bool, limit: u32) -> Result<Vec<AdminNotification>> {
+        let rows: Vec<NotificationRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM admin_notifications WHERE (read_at IS NULL OR NOT ?) ORDER BY created_at DESC LIMIT ?",
+            NOTIFICATION_COLUMNS
+        ))
+        .bind(unread_only)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_notification).collect()
+    }
+
+    pub async fn count_unread(pool: &SqlitePool) -> Result<i64> {
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM admin_notifications WHERE read_at IS NULL")
+            .fetch_one(pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// Mark a notification read; one already read keeps its first read time
+    pub async fn mark_read(pool: &SqlitePool, id: Uuid) -> Result<Option<AdminNotification>> {
+        sqlx::query("UPDATE admin_notifications SET read_at = COALESCE(read_at, ?) WHERE id = ?")
+            .bind(Utc::now().to_rfc3339())
+            .bind(id.to_string())
+            .execute(pool)
+            .await?;
+
+        let row: Option<NotificationRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM admin_notifications WHERE id = ?",
+            NOTIFICATION_COLUMNS
+        ))
+        .bind(id.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(Self::row_to_notification).transpose()
+    }
+
+    fn row_to_notification(row: NotificationRow) -> Result<AdminNotification> {
+        let (id, kind, product_id, message, created_at, read_at) = row;
+
+        Ok(AdminNotification {
+            id: id.parse()?,
+            kind: AdminNotificationKind::parse(&kind)
+                .ok_or_else(|| anyhow::anyhow!("Invalid notification kind: {}", kind))?,
+            product_id: product_id.map(|id| id.parse()).transpose()?,
+            message,
+            created_at: chrono::DateTime::parse_from_rfc3339(&created_at)?.with_timezone(&Utc),
+            read_at: read_at
+                .map(|at| chrono::DateTime::parse_from_rfc3339(&at).map(|at| at.with_timezone(&Utc)))
+                .transpose()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raises_only_when_crossing_the_threshold() {
+        // 12 available, an order of 3 leaves 9 below a threshold of 10
+        assert!(fell_below(10, 9, 3));
+        // Landing exactly on the threshold is not low yet
+        assert!(!fell_below(10, 10, 2));
+        // Already below before the order
+        assert!(!fell_below(10, 5, 2));
+        // Selling out from above the threshold
+        assert!(fell_below(10, 0, 15));
+    }
+}
@@ -1,94 +1,176 @@
 use anyhow::Result;
-use chrono::Utc;
-use sqlx::SqlitePool;
+use chrono::{Duration, Utc};
+use sqlx::{QueryBuilder, Sqlite, SqlitePool, Transaction};
 use uuid::Uuid;
-use shared::{Order, OrderItem, OrderStatus, OrderWithItems, CartItemWithProduct};
+use super::{CouponRepository, NotificationRepository, ReservationRepository, StockChange, StockRepository};
+use shared::{Address, AdminOrderListParams, Currency, Money, Order, OrderItem, OrderStatus, OrderStatusEvent, OrderWithItems, CartItemWithProduct, StockMovementReason};
+
+/// (id, user_id, status, total_cents, currency, created_at, updated_at, shipping_address)
+type OrderRow = (String, String, String, i64, String, String, String, Option<String>);
+
+/// (id, order_id, product_id, variant_id, product_name, variant_title, quantity, price_cents, subtotal_cents, discount_cents, currency)
+type OrderItemRow = (String, String, String, Option<String>, String, Option<String>, i32, i64, i64, i64, String);
+
+/// (id, order_id, from_status, to_status, changed_by, created_at)
+type StatusEventRow = (String, String, Option<String>, String, Option<String>, String);
 
 pub struct OrderRepository;
 
 impl OrderRepository {
-    /// Atomic order creation with stock validation and update
+    /// Atomic order creation with stock validation. Stock is held for
+    /// `hold_for` rather than taken; paying the order converts the holds.
+    /// The cart's coupon is priced and redeemed in the same transaction, and
+    /// an order it brings down to zero is marked paid straight away.
     /// Uses database transaction to prevent race conditions
     pub async fn create_order_atomic(
         pool: &SqlitePool,
         user_id: Uuid,
         cart_items: &[CartItemWithProduct],
+        shipping_address: &Address,
+        shipping: Money,
+        hold_for: Duration,
     ) -> Result<OrderWithItems> {
         let mut tx = pool.begin().await?;
 
         let order_id = Uuid::new_v4();
         let now = Utc::now();
+        let reserved_until = now + hold_for;
         let mut order_items = Vec::new();
-        let mut total: f64 = 0.0;
+        let currency = cart_items
+            .first()
+            .map(|item| item.product_price.currency)
+            .unwrap_or_default();
+        let mut items_total = Money::zero(currency);
 
         // Verify stock and collect order items within transaction
         for item in cart_items {
             // Lock the row by selecting FOR UPDATE (SQLite handles this implicitly in transaction)
-            let row: Option<(i32, String)> = sqlx::query_as(
-                "SELECT stock, name FROM products WHERE id = ? AND is_active = 1"
-            )
-            .bind(item.product_id.to_string())
-            .fetch_optional(&mut *tx)
-            .await?;
-
-            let (stock, product_name) = row.ok_or_else(|| {
+            let row: Option<(i32, String, Option<String>)> = match item.variant_id {
+                Some(variant_id) => {
+                    sqlx::query_as(
+                        r#"
+                        SELECT v.stock - (SELECT COALESCE(SUM(r.quantity), 0) FROM active_reservations r WHERE r.variant_id = v.id),
+                               p.name, v.title
+                        FROM product_variants v
+                        JOIN products p ON p.id = v.product_id
+                        WHERE v.id = ? AND v.product_id = ? AND v.is_active = 1 AND p.is_active = 1
+                        "#,
+                    )
+                    .bind(variant_id.to_string())
+                    .bind(item.product_id.to_string())
+                    .fetch_optional(&mut *tx)
+                    .await?
+                }
+                None => {
+                    // Products sold by variant cannot be bought without choosing one
+                    sqlx::query_as(
+                        r#"
+                        SELECT stock - (SELECT COALESCE(SUM(r.quantity), 0) FROM active_reservations r WHERE r.product_id = p.id),
+                               name, NULL
+                        FROM products p
+                        WHERE id = ? AND is_active = 1
+                          AND NOT EXISTS (SELECT 1 FROM product_variants v WHERE v.product_id = p.id AND v.is_active = 1)
+                        "#,
+                    )
+                    .bind(item.product_id.to_string())
+                    .fetch_optional(&mut *tx)
+                    .await?
+                }
+            };
+
+            let (available, product_name, variant_title) = row.ok_or_else(|| {
                 anyhow::anyhow!("Product {} not found or inactive", item.product_id)
             })?;
 
-            if stock < item.quantity {
+            if available < item.quantity {
+                let name = match &variant_title {
+                    Some(title) => format!("{} ({})", product_name, title),
+                    None => product_name.clone(),
+                };
                 return Err(anyhow::anyhow!("Insufficient stock for {}: requested {}, available {}",
-                    product_name, item.quantity, stock));
+                    name, item.quantity, available.max(0)));
             }
 
-            // Update stock immediately within transaction
-            sqlx::query(
-                "UPDATE products SET stock = stock - ?, updated_at = ? WHERE id = ?"
-            )
-            .bind(item.quantity)
-            .bind(now.to_rfc3339())
-            .bind(item.product_id.to_string())
-            .execute(&mut *tx)
-            .await?;
-
-            let subtotal = item.quantity as f64 * item.product_price;
-            total += subtotal;
+            let subtotal = item.product_price.checked_mul(item.quantity as i64)?;
+            items_total = items_total.checked_add(subtotal)?;
 
-            order_items.push((item.product_id, product_name, item.quantity, item.product_price, subtotal));
+            order_items.push((
+                item.product_id,
+                item.variant_id,
+                product_name,
+                variant_title,
+                item.quantity,
+                item.product_price,
+                subtotal,
+            ));
         }
 
+        // Price the cart's coupon against the current usage counts
+        let coupon = CouponRepository::for_cart_on(&mut tx, user_id).await?;
+        let applied = match &coupon {
+            Some(coupon) => Some(coupon.apply(cart_items, shipping).map_err(|e| {
+                anyhow::anyhow!("Coupon {} cannot be used: {}", coupon.coupon.code, e)
+            })?),
+            None => None,
+        };
+        let discount = applied.as_ref().map(|a| a.line.amount).unwrap_or(Money::zero(currency));
+        let total = items_total.checked_sub(discount)?.checked_add(shipping)?;
+
         // Create order
         sqlx::query(
             r#"
-            INSERT INTO orders (id, user_id, status, total, created_at, updated_at)
-            VALUES (?, ?, 'pending', ?, ?, ?)
+            INSERT INTO orders (id, user_id, status, total_cents, currency, shipping_cents, shipping_address, created_at, updated_at)
+            VALUES (?, ?, 'pending', ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(order_id.to_string())
         .bind(user_id.to_string())
-        .bind(total)
+        .bind(total.amount)
+        .bind(total.currency.code())
+        .bind(shipping.amount)
+        .bind(serde_json::to_string(shipping_address)?)
         .bind(now.to_rfc3339())
         .bind(now.to_rfc3339())
         .execute(&mut *tx)
         .await?;
 
+        // Use up the coupon; fails if a concurrent order took its last redemption
+        if let (Some(coupon), Some(applied)) = (&coupon, &applied) {
+            CouponRepository::redeem_in_tx(&mut tx, &coupon.coupon, user_id, order_id, &applied.line).await?;
+        }
+
+        // Hold the stock; the order row has to exist first
+        for (product_id, variant_id, _, _, quantity, _, _) in &order_items {
+            ReservationRepository::hold_in_tx(&mut tx, order_id, *product_id, *variant_id, *quantity, reserved_until).await?;
+        }
+
+        NotificationRepository::raise_low_stock_in_tx(&mut tx, order_id).await?;
+
         // Create order items
         let mut result_items = Vec::new();
-        for (product_id, product_name, quantity, price, subtotal) in order_items {
+        for (i, (product_id, variant_id, product_name, variant_title, quantity, price, subtotal)) in order_items.into_iter().enumerate() {
             let item_id = Uuid::new_v4();
+            let discount = applied
+                .as_ref()
+                .map(|a| a.item_discounts[i])
+                .filter(|discount| !discount.is_zero());
 
             sqlx::query(
                 r#"
-                INSERT INTO order_items (id, order_id, product_id, product_name, quantity, price, subtotal)
-                VALUES (?, ?, ?, ?, ?, ?, ?)
+                INSERT INTO order_items (id, order_id, product_id, variant_id, product_name, variant_title, quantity, price_cents, subtotal_cents, discount_cents)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
                 "#,
             )
             .bind(item_id.to_string())
             .bind(order_id.to_string())
             .bind(product_id.to_string())
+            .bind(variant_id.map(|id| id.to_string()))
             .bind(&product_name)
+            .bind(&variant_title)
             .bind(quantity)
-            .bind(price)
-            .bind(subtotal)
+            .bind(price.amount)
+            .bind(subtotal.amount)
+            .bind(discount.map(|d| d.amount).unwrap_or(0))
             .execute(&mut *tx)
             .await?;
 
@@ -96,13 +178,35 @@ impl OrderRepository {
                 id: item_id,
                 order_id,
                 product_id,
+                variant_id,
                 product_name,
+                variant_title,
                 quantity,
                 price,
                 subtotal,
+                discount,
             });
         }
 
+        // Record the initial timeline entry
+        let event = OrderStatusEvent {
+            id: Uuid::new_v4(),
+            order_id,
+            from_status: None,
+            to_status: OrderStatus::Pending,
+            changed_by: Some(user_id),
+            created_at: now,
+        };
+        Self::insert_status_event(&mut tx, &event).await?;
+
+        // A fully discounted order has nothing to charge, so settle it now and
+        // take its stock the way a payment would
+        let settled = total.is_zero();
+        if settled {
+            Self::transition(&mut tx, order_id, &OrderStatus::Pending, &OrderStatus::Paid, Some(user_id)).await?;
+            ReservationRepository::convert_in_tx(&mut tx, order_id, Some(user_id)).await?;
+        }
+
         // Clear cart within transaction
         sqlx::query("DELETE FROM cart_items WHERE user_id = ?")
             .bind(user_id.to_string())
@@ -112,11 +216,18 @@ impl OrderRepository {
         // Commit transaction
         tx.commit().await?;
 
+        let (status, timeline, reserved_until) = if settled {
+            (OrderStatus::Paid, Self::get_status_events(pool, order_id).await?, None)
+        } else {
+            (OrderStatus::Pending, vec![event], Some(reserved_until))
+        };
+
         let order = Order {
             id: order_id,
             user_id,
-            status: OrderStatus::Pending,
+            status,
             total,
+            shipping_address: Some(shipping_address.clone()),
             created_at: now,
             updated_at: now,
         };
@@ -124,28 +235,36 @@ impl OrderRepository {
         Ok(OrderWithItems {
             order,
             items: result_items,
+            timeline,
+            reserved_until,
+            discounts: applied.map(|a| vec![a.line]).unwrap_or_default(),
+            shipping: Some(shipping),
         })
     }
 
     pub async fn create(
         pool: &SqlitePool,
         user_id: Uuid,
-        items: Vec<(Uuid, String, i32, f64)>, // (product_id, name, quantity, price)
+        items: Vec<(Uuid, String, i32, Money)>, // (product_id, name, quantity, price)
     ) -> Result<OrderWithItems> {
         let order_id = Uuid::new_v4();
         let now = Utc::now();
-        let total: f64 = items.iter().map(|(_, _, qty, price)| *qty as f64 * price).sum();
+        let currency = items.first().map(|(_, _, _, price)| price.currency).unwrap_or_default();
+        let total = items.iter().try_fold(Money::zero(currency), |acc, (_, _, qty, price)| {
+            acc.checked_add(price.checked_mul(*qty as i64)?)
+        })?;
 
         // Create order
         sqlx::query(
             r#"
-            INSERT INTO orders (id, user_id, status, total, created_at, updated_at)
-            VALUES (?, ?, 'pending', ?, ?, ?)
+            INSERT INTO orders (id, user_id, status, total_cents, currency, created_at, updated_at)
+            VALUES (?, ?, 'pending', ?, ?, ?, ?)
             "#,
         )
         .bind(order_id.to_string())
         .bind(user_id.to_string())
-        .bind(total)
+        .bind(total.amount)
+        .bind(total.currency.code())
         .bind(now.to_rfc3339())
         .bind(now.to_rfc3339())
         .execute(pool)
@@ -155,11 +274,11 @@ impl OrderRepository {
         let mut order_items = Vec::new();
         for (product_id, product_name, quantity, price) in items {
             let item_id = Uuid::new_v4();
-            let subtotal = quantity as f64 * price;
+            let subtotal = price.checked_mul(quantity as i64)?;
 
             sqlx::query(
                 r#"
-                INSERT INTO order_items (id, order_id, product_id, product_name, quantity, price, subtotal)
+                INSERT INTO order_items (id, order_id, product_id, product_name, quantity, price_cents, subtotal_cents)
                 VALUES (?, ?, ?, ?, ?, ?, ?)
                 "#,
             )
@@ -168,8 +287,8 @@ impl OrderRepository {
             .bind(product_id.to_string())
             .bind(&product_name)
             .bind(quantity)
-            .bind(price)
-            .bind(subtotal)
+            .bind(price.amount)
+            .bind(subtotal.amount)
             .execute(pool)
             .await?;
 
@@ -177,10 +296,13 @@ impl OrderRepository {
                 id: item_id,
                 order_id,
                 product_id,
+                variant_id: None,
                 product_name,
+                variant_title: None,
                 quantity,
                 price,
                 subtotal,
+                discount: None,
             });
         }
 
@@ -189,6 +311,7 @@ impl OrderRepository {
             user_id,
             status: OrderStatus::Pending,
             total,
+            shipping_address: None,
             created_at: now,
             updated_at: now,
         };
@@ -196,6 +319,10 @@ impl OrderRepository {
         Ok(OrderWithItems {
             order,
             items: order_items,
+            timeline: Vec::new(),
+            reserved_until: None,
+            discounts: Vec::new(),
+            shipping: None,
         })
     }
 
@@ -203,9 +330,9 @@ impl OrderRepository {
         pool: &SqlitePool,
         user_id: Uuid,
     ) -> Result<Vec<Order>> {
-        let rows: Vec<(String, String, String, f64, String, String)> = sqlx::query_as(
+        let rows: Vec<OrderRow> = sqlx::query_as(
             r#"
-            SELECT id, user_id, status, total, created_at, updated_at
+            SELECT id, user_id, status, total_cents, currency, created_at, updated_at, shipping_address
             FROM orders WHERE user_id = ? ORDER BY created_at DESC
             "#,
         )
@@ -222,9 +349,9 @@ impl OrderRepository {
     }
 
     pub async fn get_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<OrderWithItems>> {
-        let row: Option<(String, String, String, f64, String, String)> = sqlx::query_as(
+        let row: Option<OrderRow> = sqlx::query_as(
             r#"
-            SELECT id, user_id, status, total, created_at, updated_at
+            SELECT id, user_id, status, total_cents, currency, created_at, updated_at, shipping_address
             FROM orders WHERE id = ?
             "#,
         )
@@ -236,17 +363,31 @@ impl OrderRepository {
             Some(row) => {
                 let order = Self::row_to_order(row)?;
                 let items = Self::get_order_items(pool, id).await?;
-                Ok(Some(OrderWithItems { order, items }))
+                let timeline = Self::get_status_events(pool, id).await?;
+                let reserved_until = match order.status {
+                    OrderStatus::Pending => ReservationRepository::expires_at(pool, id).await?,
+                    _ => None,
+                };
+                let discounts = CouponRepository::order_discounts(pool, id, order.total.currency).await?;
+                let (shipping_cents,): (i64,) = sqlx::query_as("SELECT shipping_cents FROM orders WHERE id = ?")
+                    .bind(id.to_string())
+                    .fetch_one(pool)
+                    .await?;
+                let shipping = Some(Money::new(shipping_cents, order.total.currency));
+                Ok(Some(OrderWithItems { order, items, timeline, reserved_until, discounts, shipping }))
             }
             None => Ok(None),
         }
     }
 
     pub async fn get_order_items(pool: &SqlitePool, order_id: Uuid) -> Result<Vec<OrderItem>> {
-        let rows: Vec<(String, String, String, String, i32, f64, f64)> = sqlx::query_as(
+        let rows: Vec<OrderItemRow> = sqlx::query_as(
             r#"
-            SELECT id, order_id, product_id, product_name, quantity, price, subtotal
-            FROM order_items WHERE order_id = ?
+            SELECT oi.id, oi.order_id, oi.product_id, oi.variant_id, oi.product_name, oi.variant_title, oi.quantity,
+                   oi.price_cents, oi.subtotal_cents, oi.discount_cents, o.currency
+            FROM order_items oi
+            JOIN orders o ON oi.order_id = o.id
+            WHERE oi.order_id = ?
             "#,
         )
         .bind(order_id.to_string())
@@ -255,15 +396,19 @@ impl OrderRepository {
 
         let items: Vec<OrderItem> = rows
             .into_iter()
-            .filter_map(|(id, order_id, product_id, product_name, quantity, price, subtotal)| {
+            .filter_map(|(id, order_id, product_id, variant_id, product_name, variant_title, quantity, price_cents, subtotal_cents, discount_cents, currency)| {
+                let currency = Currency::new(&currency)?;
                 Some(OrderItem {
                     id: id.parse().ok()?,
                     order_id: order_id.parse().ok()?,
                     product_id: product_id.parse().ok()?,
+                    variant_id: variant_id.and_then(|v| v.parse().ok()),
                     product_name,
+                    variant_title,
                     quantity,
-                    price,
-                    subtotal,
+                    price: Money::new(price_cents, currency),
+                    subtotal: Money::new(subtotal_cents, currency),
+                    discount: (discount_cents != 0).then(|| Money::new(discount_cents, currency)),
                 })
             })
             .collect();
@@ -271,50 +416,253 @@ impl OrderRepository {
         Ok(items)
     }
 
+    /// List orders across all users, newest first, with optional filters
+    pub async fn list_all(
+        pool: &SqlitePool,
+        params: &AdminOrderListParams,
+        page: u32,
+        limit: u32,
+    ) -> Result<(Vec<Order>, u32)> {
+        // In i64 so a huge page number cannot overflow
+        let offset = (page.max(1) as i64 - 1) * limit as i64;
+
+        let rows: Vec<OrderRow> = {
+            let mut query = QueryBuilder::<Sqlite>::new(
+                "SELECT id, user_id, status, total_cents, currency, created_at, updated_at, shipping_address FROM orders",
+            );
+            Self::push_filters(&mut query, params);
+            query
+                .push(" ORDER BY created_at DESC LIMIT ")
+                .push_bind(limit as i64)
+                .push(" OFFSET ")
+                .push_bind(offset);
+            query.build_query_as().fetch_all(pool).await?
+        };
+
+        let orders: Vec<Order> = rows
+            .into_iter()
+            .filter_map(|row| Self::row_to_order(row).ok())
+            .collect();
+
+        let count: (i64,) = {
+            let mut query = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM orders");
+            Self::push_filters(&mut query, params);
+            query.build_query_as().fetch_one(pool).await?
+        };
+
+        Ok((orders, count.0 as u32))
+    }
+
+    fn push_filters(query: &mut QueryBuilder<'_, Sqlite>, params: &AdminOrderListParams) {
+        query.push(" WHERE 1 = 1");
+        if let Some(status) = &params.status {
+            query.push(" AND status = ").push_bind(status.as_str());
+        }
+        if let Some(user_id) = params.user_id {
+            query.push(" AND user_id = ").push_bind(user_id.to_string());
+        }
+        if let Some(from) = params.from {
+            query.push(" AND julianday(created_at) >= julianday(").push_bind(from.to_rfc3339()).push(")");
+        }
+        if let Some(to) = params.to {
+            query.push(" AND julianday(created_at) <= julianday(").push_bind(to.to_rfc3339()).push(")");
+        }
+    }
+
+    /// Move an order from `from` to `to`, recording who made the change.
+    /// Returns false if the order no longer has status `from`.
     pub async fn update_status(
         pool: &SqlitePool,
         id: Uuid,
-        status: OrderStatus,
+        from: &OrderStatus,
+        to: &OrderStatus,
+        changed_by: Option<Uuid>,
     ) -> Result<bool> {
-        let status_str = match status {
-            OrderStatus::Pending => "pending",
-            OrderStatus::Paid => "paid",
-            OrderStatus::Shipped => "shipped",
-            OrderStatus::Delivered => "delivered",
-            OrderStatus::Cancelled => "cancelled",
-        };
+        let mut tx = pool.begin().await?;
+
+        let updated = Self::transition(&mut tx, id, from, to, changed_by).await?;
+
+        tx.commit().await?;
+        Ok(updated)
+    }
+
+    /// Mark a pending order paid without a charge through the payment
+    /// provider, turning its holds into sales in the same transaction.
+    /// Returns false if the order is no longer pending.
+    pub async fn mark_paid(pool: &SqlitePool, id: Uuid, changed_by: Option<Uuid>) -> Result<bool> {
+        let mut tx = pool.begin().await?;
+
+        if !Self::transition(&mut tx, id, &OrderStatus::Pending, &OrderStatus::Paid, changed_by).await? {
+            return Ok(false);
+        }
+        ReservationRepository::convert_in_tx(&mut tx, id, changed_by).await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Cancel an order, releasing its holds or restoring the stock it took, in
+    /// one transaction.
+    /// Returns false if the order no longer has status `from`.
+    pub async fn cancel(
+        pool: &SqlitePool,
+        id: Uuid,
+        from: &OrderStatus,
+        changed_by: Option<Uuid>,
+    ) -> Result<bool> {
+        let mut tx = pool.begin().await?;
+
+        if !Self::cancel_in_tx(&mut tx, id, from, changed_by).await? {
+            return Ok(false);
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// `cancel` within a caller's transaction
+    pub(super) async fn cancel_in_tx(
+        tx: &mut Transaction<'_, Sqlite>,
+        id: Uuid,
+        from: &OrderStatus,
+        changed_by: Option<Uuid>,
+    ) -> Result<bool> {
+        if !Self::transition(tx, id, from, &OrderStatus::Cancelled, changed_by).await? {
+            return Ok(false);
+        }
+
+        CouponRepository::release_in_tx(tx, id).await?;
+
+        if !ReservationRepository::release_in_tx(tx, id).await? {
+            return Ok(true);
+        }
+
+        let items: Vec<(String, Option<String>, i32)> = sqlx::query_as(
+            "SELECT product_id, variant_id, quantity FROM order_items WHERE order_id = ?",
+        )
+        .bind(id.to_string())
+        .fetch_all(&mut **tx)
+        .await?;
+
+        for (product_id, variant_id, quantity) in items {
+            let restock = StockChange {
+                product_id: product_id.parse()?,
+                variant_id: variant_id.map(|id| id.parse()).transpose()?,
+                quantity,
+                reason: StockMovementReason::Cancellation,
+                note: None,
+                actor_id: changed_by,
+                reference_id: Some(id),
+            };
+            StockRepository::record(&mut **tx, &restock).await?;
+        }
+
+        Ok(true)
+    }
+
+    /// Compare-and-set the status and append a status event
+    pub(super) async fn transition(
+        tx: &mut Transaction<'_, Sqlite>,
+        id: Uuid,
+        from: &OrderStatus,
+        to: &OrderStatus,
+        changed_by: Option<Uuid>,
+    ) -> Result<bool> {
+        let now = Utc::now();
 
         let result = sqlx::query(
-            "UPDATE orders SET status = ?, updated_at = ? WHERE id = ?",
+            "UPDATE orders SET status = ?, updated_at = ? WHERE id = ? AND status = ?",
         )
-        .bind(status_str)
-        .bind(Utc::now().to_rfc3339())
+        .bind(to.as_str())
+        .bind(now.to_rfc3339())
         .bind(id.to_string())
-        .execute(pool)
+        .bind(from.as_str())
+        .execute(&mut **tx)
         .await?;
 
-        Ok(result.rows_affected() > 0)
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        let event = OrderStatusEvent {
+            id: Uuid::new_v4(),
+            order_id: id,
+            from_status: Some(from.clone()),
+            to_status: to.clone(),
+            changed_by,
+            created_at: now,
+        };
+        Self::insert_status_event(tx, &event).await?;
+
+        Ok(true)
+    }
+
+    async fn insert_status_event(
+        tx: &mut Transaction<'_, Sqlite>,
+        event: &OrderStatusEvent,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO order_status_events (id, order_id, from_status, to_status, changed_by, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(event.id.to_string())
+        .bind(event.order_id.to_string())
+        .bind(event.from_status.as_ref().map(|s| s.as_str()))
+        .bind(event.to_status.as_str())
+        .bind(event.changed_by.map(|u| u.to_string()))
+        .bind(event.created_at.to_rfc3339())
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Status timeline of an order, oldest first
+    pub async fn get_status_events(pool: &SqlitePool, order_id: Uuid) -> Result<Vec<OrderStatusEvent>> {
+        let rows: Vec<StatusEventRow> = sqlx::query_as(
+            r#"
+            SELECT id, order_id, from_status, to_status, changed_by, created_at
+            FROM order_status_events WHERE order_id = ? ORDER BY created_at ASC
+            "#,
+        )
+        .bind(order_id.to_string())
+        .fetch_all(pool)
+        .await?;
+
+        let events: Vec<OrderStatusEvent> = rows
+            .into_iter()
+            .filter_map(|(id, order_id, from_status, to_status, changed_by, created_at)| {
+                Some(OrderStatusEvent {
+                    id: id.parse().ok()?,
+                    order_id: order_id.parse().ok()?,
+                    from_status: from_status.as_deref().and_then(OrderStatus::parse),
+                    to_status: OrderStatus::parse(&to_status)?,
+                    changed_by: changed_by.and_then(|u| u.parse().ok()),
+                    created_at: chrono::DateTime::parse_from_rfc3339(&created_at).ok()?.with_timezone(&Utc),
+                })
+            })
+            .collect();
+
+        Ok(events)
     }
 
     fn row_to_order(
-        row: (String, String, String, f64, String, String),
+        row: OrderRow,
     ) -> Result<Order> {
-        let status = match row.2.as_str() {
-            "pending" => OrderStatus::Pending,
-            "paid" => OrderStatus::Paid,
-            "shipped" => OrderStatus::Shipped,
-            "delivered" => OrderStatus::Delivered,
-            "cancelled" => OrderStatus::Cancelled,
-            _ => OrderStatus::Pending,
-        };
+        let status = OrderStatus::parse(&row.2).unwrap_or_default();
+        let currency = Currency::new(&row.4)
+            .ok_or_else(|| anyhow::anyhow!("Invalid currency code: {}", row.4))?;
 
         Ok(Order {
             id: row.0.parse()?,
             user_id: row.1.parse()?,
             status,
-            total: row.3,
-            created_at: chrono::DateTime::parse_from_rfc3339(&row.4)?.with_timezone(&Utc),
-            updated_at: chrono::DateTime::parse_from_rfc3339(&row.5)?.with_timezone(&Utc),
+            total: Money::new(row.3, currency),
+            shipping_address: row.7.as_deref().and_then(|a| serde_json::from_str(a).ok()),
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.5)?.with_timezone(&Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.6)?.with_timezone(&Utc),
         })
     }
 }
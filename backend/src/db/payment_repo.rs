@@ -0,0 +1,177 @@
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+use super::{OrderRepository, ReservationRepository};
+use shared::{Currency, Money, OrderStatus, Payment, PaymentStatus};
+
+/// (id, order_id, provider, provider_payment_id, amount_cents, refunded_cents, currency, status, failure_reason, created_at, updated_at)
+type PaymentRow = (String, String, String, String, i64, i64, String, String, Option<String>, String, String);
+
+const PAYMENT_COLUMNS: &str = "id, order_id, provider, provider_payment_id, amount_cents, refunded_cents, currency, status, failure_reason, created_at, updated_at";
+
+pub struct PaymentRepository;
+
+impl PaymentRepository {
+    pub async fn create(
+        pool: &SqlitePool,
+        order_id: Uuid,
+        provider: &str,
+        provider_payment_id: &str,
+        amount: Money,
+    ) -> Result<Payment> {
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+
+        sqlx::query(
+            r#"
+            INSERT INTO payments (id, order_id, provider, provider_payment_id, amount_cents, currency, status, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, 'pending', ?, ?)
+            "#,
+        )
+        .bind(id.to_string())
+        .bind(order_id.to_string())
+        .bind(provider)
+        .bind(provider_payment_id)
+        .bind(amount.amount)
+        .bind(amount.currency.code())
+        .bind(now.to_rfc3339())
+        .bind(now.to_rfc3339())
+        .execute(pool)
+        .await?;
+
+        Ok(Payment {
+            id,
+            order_id,
+            provider: provider.to_string(),
+            provider_payment_id: provider_payment_id.to_string(),
+            amount,
+            refunded: Money::zero(amount.currency),
+            status: PaymentStatus::Pending,
+            failure_reason: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    pub async fn get(pool: &SqlitePool, id: Uuid) -> Result<Option<Payment>> {
+        let row: Option<PaymentRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM payments WHERE id = ?",
+            PAYMENT_COLUMNS
+        ))
+        .bind(id.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(Self::row_to_payment(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The in-flight payment of an order, if a previous attempt was interrupted
+    pub async fn get_pending_for_order(pool: &SqlitePool, order_id: Uuid) -> Result<Option<Payment>> {
+        let row: Option<PaymentRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM payments WHERE order_id = ? AND status = 'pending'",
+            PAYMENT_COLUMNS
+        ))
+        .bind(order_id.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(Self::row_to_payment(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The payment that was actually collected for an order, if any
+    pub async fn get_succeeded_for_order(pool: &SqlitePool, order_id: Uuid) -> Result<Option<Payment>> {
+        let row: Option<PaymentRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM payments WHERE order_id = ? AND status = 'succeeded'",
+            PAYMENT_COLUMNS
+        ))
+        .bind(order_id.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(Self::row_to_payment(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn mark_failed(pool: &SqlitePool, id: Uuid, reason: &str) -> Result<()> {
+        sqlx::query(
+            "UPDATE payments SET status = 'failed', failure_reason = ?, updated_at = ? WHERE id = ? AND status = 'pending'",
+        )
+        .bind(reason)
+        .bind(Utc::now().to_rfc3339())
+        .bind(id.to_string())
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn mark_refunded(pool: &SqlitePool, id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE payments SET status = 'refunded', refunded_cents = amount_cents, updated_at = ? WHERE id = ?")
+            .bind(Utc::now().to_rfc3339())
+            .bind(id.to_string())
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Mark a pending payment as succeeded, move its order from Pending to
+    /// Paid and take its held stock in one transaction. Returns false, changing nothing, if the
+    /// payment or the order is no longer pending.
+    pub async fn mark_succeeded(
+        pool: &SqlitePool,
+        id: Uuid,
+        order_id: Uuid,
+        changed_by: Option<Uuid>,
+    ) -> Result<bool> {
+        let mut tx = pool.begin().await?;
+
+        let result = sqlx::query(
+            "UPDATE payments SET status = 'succeeded', updated_at = ? WHERE id = ? AND status = 'pending'",
+        )
+        .bind(Utc::now().to_rfc3339())
+        .bind(id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        if !OrderRepository::transition(&mut tx, order_id, &OrderStatus::Pending, &OrderStatus::Paid, changed_by).await? {
+            return Ok(false);
+        }
+        ReservationRepository::convert_in_tx(&mut tx, order_id, changed_by).await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    fn row_to_payment(row: PaymentRow) -> Result<Payment> {
+        let currency = Currency::new(&row.6)
+            .ok_or_else(|| anyhow::anyhow!("Invalid currency code: {}", row.6))?;
+
+        Ok(Payment {
+            id: row.0.parse()?,
+            order_id: row.1.parse()?,
+            provider: row.2,
+            provider_payment_id: row.3,
+            amount: Money::new(row.4, currency),
+            refunded: Money::new(row.5, currency),
+            status: PaymentStatus::parse(&row.7)
+                .ok_or_else(|| anyhow::anyhow!("Invalid payment status: {}", row.7))?,
+            failure_reason: row.8,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.9)?.with_timezone(&Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.10)?.with_timezone(&Utc),
+        })
+    }
+}
@@ -1,59 +1,213 @@
 use anyhow::Result;
 use chrono::Utc;
-use sqlx::SqlitePool;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
 use uuid::Uuid;
-use shared::Product;
+use shared::{
+    CategoryFacet, CreateProductRequest, Currency, Money, PriceRangeFacet, Product, ProductFacets, ProductListParams,
+    SearchHighlight, StockMovementReason, Suggestion, SuggestionKind, UpdateProductRequest,
+};
+use super::{StockChange, StockRepository};
+use crate::suggest::SuggestionIndex;
 
-pub struct ProductRepository;
+/// (id, name, description, price_cents, currency, stock, reserved, category, image_url, thumbnail_url, is_active, created_at, updated_at)
+type ProductRow = (
+    String, String, String, i64, String, i32, i32, Option<i32>, String, Option<String>, Option<String>, i32, String, String,
+);
 
-impl ProductRepository {
-    pub async fn list(
-        pool: &SqlitePool,
-        page: u32,
-        limit: u32,
-        sort_by: Option<&str>,
-        sort_order: Option<&str>,
-    ) -> Result<(Vec<Product>, u32)> {
-        let offset = (page - 1) * limit;
-
-        let order_clause = match (sort_by, sort_order) {
-            (Some("price"), Some("desc")) => "ORDER BY price DESC",
-            (Some("price"), _) => "ORDER BY price ASC",
-            (Some("name"), Some("desc")) => "ORDER BY name DESC",
-            (Some("name"), _) => "ORDER BY name ASC",
-            _ => "ORDER BY created_at DESC",
-        };
+/// A `ProductRow` followed by (name_highlight, snippet), set only when searching
+type SearchRow = (
+    String, String, String, i64, String, i32, i32, Option<i32>, String, Option<String>, Option<String>, i32, String, String,
+    Option<String>, Option<String>,
+);
 
-        let query = format!(
-            "SELECT id, name, description, price, stock, category, image_url, is_active, created_at, updated_at
-             FROM products WHERE is_active = 1 {} LIMIT ? OFFSET ?",
-            order_clause
-        );
+const PRODUCT_COLUMNS: &str = "p.id, p.name, p.description, p.price_cents, p.currency, p.stock, \
+    (SELECT COALESCE(SUM(r.quantity), 0) FROM active_reservations r WHERE r.product_id = p.id), p.reorder_threshold, \
+    p.category, p.image_url, p.thumbnail_url, p.is_active, p.created_at, p.updated_at";
 
-        let rows: Vec<(String, String, String, f64, i32, String, Option<String>, i32, String, String)> =
-            sqlx::query_as(&query)
-                .bind(limit as i64)
-                .bind(offset as i64)
-                .fetch_all(pool)
-                .await?;
+/// Upper bounds (minor units) of the price facet buckets; a final bucket
+/// holds everything above the last one
+const PRICE_BUCKET_EDGES: &[i64] = &[2_500, 5_000, 10_000, 20_000];
 
-        let products: Vec<Product> = rows
-            .into_iter()
-            .filter_map(|row| Self::row_to_product(row).ok())
-            .collect();
+/// Most terms considered from a single search query
+const MAX_SEARCH_TERMS: usize = 8;
 
-        let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM products WHERE is_active = 1")
-            .fetch_one(pool)
-            .await?;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProductSort {
+    Newest,
+    /// Best full-text match first; only meaningful with a search query
+    Relevance,
+    PriceAsc,
+    PriceDesc,
+    NameAsc,
+    NameDesc,
+}
+
+impl ProductSort {
+    pub fn parse(sort_by: Option<&str>, sort_order: Option<&str>) -> Option<Self> {
+        let desc = sort_order == Some("desc");
+
+        match sort_by? {
+            "price" if desc => Some(ProductSort::PriceDesc),
+            "price" => Some(ProductSort::PriceAsc),
+            "name" if desc => Some(ProductSort::NameDesc),
+            "name" => Some(ProductSort::NameAsc),
+            "newest" | "created_at" => Some(ProductSort::Newest),
+            "relevance" => Some(ProductSort::Relevance),
+            _ => None,
+        }
+    }
+}
+
+/// Everything a product listing can be narrowed and ordered by
+#[derive(Debug, Clone, Default)]
+pub struct ProductFilter {
+    /// Match any of these categories (by name or slug) or their subcategories; empty means all
+    pub categories: Vec<String>,
+    pub min_price: Option<i64>,
+    pub max_price: Option<i64>,
+    pub in_stock: bool,
+    pub search: Option<String>,
+    /// Defaults to relevance when searching and newest otherwise
+    pub sort: Option<ProductSort>,
+}
+
+impl ProductFilter {
+    pub fn from_params(params: &ProductListParams) -> Self {
+        Self {
+            categories: params
+                .category
+                .as_deref()
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(String::from)
+                .collect(),
+            min_price: params.min_price,
+            max_price: params.max_price,
+            in_stock: params.in_stock.unwrap_or(false),
+            search: params.search.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(String::from),
+            sort: ProductSort::parse(params.sort_by.as_deref(), params.sort_order.as_deref()),
+        }
+    }
+
+    fn order_clause(&self, searching: bool) -> &'static str {
+        let default = if searching { ProductSort::Relevance } else { ProductSort::Newest };
+
+        match self.sort.unwrap_or(default) {
+            ProductSort::Relevance if searching => "bm25(products_fts, 0.0, 10.0, 1.0), p.name",
+            ProductSort::Relevance | ProductSort::Newest => "p.created_at DESC",
+            ProductSort::PriceAsc => "p.price_cents ASC, p.name",
+            ProductSort::PriceDesc => "p.price_cents DESC, p.name",
+            ProductSort::NameAsc => "p.name ASC",
+            ProductSort::NameDesc => "p.name DESC",
+        }
+    }
+}
+
+/// One page of a product query
+#[derive(Debug, Default)]
+pub struct ProductPage {
+    pub products: Vec<Product>,
+    /// Only filled when the filter has a search query
+    pub highlights: Vec<SearchHighlight>,
+    pub total: u32,
+    pub facets: ProductFacets,
+}
+
+/// A facet whose own condition is left out while counting it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Facet {
+    Category,
+    Price,
+}
+
+/// Append the FROM and WHERE clauses shared by the page, count and facet queries
+fn push_filters(query: &mut QueryBuilder<'_, Sqlite>, filter: &ProductFilter, match_expr: Option<&str>, skip: Option<Facet>) {
+    query.push(" FROM products p");
+    if match_expr.is_some() {
+        query.push(" JOIN products_fts ON products_fts.product_id = p.id");
+    }
+
+    query.push(" WHERE p.is_active = 1");
+    if let Some(match_expr) = match_expr {
+        query.push(" AND products_fts MATCH ").push_bind(match_expr.to_string());
+    }
+
+    // A category matches its own products and those of every subcategory
+    if skip != Some(Facet::Category) && !filter.categories.is_empty() {
+        query.push(" AND p.category_id IN (WITH RECURSIVE subtree(id) AS (SELECT id FROM categories WHERE name IN (");
+        let mut categories = query.separated(", ");
+        for category in &filter.categories {
+            categories.push_bind(category.clone());
+        }
+        query.push(") OR slug IN (");
+        let mut categories = query.separated(", ");
+        for category in &filter.categories {
+            categories.push_bind(category.clone());
+        }
+        query.push(") UNION SELECT c.id FROM categories c JOIN subtree s ON c.parent_id = s.id) SELECT id FROM subtree)");
+    }
 
-        Ok((products, count.0 as u32))
+    if skip != Some(Facet::Price) {
+        if let Some(min_price) = filter.min_price {
+            query.push(" AND p.price_cents >= ").push_bind(min_price);
+        }
+        if let Some(max_price) = filter.max_price {
+            query.push(" AND p.price_cents < ").push_bind(max_price);
+        }
     }
 
+    if filter.in_stock {
+        query.push(" AND p.stock > (SELECT COALESCE(SUM(r.quantity), 0) FROM active_reservations r WHERE r.product_id = p.id)");
+    }
+}
+
+/// SQL mapping `p.price_cents` to its index in `PRICE_BUCKET_EDGES`
+fn price_bucket_expression() -> String {
+    let mut expr = String::from("CASE");
+    for (index, edge) in PRICE_BUCKET_EDGES.iter().enumerate() {
+        expr.push_str(&format!(" WHEN p.price_cents < {} THEN {}", edge, index));
+    }
+    expr.push_str(&format!(" ELSE {} END", PRICE_BUCKET_EDGES.len()));
+    expr
+}
+
+/// Every price bucket with its count from `(bucket index, count)` rows
+fn price_ranges(counts: &[(i64, i64)]) -> Vec<PriceRangeFacet> {
+    (0..=PRICE_BUCKET_EDGES.len())
+        .map(|index| PriceRangeFacet {
+            min: if index == 0 { 0 } else { PRICE_BUCKET_EDGES[index - 1] },
+            max: PRICE_BUCKET_EDGES.get(index).copied(),
+            count: counts
+                .iter()
+                .find(|(bucket, _)| *bucket == index as i64)
+                .map_or(0, |(_, count)| *count as u32),
+        })
+        .collect()
+}
+
+/// Turn free text into an FTS5 query: every word must match, each as a
+/// prefix so partially typed words still find results. Words are quoted so
+/// FTS5 operators and punctuation in the input are treated as plain text.
+fn fts_match_expression(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .take(MAX_SEARCH_TERMS)
+        .map(|term| format!("\"{}\"*", term))
+        .collect();
+
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+pub struct ProductRepository;
+
+impl ProductRepository {
     pub async fn get_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<Product>> {
-        let row: Option<(String, String, String, f64, i32, String, Option<String>, i32, String, String)> =
+        let row: Option<ProductRow> =
             sqlx::query_as(
-                "SELECT id, name, description, price, stock, category, image_url, is_active, created_at, updated_at
-                 FROM products WHERE id = ?",
+                &format!("SELECT {} FROM products p WHERE p.id = ?", PRODUCT_COLUMNS),
             )
             .bind(id.to_string())
             .fetch_optional(pool)
@@ -65,103 +219,359 @@ impl ProductRepository {
         }
     }
 
-    pub async fn search(
-        pool: &SqlitePool,
-        query: &str,
-        limit: u32,
-    ) -> Result<Vec<Product>> {
-        let search_pattern = format!("%{}%", query);
+    /// Run a filtered, sorted product query and return one page of results
+    /// with search highlights, the total match count and facet counts
+    pub async fn query(pool: &SqlitePool, filter: &ProductFilter, page: u32, limit: u32) -> Result<ProductPage> {
+        let match_expr = filter.search.as_deref().map(fts_match_expression);
+        let match_expr = match match_expr {
+            // Search text without any searchable words matches nothing
+            Some(None) => return Ok(ProductPage::default()),
+            Some(Some(expr)) => Some(expr),
+            None => None,
+        };
+        let match_expr = match_expr.as_deref();
+        // In i64 so a huge page number cannot overflow
+        let offset = (page.max(1) as i64 - 1) * limit as i64;
 
-        let rows: Vec<(String, String, String, f64, i32, String, Option<String>, i32, String, String)> =
-            sqlx::query_as(
-                "SELECT id, name, description, price, stock, category, image_url, is_active, created_at, updated_at
-                 FROM products WHERE is_active = 1 AND (name LIKE ? OR description LIKE ?) LIMIT ?",
-            )
-            .bind(&search_pattern)
-            .bind(&search_pattern)
-            .bind(limit as i64)
-            .fetch_all(pool)
-            .await?;
+        let rows: Vec<SearchRow> = {
+            let mut query = QueryBuilder::<Sqlite>::new(format!("SELECT {}", PRODUCT_COLUMNS));
+            if match_expr.is_some() {
+                query.push(", highlight(products_fts, 1, '<mark>', '</mark>'), snippet(products_fts, 2, '<mark>', '</mark>', '…', 24)");
+            } else {
+                query.push(", NULL, NULL");
+            }
+            push_filters(&mut query, filter, match_expr, None);
+            query.push(" ORDER BY ").push(filter.order_clause(match_expr.is_some()));
+            query.push(" LIMIT ").push_bind(limit as i64);
+            query.push(" OFFSET ").push_bind(offset);
+            query.build_query_as().fetch_all(pool).await?
+        };
 
-        let products: Vec<Product> = rows
-            .into_iter()
-            .filter_map(|row| Self::row_to_product(row).ok())
-            .collect();
+        let mut products = Vec::with_capacity(rows.len());
+        let mut highlights = Vec::new();
+        for row in rows {
+            let Ok((product, highlight)) = Self::row_to_search_result(row) else {
+                continue;
+            };
+            highlights.extend(highlight);
+            products.push(product);
+        }
 
-        Ok(products)
-    }
+        let total: (i64,) = {
+            let mut query = QueryBuilder::<Sqlite>::new("SELECT COUNT(*)");
+            push_filters(&mut query, filter, match_expr, None);
+            query.build_query_as().fetch_one(pool).await?
+        };
 
-    pub async fn filter_by_category(
-        pool: &SqlitePool,
-        category: &str,
-        page: u32,
-        limit: u32,
-    ) -> Result<(Vec<Product>, u32)> {
-        let offset = (page - 1) * limit;
+        let categories: Vec<(String, i64)> = {
+            let mut query = QueryBuilder::<Sqlite>::new("SELECT p.category, COUNT(*)");
+            push_filters(&mut query, filter, match_expr, Some(Facet::Category));
+            query.push(" GROUP BY p.category ORDER BY p.category");
+            query.build_query_as().fetch_all(pool).await?
+        };
 
-        let rows: Vec<(String, String, String, f64, i32, String, Option<String>, i32, String, String)> =
-            sqlx::query_as(
-                "SELECT id, name, description, price, stock, category, image_url, is_active, created_at, updated_at
-                 FROM products WHERE is_active = 1 AND category = ? ORDER BY created_at DESC LIMIT ? OFFSET ?",
-            )
-            .bind(category)
-            .bind(limit as i64)
-            .bind(offset as i64)
-            .fetch_all(pool)
-            .await?;
+        let buckets: Vec<(i64, i64)> = {
+            let mut query = QueryBuilder::<Sqlite>::new(format!("SELECT {}, COUNT(*)", price_bucket_expression()));
+            push_filters(&mut query, filter, match_expr, Some(Facet::Price));
+            query.push(" GROUP BY 1");
+            query.build_query_as().fetch_all(pool).await?
+        };
 
-        let products: Vec<Product> = rows
-            .into_iter()
-            .filter_map(|row| Self::row_to_product(row).ok())
-            .collect();
+        Ok(ProductPage {
+            products,
+            highlights,
+            total: total.0 as u32,
+            facets: ProductFacets {
+                categories: categories
+                    .into_iter()
+                    .map(|(category, count)| CategoryFacet { category, count: count as u32 })
+                    .collect(),
+                price_ranges: price_ranges(&buckets),
+            },
+        })
+    }
 
-        let count: (i64,) = sqlx::query_as(
-            "SELECT COUNT(*) FROM products WHERE is_active = 1 AND category = ?",
+    /// Rebuild the type-ahead index from the active catalog
+    pub async fn refresh_suggestions(pool: &SqlitePool, index: &SuggestionIndex) -> Result<()> {
+        let products: Vec<(String, String, i32)> =
+            sqlx::query_as("SELECT id, name, stock FROM products WHERE is_active = 1")
+                .fetch_all(pool)
+                .await?;
+
+        let categories: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT category, COUNT(*) FROM products WHERE is_active = 1 GROUP BY category",
         )
-        .bind(category)
-        .fetch_one(pool)
+        .fetch_all(pool)
         .await?;
 
-        Ok((products, count.0 as u32))
+        let mut suggestions = Vec::with_capacity(products.len() + categories.len());
+        for (id, name, stock) in products {
+            let suggestion = Suggestion {
+                text: name,
+                kind: SuggestionKind::Product,
+                product_id: Some(id.parse()?),
+            };
+            suggestions.push((suggestion, (stock > 0) as u32));
+        }
+        for (category, count) in categories {
+            let suggestion = Suggestion {
+                text: category,
+                kind: SuggestionKind::Category,
+                product_id: None,
+            };
+            suggestions.push((suggestion, count as u32));
+        }
+
+        index.rebuild(suggestions);
+        Ok(())
     }
 
-    pub async fn list_categories(pool: &SqlitePool) -> Result<Vec<String>> {
-        let rows: Vec<(String,)> = sqlx::query_as(
-            "SELECT DISTINCT category FROM products WHERE is_active = 1 ORDER BY category",
+    /// Insert a product; its initial stock enters the ledger as an import
+    pub async fn create(pool: &SqlitePool, req: &CreateProductRequest, created_by: Option<Uuid>) -> Result<Product> {
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO products (id, name, description, price_cents, currency, stock, reorder_threshold, category, image_url, is_active, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, 0, ?, ?, ?, 1, ?, ?)
+            "#,
         )
-        .fetch_all(pool)
+        .bind(id.to_string())
+        .bind(&req.name)
+        .bind(&req.description)
+        .bind(req.price.amount)
+        .bind(req.price.currency.code())
+        .bind(req.reorder_threshold)
+        .bind(&req.category)
+        .bind(&req.image_url)
+        .bind(now.to_rfc3339())
+        .bind(now.to_rfc3339())
+        .execute(&mut *tx)
         .await?;
 
-        Ok(rows.into_iter().map(|(c,)| c).collect())
+        if req.stock != 0 {
+            let import = StockChange {
+                product_id: id,
+                variant_id: None,
+                quantity: req.stock,
+                reason: StockMovementReason::Import,
+                note: Some("Initial stock".to_string()),
+                actor_id: created_by,
+                reference_id: None,
+            };
+            StockRepository::record(&mut *tx, &import).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(Product {
+            id,
+            name: req.name.clone(),
+            description: req.description.clone(),
+            price: req.price,
+            stock: req.stock,
+            reserved: 0,
+            reorder_threshold: req.reorder_threshold,
+            category: req.category.clone(),
+            image_url: req.image_url.clone(),
+            thumbnail_url: None,
+            is_active: true,
+            created_at: now,
+            updated_at: now,
+        })
     }
 
-    pub async fn update_stock(pool: &SqlitePool, id: Uuid, quantity_change: i32) -> Result<()> {
+    /// Apply a partial update; fields left as `None` keep their current value.
+    /// A new stock level is recorded in the ledger as an adjustment.
+    pub async fn update(
+        pool: &SqlitePool,
+        id: Uuid,
+        req: &UpdateProductRequest,
+        changed_by: Option<Uuid>,
+    ) -> Result<Option<Product>> {
+        let mut product = match Self::get_by_id(pool, id).await? {
+            Some(product) => product,
+            None => return Ok(None),
+        };
+
+        if let Some(name) = &req.name {
+            product.name = name.clone();
+        }
+        if let Some(description) = &req.description {
+            product.description = description.clone();
+        }
+        if let Some(price) = req.price {
+            product.price = price;
+        }
+        if let Some(category) = &req.category {
+            product.category = category.clone();
+        }
+        if let Some(image_url) = &req.image_url {
+            // A hand-set image has no generated thumbnail
+            product.image_url = Some(image_url.clone());
+            product.thumbnail_url = None;
+        }
+        if let Some(is_active) = req.is_active {
+            product.is_active = is_active;
+        }
+        if req.clear_reorder_threshold {
+            product.reorder_threshold = None;
+        }
+        if let Some(threshold) = req.reorder_threshold {
+            product.reorder_threshold = Some(threshold);
+        }
+        product.updated_at = Utc::now();
+
+        let mut tx = pool.begin().await?;
+
+        // Read the stock again inside the transaction so the ledger delta is
+        // taken against the current level
+        if let Some(stock) = req.stock {
+            let (current,): (i32,) = sqlx::query_as("SELECT stock FROM products WHERE id = ?")
+                .bind(id.to_string())
+                .fetch_one(&mut *tx)
+                .await?;
+
+            if stock != current {
+                let adjustment = StockChange {
+                    product_id: id,
+                    variant_id: None,
+                    quantity: stock - current,
+                    reason: StockMovementReason::Adjustment,
+                    note: Some(format!("Stock set to {} in product update", stock)),
+                    actor_id: changed_by,
+                    reference_id: None,
+                };
+                StockRepository::record(&mut *tx, &adjustment).await?;
+            }
+            product.stock = stock;
+        }
+
         sqlx::query(
-            "UPDATE products SET stock = stock + ?, updated_at = ? WHERE id = ?",
+            r#"
+            UPDATE products
+            SET name = ?, description = ?, price_cents = ?, currency = ?, reorder_threshold = ?, category = ?, image_url = ?,
+                thumbnail_url = ?, is_active = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&product.name)
+        .bind(&product.description)
+        .bind(product.price.amount)
+        .bind(product.price.currency.code())
+        .bind(product.reorder_threshold)
+        .bind(&product.category)
+        .bind(&product.image_url)
+        .bind(&product.thumbnail_url)
+        .bind(product.is_active as i32)
+        .bind(product.updated_at.to_rfc3339())
+        .bind(id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(product))
+    }
+
+    /// Soft delete: the row is kept so existing order items still resolve
+    pub async fn deactivate(pool: &SqlitePool, id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE products SET is_active = 0, updated_at = ? WHERE id = ? AND is_active = 1",
         )
-        .bind(quantity_change)
         .bind(Utc::now().to_rfc3339())
         .bind(id.to_string())
         .execute(pool)
         .await?;
 
-        Ok(())
+        Ok(result.rows_affected() > 0)
+    }
+
+
+    fn row_to_search_result(row: SearchRow) -> Result<(Product, Option<SearchHighlight>)> {
+        let (id, name, description, price_cents, currency, stock, reserved, reorder_threshold, category, image_url, thumbnail_url, is_active,
+            created_at, updated_at, name_highlight, snippet) = row;
+        let product = Self::row_to_product((
+            id, name, description, price_cents, currency, stock, reserved, reorder_threshold, category, image_url, thumbnail_url, is_active,
+            created_at, updated_at,
+        ))?;
+
+        let highlight = name_highlight.zip(snippet).map(|(name, snippet)| SearchHighlight {
+            product_id: product.id,
+            name,
+            snippet,
+        });
+
+        Ok((product, highlight))
     }
 
-    fn row_to_product(
-        row: (String, String, String, f64, i32, String, Option<String>, i32, String, String),
-    ) -> Result<Product> {
+    fn row_to_product(row: ProductRow) -> Result<Product> {
+        let currency = Currency::new(&row.4)
+            .ok_or_else(|| anyhow::anyhow!("Invalid currency code: {}", row.4))?;
+
         Ok(Product {
             id: row.0.parse()?,
             name: row.1,
             description: row.2,
-            price: row.3,
-            stock: row.4,
-            category: row.5,
-            image_url: row.6,
-            is_active: row.7 == 1,
-            created_at: chrono::DateTime::parse_from_rfc3339(&row.8)?.with_timezone(&Utc),
-            updated_at: chrono::DateTime::parse_from_rfc3339(&row.9)?.with_timezone(&Utc),
+            price: Money::new(row.3, currency),
+            stock: row.5,
+            reserved: row.6,
+            reorder_threshold: row.7,
+            category: row.8,
+            image_url: row.9,
+            thumbnail_url: row.10,
+            is_active: row.11 == 1,
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.12)?.with_timezone(&Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.13)?.with_timezone(&Utc),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_expression_quotes_prefix_terms() {
+        assert_eq!(fts_match_expression("wireless head").as_deref(), Some("\"wireless\"* \"head\"*"));
+        assert_eq!(fts_match_expression("  Café  ").as_deref(), Some("\"Café\"*"));
+    }
+
+    #[test]
+    fn price_ranges_cover_every_bucket() {
+        let ranges = price_ranges(&[(1, 3), (4, 2)]);
+
+        assert_eq!(ranges.len(), PRICE_BUCKET_EDGES.len() + 1);
+        assert_eq!(ranges[0], PriceRangeFacet { min: 0, max: Some(2_500), count: 0 });
+        assert_eq!(ranges[1], PriceRangeFacet { min: 2_500, max: Some(5_000), count: 3 });
+        assert_eq!(ranges[4], PriceRangeFacet { min: 20_000, max: None, count: 2 });
+    }
+
+    #[test]
+    fn filter_from_params_splits_categories_and_sort() {
+        let params = ProductListParams {
+            category: Some("Electronics, Office,".to_string()),
+            search: Some("   ".to_string()),
+            sort_by: Some("price".to_string()),
+            sort_order: Some("desc".to_string()),
+            ..Default::default()
+        };
+        let filter = ProductFilter::from_params(&params);
+
+        assert_eq!(filter.categories, vec!["Electronics", "Office"]);
+        assert_eq!(filter.search, None);
+        assert_eq!(filter.sort, Some(ProductSort::PriceDesc));
+        assert_eq!(filter.order_clause(false), "p.price_cents DESC, p.name");
+    }
+
+    #[test]
+    fn match_expression_neutralizes_fts_syntax() {
+        assert_eq!(
+            fts_match_expression("name:\"usb\" OR 100%_cotton*").as_deref(),
+            Some("\"name\"* \"usb\"* \"OR\"* \"100\"* \"cotton\"*"),
+        );
+        assert_eq!(fts_match_expression("%_ -\"()"), None);
+        assert_eq!(fts_match_expression(""), None);
+    }
+}
@@ -0,0 +1,205 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use uuid::Uuid;
+use shared::StockMovementReason;
+use super::{CouponRepository, StockChange, StockRepository};
+
+/// (product_id, variant_id, quantity)
+type HoldRow = (String, Option<String>, i32);
+
+pub struct ReservationRepository;
+
+impl ReservationRepository {
+    /// Hold stock for one line of a new order until `expires_at`. The caller
+    /// has checked that enough stock is available.
+    pub(super) async fn hold_in_tx(
+        tx: &mut Transaction<'_, Sqlite>,
+        order_id: Uuid,
+        product_id: Uuid,
+        variant_id: Option<Uuid>,
+        quantity: i32,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+
+        sqlx::query(
+            r#"
+            INSERT INTO inventory_reservations (id, order_id, product_id, variant_id, quantity, status, expires_at, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, 'active', ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(order_id.to_string())
+        .bind(product_id.to_string())
+        .bind(variant_id.map(|id| id.to_string()))
+        .bind(quantity)
+        .bind(expires_at.to_rfc3339())
+        .bind(&now)
+        .bind(&now)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// When the holds of a pending order run out, if they are still active
+    pub async fn expires_at(pool: &SqlitePool, order_id: Uuid) -> Result<Option<DateTime<Utc>>> {
+        let row: Option<(Option<String>,)> = sqlx::query_as(
+            "SELECT MIN(expires_at) FROM active_reservations WHERE order_id = ?",
+        )
+        .bind(order_id.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+        match row.and_then(|(expires_at,)| expires_at) {
+            Some(expires_at) => Ok(Some(DateTime::parse_from_rfc3339(&expires_at)?.with_timezone(&Utc))),
+            None => Ok(None),
+        }
+    }
+
+    /// Extend the holds of a pending order before charging it, taking the
+    /// stock and any coupon use again if they have expired. Fails with
+    /// "Insufficient stock" if it has since been sold to someone else, or
+    /// "cannot be used" if the coupon has been used up.
+    pub async fn renew(pool: &SqlitePool, order_id: Uuid, hold_for: Duration) -> Result<()> {
+        let mut tx = pool.begin().await?;
+
+        // On-hand stock less what other orders hold, per unconverted line
+        let lines: Vec<(String, i32)> = sqlx::query_as(
+            r#"
+            SELECT
+                CASE WHEN r.variant_id IS NULL THEN p.name ELSE p.name || ' (' || v.title || ')' END,
+                COALESCE(v.stock, p.stock) - r.quantity - (
+                    SELECT COALESCE(SUM(a.quantity), 0) FROM active_reservations a
+                    WHERE a.order_id != r.order_id
+                      AND a.product_id = r.product_id
+                      AND (r.variant_id IS NULL OR a.variant_id = r.variant_id)
+                )
+            FROM inventory_reservations r
+            JOIN products p ON p.id = r.product_id
+            LEFT JOIN product_variants v ON v.id = r.variant_id
+            WHERE r.order_id = ? AND r.status IN ('active', 'expired')
+            "#,
+        )
+        .bind(order_id.to_string())
+        .fetch_all(&mut *tx)
+        .await?;
+
+        if let Some((name, _)) = lines.iter().find(|(_, left)| *left < 0) {
+            anyhow::bail!("Insufficient stock for {}: it sold out while the order was unpaid", name);
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE inventory_reservations SET status = 'active', expires_at = ?, updated_at = ?
+            WHERE order_id = ? AND status IN ('active', 'expired')
+            "#,
+        )
+        .bind((Utc::now() + hold_for).to_rfc3339())
+        .bind(Utc::now().to_rfc3339())
+        .bind(order_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        CouponRepository::reclaim_in_tx(&mut tx, order_id, true).await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Turn a paid order's holds into sales in the stock ledger. Holds that
+    /// expired are converted too: the customer has paid, so the sale stands,
+    /// and so does the coupon use given up on expiry.
+    pub(super) async fn convert_in_tx(
+        tx: &mut Transaction<'_, Sqlite>,
+        order_id: Uuid,
+        changed_by: Option<Uuid>,
+    ) -> Result<()> {
+        let holds: Vec<HoldRow> = sqlx::query_as(
+            "SELECT product_id, variant_id, quantity FROM inventory_reservations WHERE order_id = ? AND status IN ('active', 'expired')",
+        )
+        .bind(order_id.to_string())
+        .fetch_all(&mut **tx)
+        .await?;
+
+        for (product_id, variant_id, quantity) in holds {
+            let sale = StockChange {
+                product_id: product_id.parse()?,
+                variant_id: variant_id.map(|id| id.parse()).transpose()?,
+                quantity: -quantity,
+                reason: StockMovementReason::Sale,
+                note: None,
+                actor_id: changed_by,
+                reference_id: Some(order_id),
+            };
+            StockRepository::record(&mut **tx, &sale).await?;
+        }
+
+        CouponRepository::reclaim_in_tx(tx, order_id, false).await?;
+
+        Self::set_status_in_tx(tx, order_id, "converted").await
+    }
+
+    /// Release a cancelled order's holds. Returns true if the order's stock
+    /// had already been taken and must be put back: it was paid, or placed
+    /// before reservations existed.
+    pub(super) async fn release_in_tx(tx: &mut Transaction<'_, Sqlite>, order_id: Uuid) -> Result<bool> {
+        let (total, converted): (i64, i64) = sqlx::query_as(
+            "SELECT COUNT(*), COALESCE(SUM(status = 'converted'), 0) FROM inventory_reservations WHERE order_id = ?",
+        )
+        .bind(order_id.to_string())
+        .fetch_one(&mut **tx)
+        .await?;
+
+        Self::set_status_in_tx(tx, order_id, "released").await?;
+
+        Ok(total == 0 || converted > 0)
+    }
+
+    /// Mark holds past their expiry as expired and give back the coupon uses
+    /// of their orders. Returns how many holds expired.
+    pub async fn expire(pool: &SqlitePool) -> Result<u64> {
+        let mut tx = pool.begin().await?;
+
+        let orders: Vec<(String,)> = sqlx::query_as(
+            "SELECT DISTINCT order_id FROM inventory_reservations WHERE status = 'active' AND julianday(expires_at) <= julianday('now')",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE inventory_reservations SET status = 'expired', updated_at = ?
+            WHERE status = 'active' AND julianday(expires_at) <= julianday('now')
+            "#,
+        )
+        .bind(Utc::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+        for (order_id,) in orders {
+            CouponRepository::release_in_tx(&mut tx, order_id.parse()?).await?;
+        }
+
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Move the order's unconverted holds to `status`
+    async fn set_status_in_tx(tx: &mut Transaction<'_, Sqlite>, order_id: Uuid, status: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE inventory_reservations SET status = ?, updated_at = ?
+            WHERE order_id = ? AND status IN ('active', 'expired')
+            "#,
+        )
+        .bind(status)
+        .bind(Utc::now().to_rfc3339())
+        .bind(order_id.to_string())
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+}
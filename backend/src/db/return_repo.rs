@@ -0,0 +1,440 @@
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+use uuid::Uuid;
+use super::{OrderRepository, StockChange, StockRepository};
+use shared::{Currency, Money, OrderStatus, ReturnItem, ReturnItemRequest, ReturnRequest, ReturnStatus, StockMovementReason};
+
+/// (id, order_id, user_id, status, reason, refund_cents, currency, restocked, refund_id, completed_at, admin_note, reviewed_by, created_at, updated_at)
+type ReturnRow = (
+    String, String, String, String, String, i64, String, i32,
+    Option<String>, Option<String>, Option<String>, Option<String>, String, String,
+);
+
+/// (order_item_id, product_id, product_name, quantity, refund_cents)
+type ReturnItemRow = (String, String, String, i32, i64);
+
+const RETURN_COLUMNS: &str = "id, order_id, user_id, status, reason, refund_cents, currency, restocked, refund_id, completed_at, admin_note, reviewed_by, created_at, updated_at";
+
+/// The part of an order line's discount that falls on units `returned + 1`
+/// to `returned + quantity`. Shares of successive returns add up to exactly
+/// the line's discount once every unit is back.
+fn discount_share(discount: i64, ordered: i32, returned: i64, quantity: i32) -> i64 {
+    if ordered <= 0 {
+        return 0;
+    }
+    let ordered = ordered as i64;
+    discount * (returned + quantity as i64) / ordered - discount * returned / ordered
+}
+
+pub struct ReturnRepository;
+
+impl ReturnRepository {
+    /// Open a return for items of an order. Quantities are checked against
+    /// what was ordered minus what is already requested or approved for return.
+    pub async fn create(
+        pool: &SqlitePool,
+        order_id: Uuid,
+        user_id: Uuid,
+        reason: &str,
+        items: &[ReturnItemRequest],
+    ) -> Result<ReturnRequest> {
+        let mut tx = pool.begin().await?;
+
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+
+        let (currency,): (String,) = sqlx::query_as("SELECT currency FROM orders WHERE id = ?")
+            .bind(order_id.to_string())
+            .fetch_one(&mut *tx)
+            .await?;
+        let currency = Currency::new(&currency)
+            .ok_or_else(|| anyhow::anyhow!("Invalid currency code: {}", currency))?;
+
+        let mut return_items = Vec::new();
+        for item in items {
+            let row: Option<(String, String, i32, i64, i64)> = sqlx::query_as(
+                "SELECT product_id, product_name, quantity, price_cents, discount_cents FROM order_items WHERE id = ? AND order_id = ?",
+            )
+            .bind(item.order_item_id.to_string())
+            .bind(order_id.to_string())
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            let (product_id, product_name, ordered, price_cents, discount_cents) = row.ok_or_else(|| {
+                anyhow::anyhow!("Order item {} not found in this order", item.order_item_id)
+            })?;
+
+            let (already_returned,): (i64,) = sqlx::query_as(
+                r#"
+                SELECT COALESCE(SUM(ri.quantity), 0)
+                FROM return_items ri
+                JOIN return_requests r ON ri.return_id = r.id
+                WHERE ri.order_item_id = ? AND r.status IN ('requested', 'approved')
+                "#,
+            )
+            .bind(item.order_item_id.to_string())
+            .fetch_one(&mut *tx)
+            .await?;
+
+            let eligible = ordered as i64 - already_returned;
+            if item.quantity as i64 > eligible {
+                return Err(anyhow::anyhow!(
+                    "Cannot return {} of {}: only {} eligible for return",
+                    item.quantity, product_name, eligible
+                ));
+            }
+
+            // Refund what was paid: the price less these units' share of any coupon discount
+            let discount = discount_share(discount_cents, ordered, already_returned, item.quantity);
+            let refund_amount = Money::new(price_cents, currency)
+                .checked_mul(item.quantity as i64)?
+                .checked_sub(Money::new(discount, currency))?;
+            return_items.push(ReturnItem {
+                order_item_id: item.order_item_id,
+                product_id: product_id.parse()?,
+                product_name,
+                quantity: item.quantity,
+                refund_amount,
+            });
+        }
+
+        let refund_amount = Money::checked_sum(currency, return_items.iter().map(|i| i.refund_amount))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO return_requests (id, order_id, user_id, status, reason, refund_cents, currency, created_at, updated_at)
+            VALUES (?, ?, ?, 'requested', ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(id.to_string())
+        .bind(order_id.to_string())
+        .bind(user_id.to_string())
+        .bind(reason)
+        .bind(refund_amount.amount)
+        .bind(currency.code())
+        .bind(now.to_rfc3339())
+        .bind(now.to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+        for item in &return_items {
+            sqlx::query(
+                "INSERT INTO return_items (return_id, order_item_id, quantity, refund_cents) VALUES (?, ?, ?, ?)",
+            )
+            .bind(id.to_string())
+            .bind(item.order_item_id.to_string())
+            .bind(item.quantity)
+            .bind(item.refund_amount.amount)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(ReturnRequest {
+            id,
+            order_id,
+            user_id,
+            status: ReturnStatus::Requested,
+            reason: reason.to_string(),
+            items: return_items,
+            refund_amount,
+            restocked: false,
+            refund_id: None,
+            completed_at: None,
+            admin_note: None,
+            reviewed_by: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    pub async fn get(pool: &SqlitePool, id: Uuid) -> Result<Option<ReturnRequest>> {
+        let row: Option<ReturnRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM return_requests WHERE id = ?",
+            RETURN_COLUMNS
+        ))
+        .bind(id.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(Self::load(pool, row).await?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn list_by_order(pool: &SqlitePool, order_id: Uuid) -> Result<Vec<ReturnRequest>> {
+        let rows: Vec<ReturnRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM return_requests WHERE order_id = ? ORDER BY created_at DESC",
+            RETURN_COLUMNS
+        ))
+        .bind(order_id.to_string())
+        .fetch_all(pool)
+        .await?;
+
+        let mut returns = Vec::new();
+        for row in rows {
+            returns.push(Self::load(pool, row).await?);
+        }
+
+        Ok(returns)
+    }
+
+    /// All return requests, oldest first so the review queue is worked in order
+    pub async fn list_all(pool: &SqlitePool, status: Option<&ReturnStatus>) -> Result<Vec<ReturnRequest>> {
+        let rows: Vec<ReturnRow> = {
+            let mut query = QueryBuilder::<Sqlite>::new(format!("SELECT {} FROM return_requests", RETURN_COLUMNS));
+            if let Some(status) = status {
+                query.push(" WHERE status = ").push_bind(status.as_str());
+            }
+            query.push(" ORDER BY created_at ASC");
+            query.build_query_as().fetch_all(pool).await?
+        };
+
+        let mut returns = Vec::new();
+        for row in rows {
+            returns.push(Self::load(pool, row).await?);
+        }
+
+        Ok(returns)
+    }
+
+    pub async fn reject(pool: &SqlitePool, id: Uuid, reviewed_by: Uuid, note: Option<&str>) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE return_requests SET status = 'rejected', admin_note = ?, reviewed_by = ?, updated_at = ?
+            WHERE id = ? AND status = 'requested'
+            "#,
+        )
+        .bind(note)
+        .bind(reviewed_by.to_string())
+        .bind(Utc::now().to_rfc3339())
+        .bind(id.to_string())
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Mark a request approved before the refund is issued, so two reviewers
+    /// can't both refund it. Returns false if it was already reviewed.
+    pub async fn claim_approval(
+        pool: &SqlitePool,
+        id: Uuid,
+        reviewed_by: Uuid,
+        note: Option<&str>,
+        restock: bool,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE return_requests SET status = 'approved', restocked = ?, admin_note = ?, reviewed_by = ?, updated_at = ?
+            WHERE id = ? AND status = 'requested'
+            "#,
+        )
+        .bind(restock as i32)
+        .bind(note)
+        .bind(reviewed_by.to_string())
+        .bind(Utc::now().to_rfc3339())
+        .bind(id.to_string())
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Undo `claim_approval` when the refund could not be issued
+    pub async fn release_approval(pool: &SqlitePool, id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE return_requests SET status = 'requested', restocked = 0, admin_note = NULL, reviewed_by = NULL, updated_at = ?
+            WHERE id = ? AND status = 'approved' AND refund_id IS NULL AND completed_at IS NULL
+            "#,
+        )
+        .bind(Utc::now().to_rfc3339())
+        .bind(id.to_string())
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record the provider's refund as soon as it is issued, so a failed
+    /// `complete_approval` can be retried without refunding twice
+    pub async fn record_refund(pool: &SqlitePool, id: Uuid, refund_id: &str) -> Result<()> {
+        sqlx::query("UPDATE return_requests SET refund_id = ?, updated_at = ? WHERE id = ?")
+            .bind(refund_id)
+            .bind(Utc::now().to_rfc3339())
+            .bind(id.to_string())
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Apply the effects of an approved, refunded return in one transaction:
+    /// restock if requested, add to the payment's refunded total and move the
+    /// order to PartiallyRefunded or, once every item is back, Refunded.
+    /// Does nothing if the approval was already completed.
+    pub async fn complete_approval(
+        pool: &SqlitePool,
+        return_request: &ReturnRequest,
+        payment_id: Option<Uuid>,
+        changed_by: Option<Uuid>,
+    ) -> Result<()> {
+        let mut tx = pool.begin().await?;
+
+        let now = Utc::now().to_rfc3339();
+        let result = sqlx::query(
+            "UPDATE return_requests SET completed_at = ?, updated_at = ? WHERE id = ? AND status = 'approved' AND completed_at IS NULL",
+        )
+        .bind(&now)
+        .bind(&now)
+        .bind(return_request.id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(());
+        }
+
+        if return_request.restocked {
+            for item in &return_request.items {
+                let (variant_id,): (Option<String>,) = sqlx::query_as("SELECT variant_id FROM order_items WHERE id = ?")
+                    .bind(item.order_item_id.to_string())
+                    .fetch_one(&mut *tx)
+                    .await?;
+
+                let restock = StockChange {
+                    product_id: item.product_id,
+                    variant_id: variant_id.map(|id| id.parse()).transpose()?,
+                    quantity: item.quantity,
+                    reason: StockMovementReason::Return,
+                    note: None,
+                    actor_id: changed_by,
+                    reference_id: Some(return_request.id),
+                };
+                StockRepository::record(&mut *tx, &restock).await?;
+            }
+        }
+
+        if let Some(payment_id) = payment_id {
+            sqlx::query(
+                r#"
+                UPDATE payments
+                SET refunded_cents = refunded_cents + ?,
+                    status = CASE WHEN refunded_cents + ? >= amount_cents THEN 'refunded' ELSE status END,
+                    updated_at = ?
+                WHERE id = ?
+                "#,
+            )
+            .bind(return_request.refund_amount.amount)
+            .bind(return_request.refund_amount.amount)
+            .bind(Utc::now().to_rfc3339())
+            .bind(payment_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // Fully refunded once no item has more ordered than approved for return
+        let (outstanding,): (i64,) = sqlx::query_as(
+            r#"
+            SELECT COUNT(*) FROM order_items oi
+            WHERE oi.order_id = ?
+              AND oi.quantity > (
+                  SELECT COALESCE(SUM(ri.quantity), 0)
+                  FROM return_items ri
+                  JOIN return_requests r ON ri.return_id = r.id
+                  WHERE ri.order_item_id = oi.id AND r.status = 'approved'
+              )
+            "#,
+        )
+        .bind(return_request.order_id.to_string())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let target = if outstanding == 0 {
+            OrderStatus::Refunded
+        } else {
+            OrderStatus::PartiallyRefunded
+        };
+
+        let (current,): (String,) = sqlx::query_as("SELECT status FROM orders WHERE id = ?")
+            .bind(return_request.order_id.to_string())
+            .fetch_one(&mut *tx)
+            .await?;
+        let current = OrderStatus::parse(&current).unwrap_or_default();
+
+        if current != target {
+            OrderRepository::transition(&mut tx, return_request.order_id, &current, &target, changed_by).await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn load(pool: &SqlitePool, row: ReturnRow) -> Result<ReturnRequest> {
+        let currency = Currency::new(&row.6)
+            .ok_or_else(|| anyhow::anyhow!("Invalid currency code: {}", row.6))?;
+
+        let item_rows: Vec<ReturnItemRow> = sqlx::query_as(
+            r#"
+            SELECT ri.order_item_id, oi.product_id, oi.product_name, ri.quantity, ri.refund_cents
+            FROM return_items ri
+            JOIN order_items oi ON ri.order_item_id = oi.id
+            WHERE ri.return_id = ?
+            "#,
+        )
+        .bind(&row.0)
+        .fetch_all(pool)
+        .await?;
+
+        let items = item_rows
+            .into_iter()
+            .map(|(order_item_id, product_id, product_name, quantity, refund_cents)| {
+                Ok(ReturnItem {
+                    order_item_id: order_item_id.parse()?,
+                    product_id: product_id.parse()?,
+                    product_name,
+                    quantity,
+                    refund_amount: Money::new(refund_cents, currency),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(ReturnRequest {
+            id: row.0.parse()?,
+            order_id: row.1.parse()?,
+            user_id: row.2.parse()?,
+            status: ReturnStatus::parse(&row.3)
+                .ok_or_else(|| anyhow::anyhow!("Invalid return status: {}", row.3))?,
+            reason: row.4,
+            items,
+            refund_amount: Money::new(row.5, currency),
+            restocked: row.7 == 1,
+            refund_id: row.8,
+            completed_at: row.9
+                .map(|at| chrono::DateTime::parse_from_rfc3339(&at).map(|at| at.with_timezone(&Utc)))
+                .transpose()?,
+            admin_note: row.10,
+            reviewed_by: row.11.and_then(|u| u.parse().ok()),
+            created_at: chrono::DateTime::parse_from_rfc3339(&row.12)?.with_timezone(&Utc),
+            updated_at: chrono::DateTime::parse_from_rfc3339(&row.13)?.with_timezone(&Utc),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discount_shares_add_up_to_the_line_discount() {
+        // 100 cents off 3 units, returned one at a time
+        let shares: Vec<i64> = (0..3).map(|returned| discount_share(100, 3, returned, 1)).collect();
+        assert_eq!(shares, vec![33, 33, 34]);
+        assert_eq!(discount_share(100, 3, 0, 3), 100);
+        assert_eq!(discount_share(0, 3, 1, 2), 0);
+    }
+}
@@ -0,0 +1,191 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use uuid::Uuid;
+use crate::auth::hash_token;
+
+/// Result of presenting a refresh token
+#[derive(Debug, Clone, PartialEq)]
+pub enum RefreshOutcome {
+    /// The token was exchanged; the caller hands out the new one
+    Rotated { user_id: Uuid, family_id: Uuid, mfa: bool },
+    /// An already-rotated token was presented again; its family is now revoked
+    Reused,
+    /// Unknown, expired or revoked token
+    Invalid,
+}
+
+/// (id, family_id, user_id, expires_at, rotated_at, revoked_at, mfa)
+type SessionRow = (String, String, String, String, Option<String>, Option<String>, bool);
+
+pub struct SessionRepository;
+
+impl SessionRepository {
+    /// Start a new session family for a fresh login and store the hash of its
+    /// first refresh token. Returns the family id, used as the access token `jti`.
+    /// `mfa` records whether the login passed a second factor.
+    pub async fn create(
+        pool: &SqlitePool,
+        user_id: Uuid,
+        refresh_token: &str,
+        ttl: Duration,
+        mfa: bool,
+    ) -> Result<Uuid> {
+        let family_id = Uuid::new_v4();
+        let mut tx = pool.begin().await?;
+
+        // Expired rows are no longer needed for reuse detection
+        sqlx::query("DELETE FROM sessions WHERE expires_at < ?")
+            .bind(Utc::now().to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+
+        Self::insert(&mut tx, family_id, user_id, refresh_token, ttl, mfa).await?;
+
+        tx.commit().await?;
+        Ok(family_id)
+    }
+
+    /// Exchange `presented` for `replacement` in the same family. Presenting a
+    /// token that was already rotated means it leaked, so the whole family is
+    /// revoked and every access token issued under it stops working.
+    pub async fn rotate(
+        pool: &SqlitePool,
+        presented: &str,
+        replacement: &str,
+        ttl: Duration,
+    ) -> Result<RefreshOutcome> {
+        let mut tx = pool.begin().await?;
+        let now = Utc::now();
+
+        let row: Option<SessionRow> = sqlx::query_as(
+            "SELECT id, family_id, user_id, expires_at, rotated_at, revoked_at, mfa FROM sessions WHERE token_hash = ?",
+        )
+        .bind(hash_token(presented))
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some((id, family_id, user_id, expires_at, rotated_at, revoked_at, mfa)) = row else {
+            return Ok(RefreshOutcome::Invalid);
+        };
+        let family_id: Uuid = family_id.parse()?;
+        let user_id: Uuid = user_id.parse()?;
+
+        if revoked_at.is_some() {
+            return Ok(RefreshOutcome::Invalid);
+        }
+
+        if rotated_at.is_some() {
+            Self::revoke_family_in_tx(&mut tx, family_id).await?;
+            tx.commit().await?;
+            tracing::warn!("Refresh token reuse detected for user {}; revoked session {}", user_id, family_id);
+            return Ok(RefreshOutcome::Reused);
+        }
+
+        if DateTime::parse_from_rfc3339(&expires_at)?.with_timezone(&Utc) <= now {
+            return Ok(RefreshOutcome::Invalid);
+        }
+
+        // Compare-and-set so two concurrent refreshes cannot both win
+        let result = sqlx::query(
+            "UPDATE sessions SET rotated_at = ? WHERE id = ? AND rotated_at IS NULL AND revoked_at IS NULL",
+        )
+        .bind(now.to_rfc3339())
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            Self::revoke_family_in_tx(&mut tx, family_id).await?;
+            tx.commit().await?;
+            return Ok(RefreshOutcome::Reused);
+        }
+
+        Self::insert(&mut tx, family_id, user_id, replacement, ttl, mfa).await?;
+
+        tx.commit().await?;
+        Ok(RefreshOutcome::Rotated { user_id, family_id, mfa })
+    }
+
+    /// Whether access tokens issued under this family are still honoured
+    pub async fn is_active(pool: &SqlitePool, family_id: Uuid) -> Result<bool> {
+        let row: Option<(i64,)> = sqlx::query_as(
+            r#"
+            SELECT 1 FROM sessions
+            WHERE family_id = ? AND rotated_at IS NULL AND revoked_at IS NULL AND expires_at > ?
+            LIMIT 1
+            "#,
+        )
+        .bind(family_id.to_string())
+        .bind(Utc::now().to_rfc3339())
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row.is_some())
+    }
+
+    /// Revoke one session (logout on this device)
+    pub async fn revoke_family(pool: &SqlitePool, family_id: Uuid) -> Result<()> {
+        let mut tx = pool.begin().await?;
+        Self::revoke_family_in_tx(&mut tx, family_id).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Revoke every session of a user (logout everywhere)
+    pub async fn revoke_all_for_user(pool: &SqlitePool, user_id: Uuid) -> Result<()> {
+        let mut tx = pool.begin().await?;
+        Self::revoke_all_for_user_in_tx(&mut tx, user_id).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub(super) async fn revoke_all_for_user_in_tx(tx: &mut Transaction<'_, Sqlite>, user_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE sessions SET revoked_at = ? WHERE user_id = ? AND revoked_at IS NULL")
+            .bind(Utc::now().to_rfc3339())
+            .bind(user_id.to_string())
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn revoke_family_in_tx(tx: &mut Transaction<'_, Sqlite>, family_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE sessions SET revoked_at = ? WHERE family_id = ? AND revoked_at IS NULL")
+            .bind(Utc::now().to_rfc3339())
+            .bind(family_id.to_string())
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn insert(
+        tx: &mut Transaction<'_, Sqlite>,
+        family_id: Uuid,
+        user_id: Uuid,
+        refresh_token: &str,
+        ttl: Duration,
+        mfa: bool,
+    ) -> Result<()> {
+        let now = Utc::now();
+
+        sqlx::query(
+            r#"
+            INSERT INTO sessions (id, family_id, user_id, token_hash, created_at, expires_at, mfa)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(family_id.to_string())
+        .bind(user_id.to_string())
+        .bind(hash_token(refresh_token))
+        .bind(now.to_rfc3339())
+        .bind((now + ttl).to_rfc3339())
+        .bind(mfa)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::{Executor, Sqlite, SqlitePool};
+use uuid::Uuid;
+use shared::{StockMovement, StockMovementReason};
+
+/// (id, product_id, variant_id, quantity, reason, note, actor_id, reference_id, created_at)
+type MovementRow = (String, String, Option<String>, i32, String, Option<String>, Option<String>, Option<String>, String);
+
+const MOVEMENT_COLUMNS: &str = "id, product_id, variant_id, quantity, reason, note, actor_id, reference_id, created_at";
+
+/// A stock change to record in the ledger
+pub struct StockChange {
+    pub product_id: Uuid,
+    /// Set for products sold by variant
+    pub variant_id: Option<Uuid>,
+    /// Signed change in units; must not be zero
+    pub quantity: i32,
+    pub reason: StockMovementReason,
+    pub note: Option<String>,
+    pub actor_id: Option<Uuid>,
+    pub reference_id: Option<Uuid>,
+}
+
+/// A product or variant whose stock differs from its ledger
+#[derive(Debug)]
+pub struct StockDrift {
+    pub product_id: Uuid,
+    /// Set when the drift is in a variant's stock
+    pub variant_id: Option<Uuid>,
+    /// Product name, with the variant title for variants
+    pub name: String,
+    pub stock: i64,
+    pub ledger: i64,
+}
+
+pub struct StockRepository;
+
+impl StockRepository {
+    /// Append a movement to the ledger. A trigger applies it to the variant or
+    /// product stock, so this is the only way stock changes. Accepts a pool or
+    /// an open transaction.
+    pub async fn record<'e, E>(executor: E, change: &StockChange) -> Result<StockMovement>
+    where
+        E: Executor<'e, Database = Sqlite>,
+    {
+        let movement = StockMovement {
+            id: Uuid::new_v4(),
+            product_id: change.product_id,
+            variant_id: change.variant_id,
+            quantity: change.quantity,
+            reason: change.reason,
+            note: change.note.clone(),
+            actor_id: change.actor_id,
+            reference_id: change.reference_id,
+            created_at: Utc::now(),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO stock_movements (id, product_id, variant_id, quantity, reason, note, actor_id, reference_id, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(movement.id.to_string())
+        .bind(movement.product_id.to_string())
+        .bind(movement.variant_id.map(|id| id.to_string()))
+        .bind(movement.quantity)
+        .bind(movement.reason.as_str())
+        .bind(&movement.note)
+        .bind(movement.actor_id.map(|id| id.to_string()))
+        .bind(movement.reference_id.map(|id| id.to_string()))
+        .bind(movement.created_at.to_rfc3339())
+        .execute(executor)
+        .await?;
+
+        Ok(movement)
+    }
+
+    /// Apply a manual adjustment unless it would take stock below zero.
+    /// Returns the movement and the resulting on-hand stock.
+    pub async fn adjust(pool: &SqlitePool, change: &StockChange) -> Result<(StockMovement, i32)> {
+        let mut tx = pool.begin().await?;
+
+        let (stock,): (i32,) = match change.variant_id {
+            Some(variant_id) => {
+                sqlx::query_as("SELECT stock FROM product_variants WHERE id = ?")
+                    .bind(variant_id.to_string())
+                    .fetch_one(&mut *tx)
+                    .await?
+            }
+            None => {
+                sqlx::query_as("SELECT stock FROM products WHERE id = ?")
+                    .bind(change.product_id.to_string())
+                    .fetch_one(&mut *tx)
+                    .await?
+            }
+        };
+
+        if stock + change.quantity < 0 {
+            anyhow::bail!("Insufficient stock: cannot remove {} with {} on hand", -change.quantity, stock);
+        }
+
+        let movement = Self::record(&mut *tx, change).await?;
+
+        tx.commit().await?;
+        Ok((movement, stock + change.quantity))
+    }
+
+    /// A product's ledger, newest first, optionally for one variant
+    pub async fn list(
+        pool: &SqlitePool,
+        product_id: Uuid,
+        variant_id: Option<Uuid>,
+        limit: u32,
+    ) -> Result<Vec<StockMovement>> {
+        let rows: Vec<MovementRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT {} FROM stock_movements
+            WHERE product_id = ? AND (? IS NULL OR variant_id = ?)
+            ORDER BY created_at DESC, rowid DESC
+            LIMIT ?
+            "#,
+            MOVEMENT_COLUMNS
+        ))
+        .bind(product_id.to_string())
+        .bind(variant_id.map(|id| id.to_string()))
+        .bind(variant_id.map(|id| id.to_string()))
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_movement).collect()
+    }
+
+    /// Variants, and products without variants, whose stock is not the sum
+    /// of their ledger. Stock of products with variants is derived from the
+    /// variants and is not checked separately.
+    pub async fn reconcile(pool: &SqlitePool) -> Result<Vec<StockDrift>> {
+        let rows: Vec<(String, Option<String>, String, i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT * FROM (
+                SELECT p.id, NULL, p.name, p.stock,
+                       (SELECT COALESCE(SUM(m.quantity), 0) FROM stock_movements m
+                        WHERE m.product_id = p.id AND m.variant_id IS NULL) AS ledger
+                FROM products p
+                WHERE NOT EXISTS (SELECT 1 FROM product_variants v WHERE v.product_id = p.id)
+                UNION ALL
+                SELECT v.product_id, v.id, p.name || ' (' || v.title || ')', v.stock,
+                       (SELECT COALESCE(SUM(m.quantity), 0) FROM stock_movements m WHERE m.variant_id = v.id)
+                FROM product_variants v
+                JOIN products p ON p.id = v.product_id
+            ) AS levels
+            WHERE stock != ledger
+            ORDER BY 3
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(product_id, variant_id, name, stock, ledger)| {
+                Ok(StockDrift {
+                    product_id: product_id.parse()?,
+                    variant_id: variant_id.map(|id| id.parse()).transpose()?,
+                    name,
+                    stock,
+                    ledger,
+                })
+            })
+            .collect()
+    }
+
+    fn row_to_movement(row: MovementRow) -> Result<StockMovement> {
+        let (id, product_id, variant_id, quantity, reason, note, actor_id, reference_id, created_at) = row;
+
+        Ok(StockMovement {
+            id: id.parse()?,
+            product_id: product_id.parse()?,
+            variant_id: variant_id.map(|id| id.parse()).transpose()?,
+            quantity,
+            reason: StockMovementReason::parse(&reason)
+                .ok_or_else(|| anyhow::anyhow!("Invalid stock movement reason: {}", reason))?,
+            note,
+            actor_id: actor_id.map(|id| id.parse()).transpose()?,
+            reference_id: reference_id.map(|id| id.parse()).transpose()?,
+            created_at: chrono::DateTime::parse_from_rfc3339(&created_at)?.with_timezone(&Utc),
+        })
+    }
+}
@@ -0,0 +1,129 @@
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+use shared::StockSubscription;
+
+/// (id, user_id, product_id, created_at, notified_at)
+type SubscriptionRow = (String, String, String, String, Option<String>);
+
+const SUBSCRIPTION_COLUMNS: &str = "id, user_id, product_id, created_at, notified_at";
+
+/// A waiting subscription whose product can be bought again
+pub struct RestockedSubscription {
+    pub id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub product_id: Uuid,
+    pub product_name: String,
+}
+
+pub struct StockSubscriptionRepository;
+
+impl StockSubscriptionRepository {
+    /// Subscribe a customer to a product, or return their waiting
+    /// subscription if they already have one. The flag is true if it is new.
+    pub async fn create(pool: &SqlitePool, user_id: Uuid, product_id: Uuid) -> Result<(StockSubscription, bool)> {
+        let result = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO stock_subscriptions (id, user_id, product_id, created_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(user_id.to_string())
+        .bind(product_id.to_string())
+        .bind(Utc::now().to_rfc3339())
+        .execute(pool)
+        .await?;
+
+        let row: SubscriptionRow = sqlx::query_as(&format!(
+            "SELECT {} FROM stock_subscriptions WHERE user_id = ? AND product_id = ? AND notified_at IS NULL",
+            SUBSCRIPTION_COLUMNS
+        ))
+        .bind(user_id.to_string())
+        .bind(product_id.to_string())
+        .fetch_one(pool)
+        .await?;
+
+        Ok((Self::row_to_subscription(row)?, result.rows_affected() == 1))
+    }
+
+    /// A customer's subscriptions that have not been notified yet
+    pub async fn list_waiting(pool: &SqlitePool, user_id: Uuid) -> Result<Vec<StockSubscription>> {
+        let rows: Vec<SubscriptionRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM stock_subscriptions WHERE user_id = ? AND notified_at IS NULL ORDER BY created_at DESC",
+            SUBSCRIPTION_COLUMNS
+        ))
+        .bind(user_id.to_string())
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_subscription).collect()
+    }
+
+    pub async fn delete(pool: &SqlitePool, id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM stock_subscriptions WHERE id = ? AND user_id = ? AND notified_at IS NULL")
+            .bind(id.to_string())
+            .bind(user_id.to_string())
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Waiting subscriptions whose product is active and has stock beyond
+    /// what unpaid orders hold
+    pub async fn restocked(pool: &SqlitePool) -> Result<Vec<RestockedSubscription>> {
+        let rows: Vec<(String, String, String, String, String)> = sqlx::query_as(
+            r#"
+            SELECT s.id, u.email, u.username, p.id, p.name
+            FROM stock_subscriptions s
+            JOIN users u ON u.id = s.user_id
+            JOIN products p ON p.id = s.product_id
+            WHERE s.notified_at IS NULL
+              AND p.is_active = 1
+              AND p.stock > (SELECT COALESCE(SUM(r.quantity), 0) FROM active_reservations r WHERE r.product_id = p.id)
+            ORDER BY s.created_at
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(id, email, username, product_id, product_name)| {
+                Ok(RestockedSubscription {
+                    id: id.parse()?,
+                    email,
+                    username,
+                    product_id: product_id.parse()?,
+                    product_name,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn mark_notified(pool: &SqlitePool, id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE stock_subscriptions SET notified_at = ? WHERE id = ?")
+            .bind(Utc::now().to_rfc3339())
+            .bind(id.to_string())
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    fn row_to_subscription(row: SubscriptionRow) -> Result<StockSubscription> {
+        let (id, user_id, product_id, created_at, notified_at) = row;
+
+        Ok(StockSubscription {
+            id: id.parse()?,
+            user_id: user_id.parse()?,
+            product_id: product_id.parse()?,
+            created_at: chrono::DateTime::parse_from_rfc3339(&created_at)?.with_timezone(&Utc),
+            notified_at: notified_at
+                .map(|at| chrono::DateTime::parse_from_rfc3339(&at).map(|at| at.with_timezone(&Utc)))
+                .transpose()?,
+        })
+    }
+}
@@ -0,0 +1,145 @@
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+use crate::auth::hash_token;
+
+/// A user's TOTP enrollment
+#[derive(Debug, Clone)]
+pub struct TwoFactorState {
+    /// Pending (not yet confirmed) or active secret
+    pub secret: Option<String>,
+    pub enabled: bool,
+    pub last_step: Option<i64>,
+}
+
+pub struct TwoFactorRepository;
+
+impl TwoFactorRepository {
+    pub async fn get(pool: &SqlitePool, user_id: Uuid) -> Result<TwoFactorState> {
+        let row: Option<(Option<String>, Option<String>, Option<i64>)> = sqlx::query_as(
+            "SELECT totp_secret, totp_enabled_at, totp_last_step FROM users WHERE id = ?",
+        )
+        .bind(user_id.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+        let (secret, enabled_at, last_step) = row.ok_or_else(|| anyhow::anyhow!("User not found"))?;
+
+        Ok(TwoFactorState {
+            secret,
+            enabled: enabled_at.is_some(),
+            last_step,
+        })
+    }
+
+    /// Store a new secret awaiting confirmation; refused once 2FA is enabled
+    pub async fn set_pending_secret(pool: &SqlitePool, user_id: Uuid, secret: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE users SET totp_secret = ?, totp_last_step = NULL WHERE id = ? AND totp_enabled_at IS NULL",
+        )
+        .bind(secret)
+        .bind(user_id.to_string())
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Activate the pending secret and replace the recovery codes
+    pub async fn enable(pool: &SqlitePool, user_id: Uuid, step: i64, recovery_codes: &[String]) -> Result<bool> {
+        let now = Utc::now().to_rfc3339();
+        let mut tx = pool.begin().await?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE users SET totp_enabled_at = ?, totp_last_step = ?
+            WHERE id = ? AND totp_secret IS NOT NULL AND totp_enabled_at IS NULL
+            "#,
+        )
+        .bind(&now)
+        .bind(step)
+        .bind(user_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query("DELETE FROM recovery_codes WHERE user_id = ?")
+            .bind(user_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        for code in recovery_codes {
+            sqlx::query("INSERT INTO recovery_codes (id, user_id, code_hash, created_at) VALUES (?, ?, ?, ?)")
+                .bind(Uuid::new_v4().to_string())
+                .bind(user_id.to_string())
+                .bind(hash_token(code))
+                .bind(&now)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Record an accepted time step. Compare-and-set, so the same code
+    /// presented twice concurrently is accepted only once.
+    pub async fn record_step(pool: &SqlitePool, user_id: Uuid, step: i64) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE users SET totp_last_step = ? WHERE id = ? AND (totp_last_step IS NULL OR totp_last_step < ?)",
+        )
+        .bind(step)
+        .bind(user_id.to_string())
+        .bind(step)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Spend a recovery code (already normalized). Returns false if it is
+    /// unknown or was used before.
+    pub async fn use_recovery_code(pool: &SqlitePool, user_id: Uuid, code: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE recovery_codes SET used_at = ? WHERE user_id = ? AND code_hash = ? AND used_at IS NULL",
+        )
+        .bind(Utc::now().to_rfc3339())
+        .bind(user_id.to_string())
+        .bind(hash_token(code))
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn remaining_recovery_codes(pool: &SqlitePool, user_id: Uuid) -> Result<i64> {
+        let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM recovery_codes WHERE user_id = ? AND used_at IS NULL")
+            .bind(user_id.to_string())
+            .fetch_one(pool)
+            .await?;
+
+        Ok(count.0)
+    }
+
+    /// Remove the secret and all recovery codes
+    pub async fn disable(pool: &SqlitePool, user_id: Uuid) -> Result<()> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("UPDATE users SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_step = NULL WHERE id = ?")
+            .bind(user_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM recovery_codes WHERE user_id = ?")
+            .bind(user_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
@@ -1,9 +1,34 @@
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use sqlx::SqlitePool;
 use uuid::Uuid;
 use shared::{User, UserRole};
 
+/// (id, username, email, password_hash, role, created_at, email_verified_at)
+type UserRow = (String, String, String, String, String, String, Option<String>);
+
+const USER_COLUMNS: &str = "id, username, email, password_hash, role, created_at, email_verified_at";
+
+fn row_to_user(row: UserRow) -> Result<User> {
+    let (id, username, email, password_hash, role, created_at, email_verified_at) = row;
+    let role = match role.as_str() {
+        "admin" => UserRole::Admin,
+        _ => UserRole::User,
+    };
+
+    Ok(User {
+        id: id.parse()?,
+        username,
+        email,
+        password_hash,
+        role,
+        created_at: DateTime::parse_from_rfc3339(&created_at)?.with_timezone(&Utc),
+        email_verified_at: email_verified_at
+            .map(|at| DateTime::parse_from_rfc3339(&at).map(|at| at.with_timezone(&Utc)))
+            .transpose()?,
+    })
+}
+
 pub struct UserRepository;
 
 impl UserRepository {
@@ -37,61 +62,26 @@ impl UserRepository {
             password_hash: password_hash.to_string(),
             role: UserRole::User,
             created_at: now,
+            email_verified_at: None,
         })
     }
 
     pub async fn find_by_email(pool: &SqlitePool, email: &str) -> Result<Option<User>> {
-        let row: Option<(String, String, String, String, String, String)> = sqlx::query_as(
-            "SELECT id, username, email, password_hash, role, created_at FROM users WHERE email = ?",
-        )
-        .bind(email)
-        .fetch_optional(pool)
-        .await?;
+        let row: Option<UserRow> = sqlx::query_as(&format!("SELECT {} FROM users WHERE email = ?", USER_COLUMNS))
+            .bind(email)
+            .fetch_optional(pool)
+            .await?;
 
-        match row {
-            Some((id, username, email, password_hash, role, created_at)) => {
-                let role = match role.as_str() {
-                    "admin" => UserRole::Admin,
-                    _ => UserRole::User,
-                };
-                Ok(Some(User {
-                    id: id.parse()?,
-                    username,
-                    email,
-                    password_hash,
-                    role,
-                    created_at: chrono::DateTime::parse_from_rfc3339(&created_at)?.with_timezone(&Utc),
-                }))
-            }
-            None => Ok(None),
-        }
+        row.map(row_to_user).transpose()
     }
 
     pub async fn find_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<User>> {
-        let row: Option<(String, String, String, String, String, String)> = sqlx::query_as(
-            "SELECT id, username, email, password_hash, role, created_at FROM users WHERE id = ?",
-        )
-        .bind(id.to_string())
-        .fetch_optional(pool)
-        .await?;
+        let row: Option<UserRow> = sqlx::query_as(&format!("SELECT {} FROM users WHERE id = ?", USER_COLUMNS))
+            .bind(id.to_string())
+            .fetch_optional(pool)
+            .await?;
 
-        match row {
-            Some((id, username, email, password_hash, role, created_at)) => {
-                let role = match role.as_str() {
-                    "admin" => UserRole::Admin,
-                    _ => UserRole::User,
-                };
-                Ok(Some(User {
-                    id: id.parse()?,
-                    username,
-                    email,
-                    password_hash,
-                    role,
-                    created_at: chrono::DateTime::parse_from_rfc3339(&created_at)?.with_timezone(&Utc),
-                }))
-            }
-            None => Ok(None),
-        }
+        row.map(row_to_user).transpose()
     }
 
     pub async fn email_exists(pool: &SqlitePool, email: &str) -> Result<bool> {
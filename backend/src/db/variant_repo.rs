@@ -0,0 +1,337 @@
+use std::collections::BTreeMap;
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+use shared::{
+    CreateVariantRequest, Currency, Money, ProductOption, ProductVariant, StockMovementReason, UpdateVariantRequest,
+};
+use super::{StockChange, StockRepository};
+
+/// (id, product_id, sku, options, title, price_cents, currency, stock, reserved, is_active)
+type VariantRow = (String, String, String, String, String, Option<i64>, String, i32, i32, i32);
+
+/// Variant columns plus the owning product's currency; expects `product_variants v JOIN products p`
+const VARIANT_COLUMNS: &str = "v.id, v.product_id, v.sku, v.options, v.title, v.price_cents, p.currency, v.stock, \
+    (SELECT COALESCE(SUM(r.quantity), 0) FROM active_reservations r WHERE r.variant_id = v.id), v.is_active";
+
+pub struct VariantRepository;
+
+impl VariantRepository {
+    pub async fn list_options(pool: &SqlitePool, product_id: Uuid) -> Result<Vec<ProductOption>> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT name, option_values FROM product_options WHERE product_id = ? ORDER BY position",
+        )
+        .bind(product_id.to_string())
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(name, values)| Ok(ProductOption { name, values: serde_json::from_str(&values)? }))
+            .collect()
+    }
+
+    /// Replace all options of a product. Returns false, changing nothing, if
+    /// the product already has variants built from the current options.
+    pub async fn set_options(pool: &SqlitePool, product_id: Uuid, options: &[ProductOption]) -> Result<bool> {
+        let mut tx = pool.begin().await?;
+
+        let (variants,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM product_variants WHERE product_id = ?")
+            .bind(product_id.to_string())
+            .fetch_one(&mut *tx)
+            .await?;
+        if variants > 0 {
+            return Ok(false);
+        }
+
+        sqlx::query("DELETE FROM product_options WHERE product_id = ?")
+            .bind(product_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        for (position, option) in options.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO product_options (id, product_id, name, option_values, position) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(product_id.to_string())
+            .bind(&option.name)
+            .bind(serde_json::to_string(&option.values)?)
+            .bind(position as i64)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Variants of a product in SKU order; inactive ones only when asked for
+    pub async fn list_variants(pool: &SqlitePool, product_id: Uuid, include_inactive: bool) -> Result<Vec<ProductVariant>> {
+        let rows: Vec<VariantRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM product_variants v JOIN products p ON p.id = v.product_id
+             WHERE v.product_id = ? AND (v.is_active = 1 OR ?) ORDER BY v.created_at, v.sku",
+            VARIANT_COLUMNS
+        ))
+        .bind(product_id.to_string())
+        .bind(include_inactive)
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter().map(Self::row_to_variant).collect()
+    }
+
+    pub async fn get_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<ProductVariant>> {
+        let row: Option<VariantRow> = sqlx::query_as(&format!(
+            "SELECT {} FROM product_variants v JOIN products p ON p.id = v.product_id WHERE v.id = ?",
+            VARIANT_COLUMNS
+        ))
+        .bind(id.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+        row.map(Self::row_to_variant).transpose()
+    }
+
+    pub async fn sku_exists(pool: &SqlitePool, sku: &str) -> Result<bool> {
+        let row: Option<(String,)> = sqlx::query_as("SELECT id FROM product_variants WHERE sku = ?")
+            .bind(sku)
+            .fetch_optional(pool)
+            .await?;
+
+        Ok(row.is_some())
+    }
+
+    pub async fn combination_exists(pool: &SqlitePool, product_id: Uuid, options: &BTreeMap<String, String>) -> Result<bool> {
+        let row: Option<(String,)> = sqlx::query_as("SELECT id FROM product_variants WHERE product_id = ? AND options = ?")
+            .bind(product_id.to_string())
+            .bind(serde_json::to_string(options)?)
+            .fetch_optional(pool)
+            .await?;
+
+        Ok(row.is_some())
+    }
+
+    /// Insert a variant whose options were checked with `variant_title`; its
+    /// initial stock enters the ledger as an import
+    pub async fn create(
+        pool: &SqlitePool,
+        product_id: Uuid,
+        title: &str,
+        req: &CreateVariantRequest,
+        created_by: Option<Uuid>,
+    ) -> Result<ProductVariant> {
+        let id = Uuid::new_v4();
+        let now = Utc::now().to_rfc3339();
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO product_variants (id, product_id, sku, options, title, price_cents, stock, is_active, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, 0, 1, ?, ?)
+            "#,
+        )
+        .bind(id.to_string())
+        .bind(product_id.to_string())
+        .bind(req.sku.trim())
+        .bind(serde_json::to_string(&req.options)?)
+        .bind(title)
+        .bind(req.price.map(|price| price.amount))
+        .bind(&now)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+
+        if req.stock != 0 {
+            let import = StockChange {
+                product_id,
+                variant_id: Some(id),
+                quantity: req.stock,
+                reason: StockMovementReason::Import,
+                note: Some("Initial stock".to_string()),
+                actor_id: created_by,
+                reference_id: None,
+            };
+            StockRepository::record(&mut *tx, &import).await?;
+        }
+
+        tx.commit().await?;
+
+        Self::get_by_id(pool, id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Variant {} vanished after insert", id))
+    }
+
+    /// Apply a partial update; fields left as `None` keep their current value.
+    /// A new stock level is recorded in the ledger as an adjustment.
+    pub async fn update(
+        pool: &SqlitePool,
+        id: Uuid,
+        req: &UpdateVariantRequest,
+        changed_by: Option<Uuid>,
+    ) -> Result<Option<ProductVariant>> {
+        let mut variant = match Self::get_by_id(pool, id).await? {
+            Some(variant) => variant,
+            None => return Ok(None),
+        };
+
+        if let Some(sku) = &req.sku {
+            variant.sku = sku.trim().to_string();
+        }
+        if req.clear_price {
+            variant.price = None;
+        }
+        if let Some(price) = req.price {
+            variant.price = Some(price);
+        }
+        if let Some(is_active) = req.is_active {
+            variant.is_active = is_active;
+        }
+
+        let mut tx = pool.begin().await?;
+
+        // Read the stock again inside the transaction so the ledger delta is
+        // taken against the current level
+        if let Some(stock) = req.stock {
+            let (current,): (i32,) = sqlx::query_as("SELECT stock FROM product_variants WHERE id = ?")
+                .bind(id.to_string())
+                .fetch_one(&mut *tx)
+                .await?;
+
+            if stock != current {
+                let adjustment = StockChange {
+                    product_id: variant.product_id,
+                    variant_id: Some(id),
+                    quantity: stock - current,
+                    reason: StockMovementReason::Adjustment,
+                    note: Some(format!("Stock set to {} in variant update", stock)),
+                    actor_id: changed_by,
+                    reference_id: None,
+                };
+                StockRepository::record(&mut *tx, &adjustment).await?;
+            }
+            variant.stock = stock;
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE product_variants SET sku = ?, price_cents = ?, is_active = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&variant.sku)
+        .bind(variant.price.map(|price| price.amount))
+        .bind(variant.is_active as i32)
+        .bind(Utc::now().to_rfc3339())
+        .bind(id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(variant))
+    }
+
+    /// Delete a variant and drop it from carts. Past order items keep their
+    /// variant id and title, so nothing else refers to the row.
+    pub async fn delete(pool: &SqlitePool, id: Uuid) -> Result<bool> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("DELETE FROM cart_items WHERE variant_id = ?")
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        let result = sqlx::query("DELETE FROM product_variants WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Whether a product is sold by variant; such products need a variant in the cart
+    pub async fn has_variants(pool: &SqlitePool, product_id: Uuid) -> Result<bool> {
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM product_variants WHERE product_id = ? AND is_active = 1")
+            .bind(product_id.to_string())
+            .fetch_one(pool)
+            .await?;
+
+        Ok(count > 0)
+    }
+
+    fn row_to_variant(row: VariantRow) -> Result<ProductVariant> {
+        let (id, product_id, sku, options, title, price_cents, currency, stock, reserved, is_active) = row;
+        let currency = Currency::new(&currency)
+            .ok_or_else(|| anyhow::anyhow!("Invalid currency code: {}", currency))?;
+
+        Ok(ProductVariant {
+            id: id.parse()?,
+            product_id: product_id.parse()?,
+            sku,
+            options: serde_json::from_str(&options)?,
+            title,
+            price: price_cents.map(|cents| Money::new(cents, currency)),
+            stock,
+            reserved,
+            is_active: is_active == 1,
+        })
+    }
+}
+
+/// Check that `chosen` picks an allowed value for every option and nothing
+/// else, returning the variant title ("M / Red") in option order
+pub fn variant_title(options: &[ProductOption], chosen: &BTreeMap<String, String>) -> Result<String, String> {
+    if options.is_empty() {
+        return Err("Define the product's options before adding variants".to_string());
+    }
+
+    if let Some(unknown) = chosen.keys().find(|name| !options.iter().any(|o| &o.name == *name)) {
+        return Err(format!("Unknown option '{}'", unknown));
+    }
+
+    let mut values = Vec::with_capacity(options.len());
+    for option in options {
+        let value = chosen
+            .get(&option.name)
+            .ok_or_else(|| format!("Missing a value for option '{}'", option.name))?;
+        if !option.values.contains(value) {
+            return Err(format!("'{}' is not a valid {}", value, option.name));
+        }
+        values.push(value.as_str());
+    }
+
+    Ok(values.join(" / "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> Vec<ProductOption> {
+        vec![
+            ProductOption { name: "Size".to_string(), values: vec!["S".to_string(), "M".to_string()] },
+            ProductOption { name: "Color".to_string(), values: vec!["Red".to_string(), "Blue".to_string()] },
+        ]
+    }
+
+    fn chosen(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn title_follows_option_order() {
+        assert_eq!(
+            variant_title(&options(), &chosen(&[("Color", "Blue"), ("Size", "M")])),
+            Ok("M / Blue".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_missing_unknown_and_invalid_values() {
+        assert!(variant_title(&options(), &chosen(&[("Size", "M")])).is_err());
+        assert!(variant_title(&options(), &chosen(&[("Size", "M"), ("Color", "Red"), ("Fit", "Slim")])).is_err());
+        assert!(variant_title(&options(), &chosen(&[("Size", "XL"), ("Color", "Red")])).is_err());
+        assert!(variant_title(&[], &chosen(&[])).is_err());
+    }
+}
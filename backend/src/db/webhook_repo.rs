@@ -0,0 +1,248 @@
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use uuid::Uuid;
+use super::{OrderRepository, ReservationRepository};
+use crate::payments::{PaymentEvent, PaymentEventKind};
+use shared::OrderStatus;
+
+/// Result of handling one webhook delivery
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebhookOutcome {
+    /// The event changed payment and/or order state
+    Processed,
+    /// The event was valid but had nothing left to do, e.g. arrived out of order
+    Ignored(String),
+    /// The event id was already handled by an earlier delivery
+    Duplicate,
+}
+
+pub struct WebhookRepository;
+
+impl WebhookRepository {
+    /// Record a webhook event and apply it, all in one transaction, so a
+    /// replayed event id is never applied twice. State changes are also
+    /// compare-and-set, so distinct events reporting the same fact are no-ops.
+    pub async fn process(
+        pool: &SqlitePool,
+        provider: &str,
+        event: &PaymentEvent,
+        payload: &str,
+    ) -> Result<WebhookOutcome> {
+        let mut tx = pool.begin().await?;
+
+        let existing: Option<(String,)> = sqlx::query_as(
+            "SELECT status FROM webhook_events WHERE provider = ? AND event_id = ?",
+        )
+        .bind(provider)
+        .bind(&event.id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if existing.is_some_and(|(status,)| status != "failed") {
+            return Ok(WebhookOutcome::Duplicate);
+        }
+
+        let outcome = Self::apply(&mut tx, provider, event).await?;
+        let (status, detail) = match &outcome {
+            WebhookOutcome::Ignored(reason) => ("ignored", Some(reason.as_str())),
+            _ => ("processed", None),
+        };
+
+        Self::upsert(&mut tx, provider, event, payload, status, detail).await?;
+
+        tx.commit().await?;
+        Ok(outcome)
+    }
+
+    /// Store an event whose processing failed so the delivery is on record;
+    /// a redelivery of the same event id will be processed again
+    pub async fn record_failure(
+        pool: &SqlitePool,
+        provider: &str,
+        event: &PaymentEvent,
+        payload: &str,
+        error: &str,
+    ) -> Result<()> {
+        let mut tx = pool.begin().await?;
+        Self::upsert(&mut tx, provider, event, payload, "failed", Some(error)).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn upsert(
+        tx: &mut Transaction<'_, Sqlite>,
+        provider: &str,
+        event: &PaymentEvent,
+        payload: &str,
+        status: &str,
+        detail: Option<&str>,
+    ) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+
+        sqlx::query(
+            r#"
+            INSERT INTO webhook_events (provider, event_id, event_type, provider_payment_id, payload, status, detail, received_at, processed_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(provider, event_id) DO UPDATE SET
+                status = excluded.status, detail = excluded.detail, processed_at = excluded.processed_at
+            "#,
+        )
+        .bind(provider)
+        .bind(&event.id)
+        .bind(event.kind.as_str())
+        .bind(&event.payment_id)
+        .bind(payload)
+        .bind(status)
+        .bind(detail)
+        .bind(&now)
+        .bind((status != "failed").then_some(&now))
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn apply(
+        tx: &mut Transaction<'_, Sqlite>,
+        provider: &str,
+        event: &PaymentEvent,
+    ) -> Result<WebhookOutcome> {
+        let payment: Option<(String, String, String)> = sqlx::query_as(
+            "SELECT id, order_id, status FROM payments WHERE provider = ? AND provider_payment_id = ?",
+        )
+        .bind(provider)
+        .bind(&event.payment_id)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        let (payment_id, order_id, payment_status) = match payment {
+            Some(payment) => payment,
+            None => return Ok(WebhookOutcome::Ignored(format!("Unknown payment {}", event.payment_id))),
+        };
+        let order_id: Uuid = order_id.parse()?;
+
+        // Only move a payment forward from the states listed for each event
+        let (from_states, to_state): (&[&str], &str) = match event.kind {
+            PaymentEventKind::Succeeded => (&["pending", "failed"], "succeeded"),
+            PaymentEventKind::Failed => (&["pending"], "failed"),
+            PaymentEventKind::Refunded => {
+                return Self::apply_refund(tx, event, &payment_id, order_id, &payment_status).await;
+            }
+        };
+
+        if !from_states.contains(&payment_status.as_str()) {
+            return Ok(WebhookOutcome::Ignored(format!(
+                "Payment is already {}",
+                payment_status
+            )));
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE payments
+            SET status = ?1, failure_reason = ?2, updated_at = ?3
+            WHERE id = ?4 AND status = ?5
+            "#,
+        )
+        .bind(to_state)
+        .bind(event.failure_reason.as_deref().filter(|_| to_state == "failed"))
+        .bind(Utc::now().to_rfc3339())
+        .bind(&payment_id)
+        .bind(&payment_status)
+        .execute(&mut **tx)
+        .await?;
+
+        if event.kind == PaymentEventKind::Succeeded {
+            if OrderRepository::transition(tx, order_id, &OrderStatus::Pending, &OrderStatus::Paid, None).await? {
+                ReservationRepository::convert_in_tx(tx, order_id, None).await?;
+            } else {
+                tracing::warn!(
+                    "Payment {} succeeded but order {} is no longer pending; it needs a refund",
+                    event.payment_id,
+                    order_id
+                );
+            }
+        }
+
+        Ok(WebhookOutcome::Processed)
+    }
+
+    /// Add a refund to the payment's running total, as an approved return
+    /// does. A full refund before shipping cancels the order and returns its
+    /// stock; otherwise the order becomes PartiallyRefunded or Refunded.
+    async fn apply_refund(
+        tx: &mut Transaction<'_, Sqlite>,
+        event: &PaymentEvent,
+        payment_id: &str,
+        order_id: Uuid,
+        payment_status: &str,
+    ) -> Result<WebhookOutcome> {
+        if payment_status != "succeeded" {
+            return Ok(WebhookOutcome::Ignored(format!("Payment is already {}", payment_status)));
+        }
+
+        // Refunds issued for approved returns are already on the payment
+        if let Some(refund_id) = &event.refund_id {
+            let recorded: Option<(String,)> = sqlx::query_as("SELECT id FROM return_requests WHERE refund_id = ?")
+                .bind(refund_id)
+                .fetch_optional(&mut **tx)
+                .await?;
+            if let Some((return_id,)) = recorded {
+                return Ok(WebhookOutcome::Ignored(format!("Refund already recorded by return {}", return_id)));
+            }
+        }
+
+        let (amount_cents, refunded_cents): (i64, i64) =
+            sqlx::query_as("SELECT amount_cents, refunded_cents FROM payments WHERE id = ?")
+                .bind(payment_id)
+                .fetch_one(&mut **tx)
+                .await?;
+
+        let remaining = amount_cents - refunded_cents;
+        let refund = event.amount_cents.unwrap_or(remaining);
+        if refund <= 0 || refund > remaining {
+            return Ok(WebhookOutcome::Ignored(format!(
+                "Refund of {} does not fit the {} left on the payment",
+                refund, remaining
+            )));
+        }
+        let full = refund == remaining;
+
+        sqlx::query(
+            r#"
+            UPDATE payments
+            SET refunded_cents = refunded_cents + ?1,
+                status = CASE WHEN refunded_cents + ?1 >= amount_cents THEN 'refunded' ELSE status END,
+                updated_at = ?2
+            WHERE id = ?3
+            "#,
+        )
+        .bind(refund)
+        .bind(Utc::now().to_rfc3339())
+        .bind(payment_id)
+        .execute(&mut **tx)
+        .await?;
+
+        let (current,): (String,) = sqlx::query_as("SELECT status FROM orders WHERE id = ?")
+            .bind(order_id.to_string())
+            .fetch_one(&mut **tx)
+            .await?;
+        let current = OrderStatus::parse(&current).unwrap_or_default();
+
+        match current {
+            OrderStatus::Paid if full => {
+                OrderRepository::cancel_in_tx(tx, order_id, &OrderStatus::Paid, None).await?;
+            }
+            OrderStatus::Paid | OrderStatus::Shipped | OrderStatus::Delivered | OrderStatus::PartiallyRefunded => {
+                let target = if full { OrderStatus::Refunded } else { OrderStatus::PartiallyRefunded };
+                if current != target {
+                    OrderRepository::transition(tx, order_id, &current, &target, None).await?;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(WebhookOutcome::Processed)
+    }
+}
@@ -0,0 +1,90 @@
+//! Stock held for unpaid orders
+//!
+//! Placing an order holds its stock for a limited time instead of taking it;
+//! the holds become real decrements once the order is paid. This module owns
+//! the hold length, the task that expires holds nobody paid for and the task
+//! that tells waiting customers a product is back in stock.
+
+use std::{sync::Arc, time::Duration};
+use anyhow::Result;
+use sqlx::SqlitePool;
+use crate::{
+    db::{ReservationRepository, StockSubscriptionRepository},
+    mailer::{EmailMessage, Mailer},
+};
+
+/// Default length of a hold, in minutes
+const DEFAULT_HOLD_MINUTES: i64 = 15;
+
+const SWEEP_INTERVAL_SECONDS: u64 = 30;
+
+/// Hold length from `RESERVATION_TTL_MINUTES`
+pub fn hold_duration_from_env() -> Result<chrono::Duration> {
+    let minutes = match std::env::var("RESERVATION_TTL_MINUTES") {
+        Ok(value) => value
+            .parse::<i64>()
+            .ok()
+            .filter(|m| *m > 0)
+            .ok_or_else(|| anyhow::anyhow!("RESERVATION_TTL_MINUTES must be a positive number, got '{}'", value))?,
+        Err(_) => DEFAULT_HOLD_MINUTES,
+    };
+
+    Ok(chrono::Duration::minutes(minutes))
+}
+
+/// Periodically mark holds past their expiry as expired, returning their
+/// stock to sale
+pub fn spawn_expiry_sweeper(pool: SqlitePool) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(SWEEP_INTERVAL_SECONDS));
+
+        loop {
+            interval.tick().await;
+
+            match ReservationRepository::expire(&pool).await {
+                Ok(0) => {}
+                Ok(expired) => tracing::info!("Released {} expired stock reservations", expired),
+                Err(e) => tracing::error!("Failed to expire stock reservations: {}", e),
+            }
+        }
+    })
+}
+
+/// Queue a back-in-stock email for each waiting subscription whose product
+/// can be bought again. Returns how many were sent.
+async fn notify_restocked(pool: &SqlitePool, mailer: &dyn Mailer, app_url: &str) -> Result<usize> {
+    let due = StockSubscriptionRepository::restocked(pool).await?;
+
+    for subscription in &due {
+        let message = EmailMessage {
+            to: subscription.email.clone(),
+            subject: format!("{} is back in stock", subscription.product_name),
+            body: format!(
+                "Hi {},\n\n{} is back in stock. Stock is limited, so it may sell out again.\n\n{}/products/{}\n",
+                subscription.username, subscription.product_name, app_url, subscription.product_id
+            ),
+        };
+
+        mailer.send(&message).await?;
+        StockSubscriptionRepository::mark_notified(pool, subscription.id).await?;
+    }
+
+    Ok(due.len())
+}
+
+/// Periodically email customers whose out-of-stock products are back
+pub fn spawn_restock_notifier(pool: SqlitePool, mailer: Arc<dyn Mailer>, app_url: String) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(SWEEP_INTERVAL_SECONDS));
+
+        loop {
+            interval.tick().await;
+
+            match notify_restocked(&pool, mailer.as_ref(), &app_url).await {
+                Ok(0) => {}
+                Ok(sent) => tracing::info!("Queued {} back-in-stock emails via {} mailer", sent, mailer.name()),
+                Err(e) => tracing::error!("Failed to send back-in-stock emails: {}", e),
+            }
+        }
+    })
+}
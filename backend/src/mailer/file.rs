@@ -0,0 +1,68 @@
+use std::path::PathBuf;
+use async_trait::async_trait;
+use chrono::Utc;
+use uuid::Uuid;
+use super::{EmailMessage, Mailer};
+
+/// Writes each message as a `.eml` file into a directory
+pub struct FileMailer {
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(dir: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn render(message: &EmailMessage) -> String {
+        format!(
+            "To: {}\r\nSubject: {}\r\nDate: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n",
+            message.to,
+            message.subject,
+            Utc::now().to_rfc2822(),
+            message.body
+        )
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    async fn send(&self, message: &EmailMessage) -> anyhow::Result<()> {
+        let file_name = format!("{}-{}.eml", Utc::now().format("%Y%m%dT%H%M%S"), Uuid::new_v4());
+        tokio::fs::write(self.dir.join(file_name), Self::render(message)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn writes_one_file_per_message() {
+        let dir = std::env::temp_dir().join(format!("mailer-test-{}", Uuid::new_v4()));
+        let mailer = FileMailer::new(&dir).unwrap();
+
+        let message = EmailMessage {
+            to: "user@example.com".to_string(),
+            subject: "Hello".to_string(),
+            body: "Line one".to_string(),
+        };
+        mailer.send(&message).await.unwrap();
+
+        let entries: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+
+        let contents = std::fs::read_to_string(entries[0].as_ref().unwrap().path()).unwrap();
+        assert!(contents.starts_with("To: user@example.com\r\nSubject: Hello\r\n"));
+        assert!(contents.contains("\r\n\r\nLine one"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,46 @@
+//! Outgoing email
+//!
+//! Handlers send mail through a `Mailer` trait object. The default outbox
+//! implementations record messages locally, so account emails work in
+//! development without an SMTP server; a real transport can be added later.
+
+mod file;
+mod outbox;
+
+pub use file::FileMailer;
+pub use outbox::OutboxMailer;
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+/// A plain-text email
+#[derive(Debug, Clone)]
+pub struct EmailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    /// Short identifier for logs, e.g. "outbox"
+    fn name(&self) -> &'static str;
+
+    async fn send(&self, message: &EmailMessage) -> anyhow::Result<()>;
+}
+
+/// Build the mailer selected by `MAILER`: "outbox" (default) stores messages
+/// in the `email_outbox` table, "file" writes them to `MAIL_OUTBOX_DIR`.
+pub fn from_env(pool: SqlitePool) -> anyhow::Result<Arc<dyn Mailer>> {
+    let mailer = std::env::var("MAILER").unwrap_or_else(|_| "outbox".to_string());
+
+    match mailer.as_str() {
+        "outbox" => Ok(Arc::new(OutboxMailer::new(pool))),
+        "file" => {
+            let dir = std::env::var("MAIL_OUTBOX_DIR").unwrap_or_else(|_| "./outbox".to_string());
+            Ok(Arc::new(FileMailer::new(dir)?))
+        }
+        other => anyhow::bail!("Unsupported MAILER '{}'", other),
+    }
+}
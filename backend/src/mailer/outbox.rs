@@ -0,0 +1,42 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+use super::{EmailMessage, Mailer};
+
+/// Stores every message in the `email_outbox` table for inspection or a
+/// later delivery worker
+pub struct OutboxMailer {
+    pool: SqlitePool,
+}
+
+impl OutboxMailer {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl Mailer for OutboxMailer {
+    fn name(&self) -> &'static str {
+        "outbox"
+    }
+
+    async fn send(&self, message: &EmailMessage) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO email_outbox (id, recipient, subject, body, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&message.to)
+        .bind(&message.subject)
+        .bind(&message.body)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
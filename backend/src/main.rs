@@ -3,22 +3,47 @@ mod db;
 mod routes;
 mod error;
 mod rate_limit;
+mod payments;
+mod mailer;
+mod suggest;
+mod storage;
+mod inventory;
 
 use axum::{
+    extract::DefaultBodyLimit,
+    middleware,
     routing::{get, post, put, delete},
     Router,
 };
 use tower_http::cors::{CorsLayer, AllowOrigin};
+use tower_http::services::ServeDir;
 use tower_http::set_header::SetResponseHeaderLayer;
 use axum::http::{header, HeaderValue, Method};
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use std::sync::Arc;
+use rate_limit::{KeyBy, RateLimitLayer};
 
 pub struct AppState {
     pub db: db::Database,
     pub jwt_secret: String,
-    pub login_rate_limiter: rate_limit::LoginRateLimiter,
+    pub rate_limits: rate_limit::RateLimits,
+    pub payments: Arc<dyn payments::PaymentProvider>,
+    pub mailer: Arc<dyn mailer::Mailer>,
+    /// Public URL of the frontend, used in links sent by email
+    pub app_url: String,
+    /// Block checkout until the user has verified their email address
+    pub require_verified_email: bool,
+    /// Admin routes only accept sessions that passed two-factor authentication
+    pub require_admin_2fa: bool,
+    /// Type-ahead index over product names and categories
+    pub suggestions: suggest::SuggestionIndex,
+    /// Uploaded product images and their thumbnails
+    pub blobs: Arc<dyn storage::BlobStore>,
+    /// How long placing an order holds its stock before payment
+    pub reservation_ttl: chrono::Duration,
+    /// Flat shipping fee per order, in minor units of the order's currency
+    pub shipping_fee: i64,
 }
 
 #[tokio::main]
@@ -38,9 +63,20 @@ async fn main() -> anyhow::Result<()> {
         .unwrap_or_else(|_| "sqlite:./data.db?mode=rwc".to_string());
     let db = db::Database::new(&database_url).await?;
 
-    // Run migrations
+    // `backend migrate <status|up>` manages the schema without starting the server
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        return migrate_command(&db, args.get(1).map(String::as_str)).await;
+    }
+
+    // Run pending migrations; refuses to start if applied ones have drifted
     db.migrate().await?;
 
+    // `backend stock reconcile` checks stock levels against the ledger
+    if args.first().map(String::as_str) == Some("stock") {
+        return stock_command(&db, args.get(1).map(String::as_str)).await;
+    }
+
     let jwt_secret = std::env::var("JWT_SECRET")
         .expect("JWT_SECRET environment variable is required. Set a strong random secret (at least 32 characters).");
 
@@ -49,10 +85,63 @@ async fn main() -> anyhow::Result<()> {
         panic!("JWT_SECRET must be at least 32 characters long for security");
     }
 
+    let mailer = mailer::from_env(db.pool.clone())?;
+    let app_url = std::env::var("APP_URL")
+        .unwrap_or_else(|_| "http://localhost:8080".to_string())
+        .trim_end_matches('/')
+        .to_string();
+    let require_verified_email = !matches!(
+        std::env::var("REQUIRE_EMAIL_VERIFICATION").as_deref(),
+        Ok("false") | Ok("0")
+    );
+    let require_admin_2fa = !matches!(std::env::var("REQUIRE_ADMIN_2FA").as_deref(), Ok("false") | Ok("0"));
+
+    // Rate limiter state is swept every minute and, optionally, kept in SQLite
+    let rate_limits = rate_limit::RateLimits::from_env()?;
+    let persist_rate_limits = matches!(std::env::var("RATE_LIMIT_PERSIST").as_deref(), Ok("true") | Ok("1"));
+    if persist_rate_limits {
+        rate_limits.restore(&db.pool).await?;
+    }
+    rate_limits.spawn_sweeper(persist_rate_limits.then(|| db.pool.clone()));
+
+    // Unpaid orders hold their stock until paid or the hold expires
+    let reservation_ttl = inventory::hold_duration_from_env()?;
+    inventory::spawn_expiry_sweeper(db.pool.clone());
+    // Customers waiting for out-of-stock products are emailed once they're back
+    inventory::spawn_restock_notifier(db.pool.clone(), mailer.clone(), app_url.clone());
+
+    // Charged once per order; free-shipping coupons waive it
+    let shipping_fee = match std::env::var("SHIPPING_FEE_CENTS") {
+        Ok(value) => value
+            .parse::<i64>()
+            .ok()
+            .filter(|cents| *cents >= 0)
+            .ok_or_else(|| anyhow::anyhow!("SHIPPING_FEE_CENTS must be a non-negative number, got '{}'", value))?,
+        Err(_) => 0,
+    };
+
+    let suggestions = suggest::SuggestionIndex::new();
+    db::ProductRepository::refresh_suggestions(&db.pool, &suggestions).await?;
+
+    // Uploads are written to a local directory and served from /uploads
+    let upload_dir = std::env::var("UPLOAD_DIR").unwrap_or_else(|_| "./uploads".to_string());
+    let upload_public_url = std::env::var("UPLOAD_PUBLIC_URL")
+        .unwrap_or_else(|_| "http://localhost:3000/uploads".to_string());
+    let blobs = Arc::new(storage::LocalBlobStore::new(&upload_dir, &upload_public_url)?);
+
     let state = Arc::new(AppState {
         db,
         jwt_secret,
-        login_rate_limiter: rate_limit::LoginRateLimiter::new(),
+        rate_limits: rate_limits.clone(),
+        payments: payments::from_env()?,
+        mailer,
+        app_url: app_url.clone(),
+        require_verified_email,
+        require_admin_2fa,
+        suggestions,
+        blobs,
+        reservation_ttl,
+        shipping_fee,
     });
 
     // CORS configuration - restricted to trusted origins
@@ -70,29 +159,118 @@ async fn main() -> anyhow::Result<()> {
         .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE, header::ACCEPT])
         .allow_credentials(true);
 
+    // Per-group request limits; checkout runs inside auth so it can key by user
+    let register_limit = RateLimitLayer::new(rate_limits.register.clone(), KeyBy::Ip);
+    let search_limit = RateLimitLayer::new(rate_limits.search.clone(), KeyBy::Ip);
+    let checkout_limit = RateLimitLayer::new(rate_limits.checkout.clone(), KeyBy::UserOrIp);
+
+    // Routes that require a valid access token
+    let protected_routes = Router::new()
+        // Session routes
+        .route("/api/auth/logout", post(routes::auth::logout))
+        .route("/api/auth/resend-verification", post(routes::auth::resend_verification))
+        .route("/api/auth/2fa", get(routes::two_factor::status))
+        .route("/api/auth/2fa/setup", post(routes::two_factor::setup))
+        .route("/api/auth/2fa/confirm", post(routes::two_factor::confirm))
+        .route("/api/auth/2fa/disable", post(routes::two_factor::disable))
+        // Cart routes
+        .route("/api/cart", get(routes::cart::get_cart))
+        .route("/api/cart", post(routes::cart::add_to_cart))
+        .route("/api/cart/:product_id", put(routes::cart::update_cart_item))
+        .route("/api/cart/:product_id", delete(routes::cart::remove_from_cart))
+        .route("/api/cart/coupon", post(routes::cart::apply_coupon).delete(routes::cart::remove_coupon))
+        // Order routes
+        .route("/api/orders", get(routes::orders::list_orders))
+        .route(
+            "/api/orders",
+            post(routes::orders::create_order).route_layer(checkout_limit.clone()),
+        )
+        .route("/api/orders/:id", get(routes::orders::get_order))
+        .route("/api/orders/:id/cancel", put(routes::orders::cancel_order))
+        .route("/api/orders/:id/pay", post(routes::payments::pay_order).route_layer(checkout_limit))
+        .route("/api/orders/:id/returns", get(routes::returns::list_order_returns))
+        .route("/api/orders/:id/returns", post(routes::returns::create_return))
+        // Address book routes
+        .route("/api/addresses", get(routes::addresses::list_addresses))
+        .route("/api/addresses", post(routes::addresses::create_address))
+        .route("/api/addresses/:id", put(routes::addresses::update_address))
+        .route("/api/addresses/:id", delete(routes::addresses::delete_address))
+        .route(
+            "/api/stock-subscriptions",
+            get(routes::subscriptions::list_subscriptions).post(routes::subscriptions::create_subscription),
+        )
+        .route("/api/stock-subscriptions/:id", delete(routes::subscriptions::delete_subscription))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::auth_middleware));
+
+    // Routes that additionally require the admin role
+    let admin_routes = Router::new()
+        .route("/api/admin/products", post(routes::admin::create_product))
+        .route("/api/admin/products/:id", put(routes::admin::update_product))
+        .route("/api/admin/products/:id", delete(routes::admin::delete_product))
+        .route(
+            "/api/admin/products/:id/images",
+            post(routes::images::upload_image)
+                .layer(DefaultBodyLimit::max(storage::MAX_UPLOAD_BYTES + 64 * 1024)),
+        )
+        .route("/api/admin/images/:id", put(routes::images::update_image).delete(routes::images::delete_image))
+        .route(
+            "/api/admin/products/:id/stock",
+            get(routes::stock::list_movements).post(routes::stock::adjust_stock),
+        )
+        .route("/api/admin/products/:id/options", put(routes::variants::set_options))
+        .route("/api/admin/products/:id/variants", post(routes::variants::create_variant))
+        .route("/api/admin/variants/:id", put(routes::variants::update_variant).delete(routes::variants::delete_variant))
+        .route("/api/admin/categories", post(routes::categories::create_category))
+        .route("/api/admin/categories/:id", put(routes::categories::update_category).delete(routes::categories::delete_category))
+        .route("/api/admin/orders", get(routes::admin::list_orders))
+        .route("/api/admin/orders/:id", get(routes::admin::get_order))
+        .route("/api/admin/orders/:id/status", put(routes::admin::update_order_status))
+        .route("/api/admin/returns", get(routes::returns::list_returns))
+        .route("/api/admin/returns/:id", put(routes::returns::review_return))
+        .route("/api/admin/notifications", get(routes::notifications::list_notifications))
+        .route("/api/admin/notifications/:id/read", put(routes::notifications::mark_notification_read))
+        .route("/api/admin/coupons", get(routes::coupons::list_coupons).post(routes::coupons::create_coupon))
+        .route("/api/admin/coupons/:id", put(routes::coupons::update_coupon))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::require_admin))
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::auth_middleware));
+
     // Build routes
     let app = Router::new()
         // Health check
         .route("/health", get(|| async { "OK" }))
         // Auth routes
-        .route("/api/auth/register", post(routes::auth::register))
+        .route(
+            "/api/auth/register",
+            post(routes::auth::register).route_layer(register_limit.clone()),
+        )
         .route("/api/auth/login", post(routes::auth::login))
+        .route("/api/auth/refresh", post(routes::auth::refresh))
+        .route(
+            "/api/auth/forgot-password",
+            post(routes::auth::forgot_password).route_layer(register_limit),
+        )
+        .route("/api/auth/reset-password", post(routes::auth::reset_password))
+        .route("/api/auth/verify-email", get(routes::auth::verify_email))
+        .route("/api/auth/2fa/verify", post(routes::two_factor::verify_login))
         // Product routes
-        .route("/api/products", get(routes::products::list_products))
+        .route(
+            "/api/products",
+            get(routes::products::list_products).route_layer(search_limit.clone()),
+        )
         .route("/api/products/:id", get(routes::products::get_product))
-        .route("/api/products/search", get(routes::products::search_products))
+        .route(
+            "/api/products/search",
+            get(routes::products::search_products).route_layer(search_limit),
+        )
+        .route("/api/products/suggest", get(routes::products::suggest_products))
         .route("/api/products/category/:category", get(routes::products::products_by_category))
-        .route("/api/categories", get(routes::products::list_categories))
-        // Cart routes (protected)
-        .route("/api/cart", get(routes::cart::get_cart))
-        .route("/api/cart", post(routes::cart::add_to_cart))
-        .route("/api/cart/:product_id", put(routes::cart::update_cart_item))
-        .route("/api/cart/:product_id", delete(routes::cart::remove_from_cart))
-        // Order routes (protected)
-        .route("/api/orders", get(routes::orders::list_orders))
-        .route("/api/orders", post(routes::orders::create_order))
-        .route("/api/orders/:id", get(routes::orders::get_order))
-        .route("/api/orders/:id/cancel", put(routes::orders::cancel_order))
+        .route("/api/categories", get(routes::categories::list_categories))
+        .route("/api/categories/:slug", get(routes::categories::get_category))
+        // Payment provider callbacks (authenticated by signature)
+        .route("/api/webhooks/payments", post(routes::payments::payment_webhook))
+        .merge(protected_routes)
+        .merge(admin_routes)
+        .nest_service("/uploads", ServeDir::new(&upload_dir))
         // Middleware
         .layer(cors)
         .layer(TraceLayer::new_for_http())
@@ -127,3 +305,55 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+async fn migrate_command(db: &db::Database, subcommand: Option<&str>) -> anyhow::Result<()> {
+    match subcommand {
+        Some("status") | None => {
+            let statuses = db.migration_status().await?;
+
+            println!("{:<8} {:<32} {:<10} APPLIED AT", "VERSION", "NAME", "STATE");
+            for s in &statuses {
+                println!(
+                    "{:<8} {:<32} {:<10} {}",
+                    format!("{:04}", s.version),
+                    s.name,
+                    s.state.as_str(),
+                    s.applied_at.as_deref().unwrap_or("-")
+                );
+            }
+            Ok(())
+        }
+        Some("up") => {
+            db.migrate().await?;
+            println!("Database is up to date");
+            Ok(())
+        }
+        Some(other) => anyhow::bail!("Unknown migrate subcommand '{}'; expected 'status' or 'up'", other),
+    }
+}
+
+async fn stock_command(db: &db::Database, subcommand: Option<&str>) -> anyhow::Result<()> {
+    match subcommand {
+        Some("reconcile") | None => {
+            let drift = db::StockRepository::reconcile(&db.pool).await?;
+            if drift.is_empty() {
+                println!("Stock levels match the ledger");
+                return Ok(());
+            }
+
+            println!("{:<36} {:<40} {:>8} {:>8} {:>8}", "ID", "NAME", "STOCK", "LEDGER", "DRIFT");
+            for d in &drift {
+                println!(
+                    "{:<36} {:<40} {:>8} {:>8} {:>8}",
+                    d.variant_id.unwrap_or(d.product_id),
+                    d.name,
+                    d.stock,
+                    d.ledger,
+                    d.stock - d.ledger
+                );
+            }
+            anyhow::bail!("{} stock level(s) differ from the ledger", drift.len())
+        }
+        Some(other) => anyhow::bail!("Unknown stock subcommand '{}'; expected 'reconcile'", other),
+    }
+}
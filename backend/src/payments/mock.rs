@@ -0,0 +1,210 @@
+//! In-process payment gateway for development and offline testing
+//!
+//! Behaviour is configured through the environment:
+//! - `MOCK_PAYMENT_OUTCOME`: `success` (default) or `decline`
+//! - `MOCK_PAYMENT_DELAY_MS`: artificial latency for every call (default 0)
+//! - `PAYMENT_WEBHOOK_SECRET`: key for webhook signatures
+//!
+//! Webhooks are signed with the hex HMAC-SHA256 of the raw body.
+//! The payment method `pm_card_declined` is always declined and
+//! `pm_card_visa` always succeeds, regardless of the configured outcome.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+use shared::Money;
+use super::{PaymentError, PaymentEvent, PaymentIntent, PaymentProvider};
+
+/// Development-only default; set PAYMENT_WEBHOOK_SECRET in any shared environment
+const DEFAULT_WEBHOOK_SECRET: &str = "mock-webhook-secret";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockOutcome {
+    Succeed,
+    Decline,
+}
+
+struct MockIntent {
+    amount: Money,
+    captured: bool,
+    refunded: i64,
+}
+
+pub struct MockPaymentProvider {
+    outcome: MockOutcome,
+    delay: Duration,
+    webhook_secret: String,
+    intents: Mutex<HashMap<String, MockIntent>>,
+}
+
+impl MockPaymentProvider {
+    pub fn new(outcome: MockOutcome, delay: Duration, webhook_secret: impl Into<String>) -> Self {
+        Self {
+            outcome,
+            delay,
+            webhook_secret: webhook_secret.into(),
+            intents: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_env() -> anyhow::Result<Self> {
+        let outcome = match std::env::var("MOCK_PAYMENT_OUTCOME").as_deref() {
+            Ok("decline") => MockOutcome::Decline,
+            Ok("success") | Err(_) => MockOutcome::Succeed,
+            Ok(other) => anyhow::bail!("MOCK_PAYMENT_OUTCOME must be 'success' or 'decline', got '{}'", other),
+        };
+
+        let delay_ms: u64 = match std::env::var("MOCK_PAYMENT_DELAY_MS") {
+            Ok(value) => value
+                .parse()
+                .map_err(|_| anyhow::anyhow!("MOCK_PAYMENT_DELAY_MS must be a number of milliseconds"))?,
+            Err(_) => 0,
+        };
+
+        let webhook_secret = std::env::var("PAYMENT_WEBHOOK_SECRET")
+            .unwrap_or_else(|_| DEFAULT_WEBHOOK_SECRET.to_string());
+
+        Ok(Self::new(outcome, Duration::from_millis(delay_ms), webhook_secret))
+    }
+
+    /// HMAC-SHA256 of `payload`; its hex digest is the webhook signature
+    fn mac(&self, payload: &[u8]) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.webhook_secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(payload);
+        mac
+    }
+
+    async fn simulate_latency(&self) {
+        if !self.delay.is_zero() {
+            tokio::time::sleep(self.delay).await;
+        }
+    }
+}
+
+#[async_trait]
+impl PaymentProvider for MockPaymentProvider {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn create_intent(&self, _order_id: Uuid, amount: Money) -> Result<PaymentIntent, PaymentError> {
+        self.simulate_latency().await;
+
+        if amount.is_negative() || amount.is_zero() {
+            return Err(PaymentError::InvalidRequest("Amount must be positive".to_string()));
+        }
+
+        let id = format!("mock_pi_{}", Uuid::new_v4().simple());
+        self.intents.lock().unwrap().insert(
+            id.clone(),
+            MockIntent { amount, captured: false, refunded: 0 },
+        );
+
+        Ok(PaymentIntent { id, amount })
+    }
+
+    async fn confirm(&self, intent_id: &str, payment_method: Option<&str>) -> Result<(), PaymentError> {
+        self.simulate_latency().await;
+
+        let decline = match payment_method {
+            Some("pm_card_declined") => true,
+            Some("pm_card_visa") => false,
+            _ => self.outcome == MockOutcome::Decline,
+        };
+
+        let mut intents = self.intents.lock().unwrap();
+        let intent = intents
+            .get_mut(intent_id)
+            .ok_or_else(|| PaymentError::NotFound(intent_id.to_string()))?;
+
+        if decline {
+            return Err(PaymentError::Declined("Your card was declined".to_string()));
+        }
+
+        intent.captured = true;
+        Ok(())
+    }
+
+    async fn refund(&self, intent_id: &str, amount: Money) -> Result<String, PaymentError> {
+        self.simulate_latency().await;
+
+        let mut intents = self.intents.lock().unwrap();
+        let intent = intents
+            .get_mut(intent_id)
+            .ok_or_else(|| PaymentError::NotFound(intent_id.to_string()))?;
+
+        if !intent.captured {
+            return Err(PaymentError::InvalidRequest("Payment has not been captured".to_string()));
+        }
+        if amount.currency != intent.amount.currency {
+            return Err(PaymentError::InvalidRequest("Refund currency does not match payment".to_string()));
+        }
+        if amount.amount <= 0 || intent.refunded + amount.amount > intent.amount.amount {
+            return Err(PaymentError::InvalidRequest("Refund exceeds the captured amount".to_string()));
+        }
+
+        intent.refunded += amount.amount;
+        Ok(format!("mock_re_{}", Uuid::new_v4().simple()))
+    }
+
+    fn verify_webhook_signature(&self, payload: &[u8], signature: &str) -> Result<(), PaymentError> {
+        let signature = hex::decode(signature.trim()).map_err(|_| PaymentError::InvalidSignature)?;
+
+        // Constant-time comparison
+        self.mac(payload)
+            .verify_slice(&signature)
+            .map_err(|_| PaymentError::InvalidSignature)
+    }
+
+    /// Mock events are `{"id", "type", "payment_id", "failure_reason"?, "amount_cents"?, "refund_id"?}`
+    fn parse_webhook_event(&self, payload: &[u8]) -> Result<PaymentEvent, PaymentError> {
+        serde_json::from_slice(payload).map_err(|e| PaymentError::InvalidRequest(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::Currency;
+
+    fn provider(outcome: MockOutcome) -> MockPaymentProvider {
+        MockPaymentProvider::new(outcome, Duration::ZERO, "test-secret")
+    }
+
+    #[tokio::test]
+    async fn test_confirm_and_refund() {
+        let provider = provider(MockOutcome::Succeed);
+        let amount = Money::new(5000, Currency::USD);
+        let intent = provider.create_intent(Uuid::new_v4(), amount).await.unwrap();
+
+        provider.confirm(&intent.id, None).await.unwrap();
+        provider.refund(&intent.id, Money::new(2000, Currency::USD)).await.unwrap();
+        provider.refund(&intent.id, Money::new(3000, Currency::USD)).await.unwrap();
+        assert!(provider.refund(&intent.id, Money::new(1, Currency::USD)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_decline() {
+        let provider = provider(MockOutcome::Decline);
+        let intent = provider.create_intent(Uuid::new_v4(), Money::new(100, Currency::USD)).await.unwrap();
+
+        assert!(matches!(provider.confirm(&intent.id, None).await, Err(PaymentError::Declined(_))));
+        assert!(provider.confirm(&intent.id, Some("pm_card_visa")).await.is_ok());
+    }
+
+    #[test]
+    fn test_webhook_signature() {
+        let provider = provider(MockOutcome::Succeed);
+        let payload = br#"{"id":"evt_1"}"#;
+        let signature = hex::encode(provider.mac(payload).finalize().into_bytes());
+
+        assert!(provider.verify_webhook_signature(payload, &signature).is_ok());
+        assert!(provider.verify_webhook_signature(b"tampered", &signature).is_err());
+        assert!(provider.verify_webhook_signature(payload, "not-hex").is_err());
+    }
+}
@@ -0,0 +1,112 @@
+//! Payment provider abstraction
+//!
+//! Handlers talk to a `PaymentProvider` trait object so a real gateway can
+//! replace the in-process mock without touching the order flow.
+
+mod mock;
+
+pub use mock::MockPaymentProvider;
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde::Deserialize;
+use thiserror::Error;
+use uuid::Uuid;
+use shared::Money;
+
+/// A payment intent created at the provider for an order
+#[derive(Debug, Clone)]
+pub struct PaymentIntent {
+    pub id: String,
+    pub amount: Money,
+}
+
+/// What a provider webhook reports about a payment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum PaymentEventKind {
+    #[serde(rename = "payment.succeeded")]
+    Succeeded,
+    #[serde(rename = "payment.failed")]
+    Failed,
+    #[serde(rename = "payment.refunded")]
+    Refunded,
+}
+
+impl PaymentEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentEventKind::Succeeded => "payment.succeeded",
+            PaymentEventKind::Failed => "payment.failed",
+            PaymentEventKind::Refunded => "payment.refunded",
+        }
+    }
+}
+
+/// A webhook event in provider-neutral form
+#[derive(Debug, Clone, Deserialize)]
+pub struct PaymentEvent {
+    /// The provider's unique event id, used for deduplication
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: PaymentEventKind,
+    /// The provider's payment intent id
+    pub payment_id: String,
+    #[serde(default)]
+    pub failure_reason: Option<String>,
+    /// Amount refunded by a refund event, in minor units; the rest of the
+    /// payment when absent
+    #[serde(default)]
+    pub amount_cents: Option<i64>,
+    /// The provider's refund id, for refunds that may already be on record
+    #[serde(default)]
+    pub refund_id: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum PaymentError {
+    #[error("Payment declined: {0}")]
+    Declined(String),
+
+    #[error("Unknown payment intent: {0}")]
+    NotFound(String),
+
+    #[error("Invalid payment request: {0}")]
+    InvalidRequest(String),
+
+    #[error("Invalid webhook signature")]
+    InvalidSignature,
+
+    #[error("Payment provider error: {0}")]
+    Provider(String),
+}
+
+#[async_trait]
+pub trait PaymentProvider: Send + Sync {
+    /// Short identifier stored with each payment, e.g. "mock"
+    fn name(&self) -> &'static str;
+
+    /// Create an intent to collect `amount` for an order
+    async fn create_intent(&self, order_id: Uuid, amount: Money) -> Result<PaymentIntent, PaymentError>;
+
+    /// Charge the intent; returns `PaymentError::Declined` if the charge fails
+    async fn confirm(&self, intent_id: &str, payment_method: Option<&str>) -> Result<(), PaymentError>;
+
+    /// Refund part or all of a confirmed intent; returns the provider's refund id
+    async fn refund(&self, intent_id: &str, amount: Money) -> Result<String, PaymentError>;
+
+    /// Check that a webhook payload was signed by the provider
+    fn verify_webhook_signature(&self, payload: &[u8], signature: &str) -> Result<(), PaymentError>;
+
+    /// Decode a verified webhook payload
+    fn parse_webhook_event(&self, payload: &[u8]) -> Result<PaymentEvent, PaymentError>;
+}
+
+/// Build the provider selected by `PAYMENT_PROVIDER` (only "mock" for now)
+pub fn from_env() -> anyhow::Result<Arc<dyn PaymentProvider>> {
+    let provider = std::env::var("PAYMENT_PROVIDER").unwrap_or_else(|_| "mock".to_string());
+
+    match provider.as_str() {
+        "mock" => Ok(Arc::new(MockPaymentProvider::from_env()?)),
+        other => anyhow::bail!("Unsupported PAYMENT_PROVIDER '{}'", other),
+    }
+}
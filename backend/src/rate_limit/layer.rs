@@ -0,0 +1,130 @@
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use tower::{Layer, Service};
+use shared::ApiError;
+use crate::auth::Claims;
+use super::{Decision, RateLimiter};
+
+/// What a request is counted against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyBy {
+    /// The client IP address
+    Ip,
+    /// The authenticated user, falling back to the IP for anonymous requests.
+    /// Must run inside `auth_middleware` to see the user.
+    UserOrIp,
+}
+
+/// Tower layer enforcing a `RateLimiter` on every request it wraps. Responses
+/// carry `RateLimit-Limit`/`-Remaining`/`-Reset`; rejections are 429 with
+/// `Retry-After`.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limiter: Arc<RateLimiter>,
+    key_by: KeyBy,
+}
+
+impl RateLimitLayer {
+    pub fn new(limiter: Arc<RateLimiter>, key_by: KeyBy) -> Self {
+        Self { limiter, key_by }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            limiter: self.limiter.clone(),
+            key_by: self.key_by,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    limiter: Arc<RateLimiter>,
+    key_by: KeyBy,
+}
+
+impl<S> Service<Request> for RateLimitService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let key = request_key(&request, self.key_by);
+        let decision = self.limiter.hit(&key);
+
+        if !decision.allowed {
+            tracing::warn!("Rate limit '{}' exceeded for {}", self.limiter.name(), key);
+            return Box::pin(async move { Ok(rejection(&decision)) });
+        }
+
+        // Use the instance that was polled ready, leaving a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let mut response = inner.call(request).await?;
+            set_rate_limit_headers(response.headers_mut(), &decision);
+            Ok(response)
+        })
+    }
+}
+
+fn request_key(request: &Request, key_by: KeyBy) -> String {
+    if key_by == KeyBy::UserOrIp {
+        if let Some(claims) = request.extensions().get::<Claims>() {
+            return format!("user:{}", claims.sub);
+        }
+    }
+
+    match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+fn set_rate_limit_headers(headers: &mut HeaderMap, decision: &Decision) {
+    headers.insert("ratelimit-limit", HeaderValue::from(decision.limit));
+    headers.insert("ratelimit-remaining", HeaderValue::from(decision.remaining));
+    headers.insert("ratelimit-reset", HeaderValue::from(decision.reset_after.as_secs()));
+}
+
+fn rejection(decision: &Decision) -> Response {
+    // Round up so clients never retry a moment too early
+    let retry_after = decision.retry_after.as_millis().div_ceil(1000).max(1) as u64;
+
+    let mut response = (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(ApiError::new(
+            "TOO_MANY_REQUESTS",
+            format!("Too many requests. Please try again in {} seconds.", retry_after),
+        )),
+    )
+        .into_response();
+
+    set_rate_limit_headers(response.headers_mut(), decision);
+    response.headers_mut().insert("retry-after", HeaderValue::from(retry_after));
+    response
+}
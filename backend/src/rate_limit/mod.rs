@@ -0,0 +1,399 @@
+//! Rate limiting for route groups
+//!
+//! Each `RateLimiter` applies one quota to arbitrary string keys such as
+//! `ip:1.2.3.4`, `user:<uuid>` or `email:a@b.c`, using either a token bucket
+//! (smooth bursts) or a sliding window counter. Request-based groups are
+//! enforced by `RateLimitLayer`; login failures and account lockout are
+//! counted by the handlers themselves.
+
+mod layer;
+mod store;
+
+pub use layer::{KeyBy, RateLimitLayer};
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::SqlitePool;
+
+/// How often idle entries are dropped (and state persisted, if enabled)
+const SWEEP_INTERVAL_SECONDS: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Holds up to `limit` tokens, refilled evenly over `period`
+    TokenBucket,
+    /// At most `limit` events in any `period`, estimated from the current
+    /// and previous fixed windows
+    SlidingWindow,
+}
+
+impl Algorithm {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Algorithm::TokenBucket => "token_bucket",
+            Algorithm::SlidingWindow => "sliding_window",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    pub limit: u32,
+    pub period: Duration,
+}
+
+impl Quota {
+    pub fn new(limit: u32, period_seconds: u64) -> Self {
+        Self {
+            limit,
+            period: Duration::from_secs(period_seconds),
+        }
+    }
+
+    /// Parse `<limit>/<seconds>`, e.g. `5/60`
+    pub fn parse(value: &str) -> Option<Self> {
+        let (limit, seconds) = value.trim().split_once('/')?;
+        let limit: u32 = limit.trim().parse().ok()?;
+        let seconds: u64 = seconds.trim().trim_end_matches('s').parse().ok()?;
+
+        (limit > 0 && seconds > 0).then(|| Self::new(limit, seconds))
+    }
+
+    fn period_ms(&self) -> i64 {
+        self.period.as_millis() as i64
+    }
+}
+
+/// Outcome of a rate limit check, with the values for `RateLimit-*` headers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Time until the quota is fully available again
+    pub reset_after: Duration,
+    /// Time until the next request would be allowed; zero when allowed
+    pub retry_after: Duration,
+}
+
+/// Per-key state. For a token bucket `count` is the tokens left at
+/// `updated_ms`; for a sliding window `count`/`previous` are the events in the
+/// window starting at `updated_ms` and in the one before it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Entry {
+    updated_ms: i64,
+    count: f64,
+    previous: f64,
+}
+
+pub struct RateLimiter {
+    name: &'static str,
+    algorithm: Algorithm,
+    quota: Quota,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl RateLimiter {
+    pub fn new(name: &'static str, algorithm: Algorithm, quota: Quota) -> Self {
+        Self {
+            name,
+            algorithm,
+            quota,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Whether one more event would be allowed, without recording it
+    pub fn check(&self, key: &str) -> Decision {
+        self.apply(key, false, Utc::now().timestamp_millis())
+    }
+
+    /// Record one event if the quota allows it
+    pub fn hit(&self, key: &str) -> Decision {
+        self.apply(key, true, Utc::now().timestamp_millis())
+    }
+
+    /// Forget a key, e.g. after a successful login
+    pub fn reset(&self, key: &str) {
+        self.entries.lock().unwrap().remove(key);
+    }
+
+    /// Drop entries that have fully recovered; returns how many were removed
+    pub fn sweep(&self) -> usize {
+        self.sweep_at(Utc::now().timestamp_millis())
+    }
+
+    fn apply(&self, key: &str, consume: bool, now_ms: i64) -> Decision {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.entry(key.to_string()).or_insert_with(|| self.fresh_entry(now_ms));
+
+        match self.algorithm {
+            Algorithm::TokenBucket => token_bucket(&self.quota, entry, consume, now_ms),
+            Algorithm::SlidingWindow => sliding_window(&self.quota, entry, consume, now_ms),
+        }
+    }
+
+    fn fresh_entry(&self, now_ms: i64) -> Entry {
+        match self.algorithm {
+            Algorithm::TokenBucket => Entry {
+                updated_ms: now_ms,
+                count: self.quota.limit as f64,
+                previous: 0.0,
+            },
+            Algorithm::SlidingWindow => Entry {
+                updated_ms: now_ms - now_ms.rem_euclid(self.quota.period_ms()),
+                count: 0.0,
+                previous: 0.0,
+            },
+        }
+    }
+
+    fn sweep_at(&self, now_ms: i64) -> usize {
+        let period_ms = self.quota.period_ms();
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+
+        // An entry untouched for two periods is back at the full quota
+        entries.retain(|_, entry| now_ms - entry.updated_ms < 2 * period_ms);
+
+        before - entries.len()
+    }
+}
+
+fn token_bucket(quota: &Quota, entry: &mut Entry, consume: bool, now_ms: i64) -> Decision {
+    let limit = quota.limit as f64;
+    let rate_per_ms = limit / quota.period_ms() as f64;
+
+    let elapsed = (now_ms - entry.updated_ms).max(0) as f64;
+    entry.count = (entry.count + elapsed * rate_per_ms).min(limit);
+    entry.updated_ms = now_ms;
+
+    let allowed = entry.count >= 1.0;
+    if allowed && consume {
+        entry.count -= 1.0;
+    }
+
+    let retry_after_ms = if allowed { 0.0 } else { (1.0 - entry.count) / rate_per_ms };
+
+    Decision {
+        allowed,
+        limit: quota.limit,
+        remaining: entry.count.floor() as u32,
+        reset_after: Duration::from_millis(((limit - entry.count) / rate_per_ms).ceil() as u64),
+        retry_after: Duration::from_millis(retry_after_ms.ceil() as u64),
+    }
+}
+
+fn sliding_window(quota: &Quota, entry: &mut Entry, consume: bool, now_ms: i64) -> Decision {
+    let limit = quota.limit as f64;
+    let period_ms = quota.period_ms();
+    let window_start = now_ms - now_ms.rem_euclid(period_ms);
+
+    if entry.updated_ms != window_start {
+        entry.previous = if entry.updated_ms == window_start - period_ms { entry.count } else { 0.0 };
+        entry.count = 0.0;
+        entry.updated_ms = window_start;
+    }
+
+    let into_window = (now_ms - window_start) as f64 / period_ms as f64;
+    let estimate = |count: f64| entry.previous * (1.0 - into_window) + count;
+
+    let allowed = estimate(entry.count) + 1.0 <= limit;
+    if allowed && consume {
+        entry.count += 1.0;
+    }
+    let used = estimate(entry.count);
+
+    let retry_after_ms = if allowed {
+        0.0
+    } else if entry.count + 1.0 <= limit {
+        // Wait for the previous window's weight to decay far enough
+        let weight = (limit - 1.0 - entry.count) / entry.previous;
+        (1.0 - weight - into_window) * period_ms as f64
+    } else {
+        // This window alone is full; wait until it becomes the previous one
+        let weight = (limit - 1.0) / entry.count;
+        (2.0 - weight - into_window) * period_ms as f64
+    };
+
+    Decision {
+        allowed,
+        limit: quota.limit,
+        remaining: (limit - used).max(0.0).floor() as u32,
+        reset_after: Duration::from_millis((window_start + 2 * period_ms - now_ms) as u64),
+        retry_after: Duration::from_millis(retry_after_ms.max(0.0).ceil() as u64),
+    }
+}
+
+/// The limiters for every route group
+#[derive(Clone)]
+pub struct RateLimits {
+    /// Failed logins and 2FA codes per client IP
+    pub login: Arc<RateLimiter>,
+    /// Failed logins per account email; locks the account temporarily
+    pub account: Arc<RateLimiter>,
+    /// Registrations and recovery emails per client IP
+    pub register: Arc<RateLimiter>,
+    /// Product listing and search per client IP
+    pub search: Arc<RateLimiter>,
+    /// Order placement and payment per user
+    pub checkout: Arc<RateLimiter>,
+}
+
+impl RateLimits {
+    /// Build the limiters, overriding default quotas with
+    /// `RATE_LIMIT_<GROUP>=<limit>/<seconds>`
+    pub fn from_env() -> Result<Self> {
+        let limiter = |name: &'static str, algorithm: Algorithm, default: Quota| -> Result<Arc<RateLimiter>> {
+            let var = format!("RATE_LIMIT_{}", name.to_uppercase());
+            let quota = match std::env::var(&var) {
+                Ok(value) => Quota::parse(&value)
+                    .ok_or_else(|| anyhow::anyhow!("{} must look like <limit>/<seconds>, got '{}'", var, value))?,
+                Err(_) => default,
+            };
+            Ok(Arc::new(RateLimiter::new(name, algorithm, quota)))
+        };
+
+        Ok(Self {
+            login: limiter("login", Algorithm::SlidingWindow, Quota::new(5, 60))?,
+            account: limiter("account", Algorithm::SlidingWindow, Quota::new(10, 900))?,
+            register: limiter("register", Algorithm::SlidingWindow, Quota::new(5, 3600))?,
+            search: limiter("search", Algorithm::TokenBucket, Quota::new(60, 60))?,
+            checkout: limiter("checkout", Algorithm::TokenBucket, Quota::new(10, 60))?,
+        })
+    }
+
+    fn all(&self) -> [&Arc<RateLimiter>; 5] {
+        [&self.login, &self.account, &self.register, &self.search, &self.checkout]
+    }
+
+    /// Load persisted state saved by an earlier process
+    pub async fn restore(&self, pool: &SqlitePool) -> Result<()> {
+        for limiter in self.all() {
+            store::restore(pool, limiter).await?;
+        }
+        Ok(())
+    }
+
+    /// Save the current state of every limiter
+    pub async fn persist(&self, pool: &SqlitePool) -> Result<()> {
+        for limiter in self.all() {
+            store::persist(pool, limiter).await?;
+        }
+        Ok(())
+    }
+
+    /// Periodically drop idle entries and, with `pool`, persist the rest
+    pub fn spawn_sweeper(&self, pool: Option<SqlitePool>) -> tokio::task::JoinHandle<()> {
+        let limits = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(SWEEP_INTERVAL_SECONDS));
+            interval.tick().await;
+
+            loop {
+                interval.tick().await;
+
+                let removed: usize = limits.all().iter().map(|limiter| limiter.sweep()).sum();
+                if removed > 0 {
+                    tracing::debug!("Rate limit sweeper removed {} idle entries", removed);
+                }
+
+                if let Some(pool) = &pool {
+                    if let Err(e) = limits.persist(pool).await {
+                        tracing::error!("Failed to persist rate limit state: {}", e);
+                    }
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: i64 = 1000;
+
+    #[test]
+    fn sliding_window_blocks_after_limit_until_reset() {
+        let limiter = RateLimiter::new("login", Algorithm::SlidingWindow, Quota::new(5, 60));
+        let start = 120 * SECOND;
+
+        for i in 0..5 {
+            let decision = limiter.apply("ip:127.0.0.1", true, start + i);
+            assert!(decision.allowed);
+            assert_eq!(decision.remaining, 4 - i as u32);
+        }
+
+        let blocked = limiter.apply("ip:127.0.0.1", false, start + 10);
+        assert!(!blocked.allowed);
+        assert!(blocked.retry_after > Duration::from_secs(60));
+
+        // Other keys are independent
+        assert!(limiter.apply("ip:10.0.0.1", false, start).allowed);
+
+        limiter.reset("ip:127.0.0.1");
+        let decision = limiter.apply("ip:127.0.0.1", false, start + 20);
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, 5);
+    }
+
+    #[test]
+    fn sliding_window_weights_previous_window() {
+        let limiter = RateLimiter::new("register", Algorithm::SlidingWindow, Quota::new(4, 60));
+        let start = 60 * SECOND;
+
+        for _ in 0..4 {
+            assert!(limiter.apply("k", true, start).allowed);
+        }
+
+        // Halfway into the next window the previous 4 count as 2
+        let halfway = limiter.apply("k", true, start + 90 * SECOND);
+        assert!(halfway.allowed);
+        assert_eq!(halfway.remaining, 1);
+        assert!(limiter.apply("k", true, start + 90 * SECOND).allowed);
+        assert!(!limiter.apply("k", false, start + 90 * SECOND).allowed);
+    }
+
+    #[test]
+    fn token_bucket_refills_over_time() {
+        let limiter = RateLimiter::new("search", Algorithm::TokenBucket, Quota::new(2, 10));
+        let start = 1_000 * SECOND;
+
+        assert!(limiter.apply("k", true, start).allowed);
+        assert!(limiter.apply("k", true, start).allowed);
+
+        let empty = limiter.apply("k", true, start);
+        assert!(!empty.allowed);
+        assert_eq!(empty.retry_after, Duration::from_secs(5));
+
+        // One token every 5 seconds
+        assert!(limiter.apply("k", true, start + 5 * SECOND).allowed);
+        assert!(!limiter.apply("k", false, start + 5 * SECOND).allowed);
+    }
+
+    #[test]
+    fn sweep_drops_recovered_entries() {
+        let limiter = RateLimiter::new("checkout", Algorithm::TokenBucket, Quota::new(1, 10));
+        limiter.apply("k", true, 0);
+
+        assert_eq!(limiter.sweep_at(5 * SECOND), 0);
+        assert_eq!(limiter.sweep_at(30 * SECOND), 1);
+    }
+
+    #[test]
+    fn quota_parses_limit_per_seconds() {
+        assert_eq!(Quota::parse("5/60"), Some(Quota::new(5, 60)));
+        assert_eq!(Quota::parse(" 10 / 30s "), Some(Quota::new(10, 30)));
+        assert_eq!(Quota::parse("0/60"), None);
+        assert_eq!(Quota::parse("five/60"), None);
+    }
+}
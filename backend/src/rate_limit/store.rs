@@ -0,0 +1,63 @@
+//! Optional SQLite persistence so limits and lockouts survive restarts
+
+use anyhow::Result;
+use sqlx::SqlitePool;
+use super::{Entry, RateLimiter};
+
+/// (key, updated_ms, count, previous)
+type EntryRow = (String, i64, f64, f64);
+
+pub(super) async fn restore(pool: &SqlitePool, limiter: &RateLimiter) -> Result<()> {
+    let rows: Vec<EntryRow> = sqlx::query_as(
+        "SELECT key, updated_ms, count, previous FROM rate_limits WHERE policy = ? AND algorithm = ?",
+    )
+    .bind(limiter.name)
+    .bind(limiter.algorithm.as_str())
+    .fetch_all(pool)
+    .await?;
+
+    let mut entries = limiter.entries.lock().unwrap();
+    for (key, updated_ms, count, previous) in rows {
+        entries.insert(key, Entry { updated_ms, count, previous });
+    }
+
+    Ok(())
+}
+
+/// Replace the stored state of one limiter with its in-memory entries
+pub(super) async fn persist(pool: &SqlitePool, limiter: &RateLimiter) -> Result<()> {
+    let snapshot: Vec<(String, Entry)> = limiter
+        .entries
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(key, entry)| (key.clone(), *entry))
+        .collect();
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM rate_limits WHERE policy = ?")
+        .bind(limiter.name)
+        .execute(&mut *tx)
+        .await?;
+
+    for (key, entry) in snapshot {
+        sqlx::query(
+            r#"
+            INSERT INTO rate_limits (policy, key, algorithm, updated_ms, count, previous)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(limiter.name)
+        .bind(key)
+        .bind(limiter.algorithm.as_str())
+        .bind(entry.updated_ms)
+        .bind(entry.count)
+        .bind(entry.previous)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
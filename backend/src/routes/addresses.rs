@@ -0,0 +1,112 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+use shared::{Address, SaveAddressRequest, AddressResponse, AddressListResponse, MessageResponse, ApiError};
+use crate::{AppState, auth::CurrentUser, db::AddressRepository};
+
+/// Trim free-text fields and reject addresses that fail validation
+pub fn normalize_address(mut a: Address) -> Result<Address, (StatusCode, Json<ApiError>)> {
+    a.name = a.name.trim().to_string();
+    a.line1 = a.line1.trim().to_string();
+    a.city = a.city.trim().to_string();
+    a.postal_code = a.postal_code.trim().to_string();
+    a.country = a.country.trim().to_ascii_uppercase();
+    for field in [&mut a.line2, &mut a.region, &mut a.phone] {
+        *field = field.take().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    }
+
+    a.validate()
+        .map_err(|msg| (StatusCode::BAD_REQUEST, Json(ApiError::validation_error(msg))))?;
+
+    Ok(a)
+}
+
+fn normalize_request(mut req: SaveAddressRequest) -> Result<SaveAddressRequest, (StatusCode, Json<ApiError>)> {
+    req.address = normalize_address(req.address)?;
+    req.label = req.label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
+
+    if req.label.as_ref().is_some_and(|l| l.chars().count() > 50) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::validation_error("Label must be at most 50 characters")),
+        ));
+    }
+
+    Ok(req)
+}
+
+pub async fn list_addresses(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<Json<AddressListResponse>, (StatusCode, Json<ApiError>)> {
+    let addresses = AddressRepository::list_by_user(&state.db.pool, claims.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok(Json(AddressListResponse { addresses }))
+}
+
+pub async fn create_address(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Json(req): Json<SaveAddressRequest>,
+) -> Result<(StatusCode, Json<AddressResponse>), (StatusCode, Json<ApiError>)> {
+    let req = normalize_request(req)?;
+
+    let address = AddressRepository::create(&state.db.pool, claims.sub, &req)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok((StatusCode::CREATED, Json(AddressResponse { address })))
+}
+
+pub async fn update_address(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Path(id): Path<String>,
+    Json(req): Json<SaveAddressRequest>,
+) -> Result<Json<AddressResponse>, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid address ID")))
+    })?;
+
+    let req = normalize_request(req)?;
+
+    let address = AddressRepository::update(&state.db.pool, claims.sub, id, &req)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Address not found")))
+        })?;
+
+    Ok(Json(AddressResponse { address }))
+}
+
+pub async fn delete_address(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Path(id): Path<String>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid address ID")))
+    })?;
+
+    let deleted = AddressRepository::delete(&state.db.pool, claims.sub, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if !deleted {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiError::not_found("Address not found")),
+        ));
+    }
+
+    Ok(Json(MessageResponse {
+        message: "Address deleted".to_string(),
+    }))
+}
@@ -0,0 +1,269 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+use shared::{
+    CreateProductRequest, UpdateProductRequest, Currency, Money, ProductResponse, MessageResponse, ApiError,
+    AdminOrderListParams, OrderListResponse, OrderResponse, OrderStatus, UpdateOrderStatusRequest,
+};
+use crate::{AppState, auth::CurrentUser, db::{CategoryRepository, OrderRepository, ProductRepository, ReservationRepository, VariantRepository}};
+use super::variants;
+
+/// Keep type-ahead in step with the catalog. The product change itself has
+/// already been saved, so a failure here is logged rather than returned.
+pub(super) async fn refresh_suggestions(state: &AppState) {
+    if let Err(e) = ProductRepository::refresh_suggestions(&state.db.pool, &state.suggestions).await {
+        tracing::error!("Failed to refresh search suggestions: {}", e);
+    }
+}
+
+/// Validate product fields shared by create and update
+fn validate_product_fields(
+    name: Option<&str>,
+    category: Option<&str>,
+    price: Option<Money>,
+    stock: Option<i32>,
+    reorder_threshold: Option<i32>,
+) -> Result<(), (StatusCode, Json<ApiError>)> {
+    let error = |message: &str| {
+        Err((StatusCode::BAD_REQUEST, Json(ApiError::validation_error(message))))
+    };
+
+    if name.is_some_and(|n| n.trim().is_empty()) {
+        return error("Product name must not be empty");
+    }
+    if category.is_some_and(|c| c.trim().is_empty()) {
+        return error("Product category must not be empty");
+    }
+    if price.is_some_and(|p| p.is_negative()) {
+        return error("Price must not be negative");
+    }
+    if price.is_some_and(|p| p.currency != Currency::default()) {
+        return error("Price must be in the store currency (USD)");
+    }
+    if stock.is_some_and(|s| s < 0) {
+        return error("Stock must not be negative");
+    }
+    if reorder_threshold.is_some_and(|t| t < 1) {
+        return error("Reorder threshold must be at least 1");
+    }
+
+    Ok(())
+}
+
+/// Resolve a category given by name or slug to the name stored on products
+async fn category_name(state: &AppState, category: &str) -> Result<String, (StatusCode, Json<ApiError>)> {
+    let category = CategoryRepository::find(&state.db.pool, category.trim())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::BAD_REQUEST, Json(ApiError::validation_error(format!("Unknown category '{}'", category.trim()))))
+        })?;
+
+    Ok(category.name)
+}
+
+pub async fn create_product(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Json(mut req): Json<CreateProductRequest>,
+) -> Result<(StatusCode, Json<ProductResponse>), (StatusCode, Json<ApiError>)> {
+    validate_product_fields(
+        Some(&req.name),
+        Some(&req.category),
+        Some(req.price),
+        Some(req.stock),
+        req.reorder_threshold,
+    )?;
+
+    req.category = category_name(&state, &req.category).await?;
+
+    let product = ProductRepository::create(&state.db.pool, &req, Some(claims.sub))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    refresh_suggestions(&state).await;
+
+    Ok((StatusCode::CREATED, Json(ProductResponse { product, options: Vec::new(), variants: Vec::new(), images: Vec::new() })))
+}
+
+pub async fn update_product(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Path(id): Path<String>,
+    Json(mut req): Json<UpdateProductRequest>,
+) -> Result<Json<ProductResponse>, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid product ID")))
+    })?;
+
+    validate_product_fields(
+        req.name.as_deref(),
+        req.category.as_deref(),
+        req.price,
+        req.stock,
+        req.reorder_threshold,
+    )?;
+
+    if let Some(category) = &req.category {
+        req.category = Some(category_name(&state, category).await?);
+    }
+
+    if req.stock.is_some() {
+        let has_variants = VariantRepository::has_variants(&state.db.pool, id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+        if has_variants {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ApiError::validation_error("Stock of a product with variants is set per variant")),
+            ));
+        }
+    }
+
+    let product = ProductRepository::update(&state.db.pool, id, &req, Some(claims.sub))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Product not found")))
+        })?;
+
+    refresh_suggestions(&state).await;
+
+    Ok(Json(variants::product_response(&state, product, true).await?))
+}
+
+pub async fn delete_product(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid product ID")))
+    })?;
+
+    let deactivated = ProductRepository::deactivate(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if !deactivated {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiError::not_found("Product not found")),
+        ));
+    }
+
+    refresh_suggestions(&state).await;
+
+    Ok(Json(MessageResponse {
+        message: "Product deleted successfully".to_string(),
+    }))
+}
+
+pub async fn list_orders(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AdminOrderListParams>,
+) -> Result<Json<OrderListResponse>, (StatusCode, Json<ApiError>)> {
+    let page = params.page.unwrap_or(1).max(1);
+    let limit = params.limit.unwrap_or(20).clamp(1, 100);
+
+    let (orders, total) = OrderRepository::list_all(&state.db.pool, &params, page, limit)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok(Json(OrderListResponse { orders, total }))
+}
+
+pub async fn get_order(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<OrderResponse>, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid order ID")))
+    })?;
+
+    let order_with_items = OrderRepository::get_by_id(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Order not found")))
+        })?;
+
+    Ok(Json(OrderResponse { order: order_with_items }))
+}
+
+pub async fn update_order_status(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateOrderStatusRequest>,
+) -> Result<Json<OrderResponse>, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid order ID")))
+    })?;
+
+    let order_with_items = OrderRepository::get_by_id(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Order not found")))
+        })?;
+
+    let current = &order_with_items.order.status;
+    if !current.can_transition_to(&req.status) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::bad_request(format!(
+                "Cannot change order status from {} to {}",
+                current.as_str(),
+                req.status.as_str()
+            ))),
+        ));
+    }
+
+    // Marking an order paid by hand takes its stock like a payment would;
+    // if the holds expired, take it again or refuse
+    if req.status == OrderStatus::Paid {
+        ReservationRepository::renew(&state.db.pool, id, state.reservation_ttl)
+            .await
+            .map_err(|e| {
+                let msg = e.to_string();
+                if msg.contains("Insufficient stock") {
+                    (StatusCode::CONFLICT, Json(ApiError::new("OUT_OF_STOCK", msg)))
+                } else if msg.contains("cannot be used") {
+                    (StatusCode::CONFLICT, Json(ApiError::new("INVALID_COUPON", msg)))
+                } else {
+                    (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(msg)))
+                }
+            })?;
+    }
+
+    // Cancellation also releases the held stock; payment converts it into sales
+    let updated = if req.status == OrderStatus::Cancelled {
+        OrderRepository::cancel(&state.db.pool, id, current, Some(claims.sub)).await
+    } else if req.status == OrderStatus::Paid {
+        OrderRepository::mark_paid(&state.db.pool, id, Some(claims.sub)).await
+    } else {
+        OrderRepository::update_status(&state.db.pool, id, current, &req.status, Some(claims.sub)).await
+    }
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if !updated {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ApiError::new("CONFLICT", "Order status changed, please refresh")),
+        ));
+    }
+
+    let order_with_items = OrderRepository::get_by_id(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Order not found")))
+        })?;
+
+    Ok(Json(OrderResponse { order: order_with_items }))
+}
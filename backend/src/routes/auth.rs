@@ -1,12 +1,61 @@
 use axum::{
-    extract::{ConnectInfo, State},
+    extract::{ConnectInfo, Query, State},
     http::StatusCode,
     Json,
 };
 use std::net::SocketAddr;
 use std::sync::Arc;
-use shared::{RegisterRequest, LoginRequest, AuthResponse, ApiError, UserProfile};
-use crate::{AppState, auth, db::UserRepository};
+use chrono::Duration;
+use serde::Deserialize;
+use shared::{
+    RegisterRequest, LoginRequest, RefreshRequest, LogoutRequest, ForgotPasswordRequest, ResetPasswordRequest,
+    AuthResponse, LoginResponse, MessageResponse, ApiError, User, UserProfile,
+};
+use crate::{
+    AppState,
+    auth::{self, CurrentUser},
+    db::{
+        EmailVerificationRepository, PasswordResetRepository, RefreshOutcome, SessionRepository,
+        TwoFactorRepository, UserRepository,
+    },
+    mailer::EmailMessage,
+    rate_limit::Decision,
+};
+
+/// How long a password reset link stays valid
+const RESET_TOKEN_TTL_MINUTES: i64 = 60;
+/// How long an email verification link stays valid
+const VERIFICATION_TOKEN_TTL_HOURS: i64 = 48;
+
+/// Count a failed password or code against both the client IP and the account
+pub(super) fn record_login_failure(state: &AppState, ip_key: &str, account_key: &str) {
+    state.rate_limits.login.hit(ip_key);
+    state.rate_limits.account.hit(account_key);
+
+    if !state.rate_limits.account.check(account_key).allowed {
+        tracing::warn!("Locking {} after repeated failed logins", account_key);
+    }
+}
+
+/// 429 for an exhausted login quota. Lockouts get their own code so the
+/// client can tell the user the account, not just this device, is blocked.
+pub(super) fn login_throttled(decision: &Decision, account_locked: bool) -> (StatusCode, Json<ApiError>) {
+    let seconds = decision.retry_after.as_secs().max(1);
+
+    let error = if account_locked {
+        ApiError::new(
+            "ACCOUNT_LOCKED",
+            format!("Too many failed sign-in attempts for this account. Please try again in {} seconds.", seconds),
+        )
+    } else {
+        ApiError::new(
+            "TOO_MANY_REQUESTS",
+            format!("Too many login attempts. Please try again in {} seconds.", seconds),
+        )
+    };
+
+    (StatusCode::TOO_MANY_REQUESTS, Json(error))
+}
 
 /// Validate email format using a simple regex pattern
 fn is_valid_email(email: &str) -> bool {
@@ -94,32 +143,34 @@ pub async fn register(
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
 
-    // Generate token
-    let token = auth::create_token(user.id, &user.email, &user.role, &state.jwt_secret)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+    // Delivery failures must not fail the registration; the user can resend
+    let background_state = state.clone();
+    let background_user = user.clone();
+    tokio::spawn(async move {
+        if let Err(e) = send_verification_email(&background_state, &background_user).await {
+            tracing::error!("Failed to send verification email: {}", e);
+        }
+    });
 
-    Ok(Json(AuthResponse {
-        token,
-        user: UserProfile::from(user),
-    }))
+    start_session(&state, user, false).await
 }
 
 pub async fn login(
     State(state): State<Arc<AppState>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Json(req): Json<LoginRequest>,
-) -> Result<Json<AuthResponse>, (StatusCode, Json<ApiError>)> {
-    let client_ip = addr.ip();
+) -> Result<Json<LoginResponse>, (StatusCode, Json<ApiError>)> {
+    let ip_key = format!("ip:{}", addr.ip());
+    let account_key = format!("email:{}", req.email.trim().to_lowercase());
 
-    // Check rate limit before processing
-    if let Err(seconds_remaining) = state.login_rate_limiter.check(client_ip) {
-        return Err((
-            StatusCode::TOO_MANY_REQUESTS,
-            Json(ApiError::new(
-                "TOO_MANY_REQUESTS",
-                format!("Too many login attempts. Please try again in {} seconds.", seconds_remaining),
-            )),
-        ));
+    // Check the client and the account before doing any password work
+    let decision = state.rate_limits.login.check(&ip_key);
+    if !decision.allowed {
+        return Err(login_throttled(&decision, false));
+    }
+    let decision = state.rate_limits.account.check(&account_key);
+    if !decision.allowed {
+        return Err(login_throttled(&decision, true));
     }
 
     // Find user by email
@@ -127,7 +178,7 @@ pub async fn login(
         Ok(Some(user)) => user,
         Ok(None) => {
             // Record failed attempt even for non-existent user (prevent enumeration)
-            let _ = state.login_rate_limiter.record_failure(client_ip);
+            record_login_failure(&state, &ip_key, &account_key);
             return Err((
                 StatusCode::UNAUTHORIZED,
                 Json(ApiError::unauthorized("Invalid email or password")),
@@ -146,31 +197,272 @@ pub async fn login(
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
 
     if !is_valid {
-        // Record failed attempt
-        if let Err(seconds_remaining) = state.login_rate_limiter.record_failure(client_ip) {
-            return Err((
-                StatusCode::TOO_MANY_REQUESTS,
-                Json(ApiError::new(
-                    "TOO_MANY_REQUESTS",
-                    format!("Too many login attempts. Please try again in {} seconds.", seconds_remaining),
-                )),
-            ));
-        }
+        record_login_failure(&state, &ip_key, &account_key);
         return Err((
             StatusCode::UNAUTHORIZED,
             Json(ApiError::unauthorized("Invalid email or password")),
         ));
     }
 
-    // Successful login - clear rate limit for this IP
-    state.login_rate_limiter.clear(client_ip);
+    // Successful login - clear failures for this IP and account
+    state.rate_limits.login.reset(&ip_key);
+    state.rate_limits.account.reset(&account_key);
+
+    // With 2FA enabled the password alone only earns a challenge token
+    let two_factor = TwoFactorRepository::get(&state.db.pool, user.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if two_factor.enabled {
+        let challenge_token = auth::create_challenge_token(user.id, &state.jwt_secret)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+        return Ok(Json(LoginResponse::TwoFactorRequired {
+            challenge_token,
+            expires_in: auth::CHALLENGE_TTL_SECONDS,
+        }));
+    }
+
+    let Json(response) = start_session(&state, user, false).await?;
+    Ok(Json(LoginResponse::Authenticated(response)))
+}
 
-    // Generate token
-    let token = auth::create_token(user.id, &user.email, &user.role, &state.jwt_secret)
+/// Open a new session for `user` and issue its first token pair. `mfa` is
+/// true when the login passed a second factor.
+pub(super) async fn start_session(
+    state: &AppState,
+    user: User,
+    mfa: bool,
+) -> Result<Json<AuthResponse>, (StatusCode, Json<ApiError>)> {
+    let refresh_token = auth::generate_token();
+    let session_id = SessionRepository::create(&state.db.pool, user.id, &refresh_token, auth::refresh_token_ttl(), mfa)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    let token = auth::create_token(user.id, &user.email, &user.role, session_id, mfa, &state.jwt_secret)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
 
     Ok(Json(AuthResponse {
         token,
+        refresh_token,
         user: UserProfile::from(user),
     }))
 }
+
+/// Exchange a refresh token for a new access/refresh pair. The presented
+/// token is single-use; replaying it revokes the whole session.
+pub async fn refresh(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<RefreshRequest>,
+) -> Result<Json<AuthResponse>, (StatusCode, Json<ApiError>)> {
+    let refresh_token = auth::generate_token();
+
+    let outcome = SessionRepository::rotate(&state.db.pool, &req.refresh_token, &refresh_token, auth::refresh_token_ttl())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    let (user_id, session_id, mfa) = match outcome {
+        RefreshOutcome::Rotated { user_id, family_id, mfa } => (user_id, family_id, mfa),
+        RefreshOutcome::Reused => {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                Json(ApiError::unauthorized("Refresh token was already used; session revoked")),
+            ));
+        }
+        RefreshOutcome::Invalid => {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                Json(ApiError::unauthorized("Invalid or expired refresh token")),
+            ));
+        }
+    };
+
+    // Reload the user so role changes take effect on the next access token
+    let user = UserRepository::find_by_id(&state.db.pool, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(ApiError::unauthorized("User no longer exists"))))?;
+
+    let token = auth::create_token(user.id, &user.email, &user.role, session_id, mfa, &state.jwt_secret)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok(Json(AuthResponse {
+        token,
+        refresh_token,
+        user: UserProfile::from(user),
+    }))
+}
+
+/// Revoke the current session, or all of the user's sessions with `all`
+pub async fn logout(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Json(req): Json<LogoutRequest>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<ApiError>)> {
+    let result = if req.all {
+        SessionRepository::revoke_all_for_user(&state.db.pool, claims.sub).await
+    } else {
+        SessionRepository::revoke_family(&state.db.pool, claims.jti).await
+    };
+    result.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    let message = if req.all {
+        "Logged out of all sessions"
+    } else {
+        "Logged out"
+    };
+
+    Ok(Json(MessageResponse {
+        message: message.to_string(),
+    }))
+}
+
+/// Email a password reset link. The response is identical whether or not the
+/// address belongs to an account, and the lookup and delivery run in the
+/// background so response timing does not reveal it either.
+pub async fn forgot_password(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ForgotPasswordRequest>,
+) -> Json<MessageResponse> {
+    tokio::spawn(async move {
+        if let Err(e) = send_reset_email(&state, &req.email).await {
+            tracing::error!("Failed to send password reset email: {}", e);
+        }
+    });
+
+    Json(MessageResponse {
+        message: "If an account exists for that email, a password reset link has been sent".to_string(),
+    })
+}
+
+async fn send_reset_email(state: &AppState, email: &str) -> anyhow::Result<()> {
+    let Some(user) = UserRepository::find_by_email(&state.db.pool, email).await? else {
+        return Ok(());
+    };
+
+    let token = auth::generate_token();
+    PasswordResetRepository::create(&state.db.pool, user.id, &token, Duration::minutes(RESET_TOKEN_TTL_MINUTES))
+        .await?;
+
+    let message = EmailMessage {
+        to: user.email,
+        subject: "Reset your password".to_string(),
+        body: format!(
+            "Hi {},\n\nUse the link below to choose a new password. It expires in {} minutes and can be used once.\n\n{}/reset-password?token={}\n\nIf you did not ask for this, you can ignore this email.\n",
+            user.username, RESET_TOKEN_TTL_MINUTES, state.app_url, token
+        ),
+    };
+
+    state.mailer.send(&message).await?;
+    tracing::info!("Password reset email for user {} queued via {} mailer", user.id, state.mailer.name());
+    Ok(())
+}
+
+/// Set a new password with a reset token. Signs the user out everywhere.
+pub async fn reset_password(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ResetPasswordRequest>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<ApiError>)> {
+    if !is_valid_password(&req.new_password) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::validation_error(
+                "Password must be at least 8 characters and contain both letters and numbers"
+            )),
+        ));
+    }
+
+    let password_hash = auth::hash_password(&req.new_password)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    let user_id = PasswordResetRepository::consume(&state.db.pool, &req.token, &password_hash)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if user_id.is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::bad_request("Invalid or expired reset token")),
+        ));
+    }
+
+    Ok(Json(MessageResponse {
+        message: "Password has been reset. Please log in with your new password".to_string(),
+    }))
+}
+
+async fn send_verification_email(state: &AppState, user: &User) -> anyhow::Result<()> {
+    let token = auth::generate_token();
+    EmailVerificationRepository::create(
+        &state.db.pool,
+        user.id,
+        &token,
+        Duration::hours(VERIFICATION_TOKEN_TTL_HOURS),
+    )
+    .await?;
+
+    let message = EmailMessage {
+        to: user.email.clone(),
+        subject: "Verify your email address".to_string(),
+        body: format!(
+            "Hi {},\n\nPlease confirm your email address by opening the link below. It expires in {} hours.\n\n{}/verify-email?token={}\n",
+            user.username, VERIFICATION_TOKEN_TTL_HOURS, state.app_url, token
+        ),
+    };
+
+    state.mailer.send(&message).await?;
+    tracing::info!("Verification email for user {} queued via {} mailer", user.id, state.mailer.name());
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyEmailParams {
+    pub token: String,
+}
+
+/// Confirm ownership of an email address with the emailed token
+pub async fn verify_email(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<VerifyEmailParams>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<ApiError>)> {
+    let user_id = EmailVerificationRepository::consume(&state.db.pool, &params.token)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if user_id.is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::bad_request("Invalid or expired verification token")),
+        ));
+    }
+
+    Ok(Json(MessageResponse {
+        message: "Email address verified".to_string(),
+    }))
+}
+
+/// Send a fresh verification link to the current user
+pub async fn resend_verification(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<ApiError>)> {
+    let user = UserRepository::find_by_id(&state.db.pool, claims.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, Json(ApiError::not_found("User not found"))))?;
+
+    if user.email_verified_at.is_some() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::bad_request("Email address is already verified")),
+        ));
+    }
+
+    send_verification_email(&state, &user)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok(Json(MessageResponse {
+        message: format!("Verification email sent to {}", user.email),
+    }))
+}
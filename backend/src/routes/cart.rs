@@ -1,40 +1,67 @@
 use axum::{
-    extract::{Path, State},
-    http::{header, StatusCode},
+    extract::{Path, Query, State},
+    http::StatusCode,
     Json,
 };
+use serde::Deserialize;
 use std::sync::Arc;
 use uuid::Uuid;
-use shared::{AddToCartRequest, UpdateCartItemRequest, CartResponse, MessageResponse, ApiError};
-use crate::{AppState, auth, db::{CartRepository, ProductRepository}};
+use shared::{AddToCartRequest, ApplyCouponRequest, UpdateCartItemRequest, CartResponse, MessageResponse, ApiError, Product};
+use crate::{AppState, auth::CurrentUser, db::{CartRepository, CouponRepository, ProductRepository, VariantRepository}};
 
-// Helper to extract user from token
-async fn get_user_id(
+/// Selects the variant line of a cart item; omitted for products without variants
+#[derive(Debug, Deserialize)]
+pub struct CartItemQuery {
+    pub variant_id: Option<Uuid>,
+}
+
+/// Stock available for a product or one of its variants, net of holds by
+/// unpaid orders. Products sold by variant can only be added to the cart
+/// with an active variant chosen.
+async fn available_stock(
     state: &AppState,
-    auth_header: Option<&str>,
-) -> Result<Uuid, (StatusCode, Json<ApiError>)> {
-    let token = auth_header
-        .and_then(|h| h.strip_prefix("Bearer "))
+    product: &Product,
+    variant_id: Option<Uuid>,
+) -> Result<i32, (StatusCode, Json<ApiError>)> {
+    let Some(variant_id) = variant_id else {
+        let has_variants = VariantRepository::has_variants(&state.db.pool, product.id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+        if has_variants {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ApiError::validation_error("Choose a variant of this product")),
+            ));
+        }
+        return Ok(product.available_stock());
+    };
+
+    let variant = VariantRepository::get_by_id(&state.db.pool, variant_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .filter(|variant| variant.product_id == product.id)
         .ok_or_else(|| {
-            (StatusCode::UNAUTHORIZED, Json(ApiError::unauthorized("Missing authorization header")))
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Variant not found")))
         })?;
 
-    let claims = auth::verify_token(token, &state.jwt_secret)
-        .map_err(|_| {
-            (StatusCode::UNAUTHORIZED, Json(ApiError::unauthorized("Invalid or expired token")))
-        })?;
+    if !variant.is_active {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::bad_request("Variant is not available")),
+        ));
+    }
 
-    Ok(claims.sub)
+    Ok(variant.available_stock())
 }
 
 pub async fn get_cart(
     State(state): State<Arc<AppState>>,
-    headers: axum::http::HeaderMap,
+    CurrentUser(claims): CurrentUser,
 ) -> Result<Json<CartResponse>, (StatusCode, Json<ApiError>)> {
-    let auth_header = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok());
-    let user_id = get_user_id(&state, auth_header).await?;
+    let user_id = claims.sub;
 
-    let cart = CartRepository::get_cart(&state.db.pool, user_id)
+    let cart = CartRepository::get_cart(&state.db.pool, user_id, state.shipping_fee)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
 
@@ -43,11 +70,10 @@ pub async fn get_cart(
 
 pub async fn add_to_cart(
     State(state): State<Arc<AppState>>,
-    headers: axum::http::HeaderMap,
+    CurrentUser(claims): CurrentUser,
     Json(req): Json<AddToCartRequest>,
 ) -> Result<Json<CartResponse>, (StatusCode, Json<ApiError>)> {
-    let auth_header = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok());
-    let user_id = get_user_id(&state, auth_header).await?;
+    let user_id = claims.sub;
 
     // Validate quantity
     if req.quantity <= 0 {
@@ -72,7 +98,7 @@ pub async fn add_to_cart(
         ));
     }
 
-    if product.stock < req.quantity {
+    if available_stock(&state, &product, req.variant_id).await? < req.quantity {
         return Err((
             StatusCode::BAD_REQUEST,
             Json(ApiError::bad_request("Insufficient stock")),
@@ -80,12 +106,12 @@ pub async fn add_to_cart(
     }
 
     // Add to cart
-    CartRepository::add_item(&state.db.pool, user_id, req.product_id, req.quantity)
+    CartRepository::add_item(&state.db.pool, user_id, req.product_id, req.variant_id, req.quantity)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
 
     // Return updated cart
-    let cart = CartRepository::get_cart(&state.db.pool, user_id)
+    let cart = CartRepository::get_cart(&state.db.pool, user_id, state.shipping_fee)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
 
@@ -94,12 +120,12 @@ pub async fn add_to_cart(
 
 pub async fn update_cart_item(
     State(state): State<Arc<AppState>>,
-    headers: axum::http::HeaderMap,
+    CurrentUser(claims): CurrentUser,
     Path(product_id): Path<String>,
+    Query(item): Query<CartItemQuery>,
     Json(req): Json<UpdateCartItemRequest>,
 ) -> Result<Json<CartResponse>, (StatusCode, Json<ApiError>)> {
-    let auth_header = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok());
-    let user_id = get_user_id(&state, auth_header).await?;
+    let user_id = claims.sub;
 
     let product_id: Uuid = product_id.parse().map_err(|_| {
         (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid product ID")))
@@ -107,7 +133,7 @@ pub async fn update_cart_item(
 
     if req.quantity <= 0 {
         // Remove item if quantity is 0 or negative
-        CartRepository::remove_item(&state.db.pool, user_id, product_id)
+        CartRepository::remove_item(&state.db.pool, user_id, product_id, item.variant_id)
             .await
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
     } else {
@@ -119,20 +145,20 @@ pub async fn update_cart_item(
                 (StatusCode::NOT_FOUND, Json(ApiError::not_found("Product not found")))
             })?;
 
-        if product.stock < req.quantity {
+        if available_stock(&state, &product, item.variant_id).await? < req.quantity {
             return Err((
                 StatusCode::BAD_REQUEST,
                 Json(ApiError::bad_request("Insufficient stock")),
             ));
         }
 
-        CartRepository::update_item_quantity(&state.db.pool, user_id, product_id, req.quantity)
+        CartRepository::update_item_quantity(&state.db.pool, user_id, product_id, item.variant_id, req.quantity)
             .await
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
     }
 
     // Return updated cart
-    let cart = CartRepository::get_cart(&state.db.pool, user_id)
+    let cart = CartRepository::get_cart(&state.db.pool, user_id, state.shipping_fee)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
 
@@ -141,17 +167,17 @@ pub async fn update_cart_item(
 
 pub async fn remove_from_cart(
     State(state): State<Arc<AppState>>,
-    headers: axum::http::HeaderMap,
+    CurrentUser(claims): CurrentUser,
     Path(product_id): Path<String>,
+    Query(item): Query<CartItemQuery>,
 ) -> Result<Json<MessageResponse>, (StatusCode, Json<ApiError>)> {
-    let auth_header = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok());
-    let user_id = get_user_id(&state, auth_header).await?;
+    let user_id = claims.sub;
 
     let product_id: Uuid = product_id.parse().map_err(|_| {
         (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid product ID")))
     })?;
 
-    let removed = CartRepository::remove_item(&state.db.pool, user_id, product_id)
+    let removed = CartRepository::remove_item(&state.db.pool, user_id, product_id, item.variant_id)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
 
@@ -166,3 +192,80 @@ pub async fn remove_from_cart(
         message: "Item removed from cart".to_string(),
     }))
 }
+
+/// Apply a coupon code to the cart, replacing any other. The code must give
+/// a discount on the cart as it is now.
+pub async fn apply_coupon(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Json(req): Json<ApplyCouponRequest>,
+) -> Result<Json<CartResponse>, (StatusCode, Json<ApiError>)> {
+    let user_id = claims.sub;
+
+    if req.code.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::validation_error("Enter a coupon code")),
+        ));
+    }
+
+    let coupon = CouponRepository::find_by_code(&state.db.pool, &req.code)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Coupon not found")))
+        })?;
+
+    let cart = CartRepository::get_cart(&state.db.pool, user_id, state.shipping_fee)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if cart.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::bad_request("Cart is empty")),
+        ));
+    }
+
+    let coupon = CouponRepository::for_user(&state.db.pool, coupon, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    coupon
+        .apply(&cart.items, cart.shipping)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ApiError::new("INVALID_COUPON", e.to_string()))))?;
+
+    CouponRepository::add_to_cart(&state.db.pool, user_id, coupon.coupon.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    let cart = CartRepository::get_cart(&state.db.pool, user_id, state.shipping_fee)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok(Json(CartResponse { cart }))
+}
+
+pub async fn remove_coupon(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<Json<CartResponse>, (StatusCode, Json<ApiError>)> {
+    let user_id = claims.sub;
+
+    let removed = CouponRepository::remove_from_cart(&state.db.pool, user_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if !removed {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiError::not_found("No coupon applied")),
+        ));
+    }
+
+    let cart = CartRepository::get_cart(&state.db.pool, user_id, state.shipping_fee)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok(Json(CartResponse { cart }))
+}
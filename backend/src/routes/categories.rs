@@ -0,0 +1,189 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+use shared::{
+    ApiError, CategoryListResponse, CategoryResponse, CreateCategoryRequest, MessageResponse, ProductCategory,
+    UpdateCategoryRequest,
+};
+use crate::{AppState, db::{find_in_tree, slugify, CategoryRepository}};
+use super::admin::refresh_suggestions;
+
+/// The category tree with product counts that include subcategories
+pub async fn list_categories(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<CategoryListResponse>, (StatusCode, Json<ApiError>)> {
+    let categories = CategoryRepository::tree(&state.db.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok(Json(CategoryListResponse { categories }))
+}
+
+/// One category with its subtree and the path down to it
+pub async fn get_category(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+) -> Result<Json<CategoryResponse>, (StatusCode, Json<ApiError>)> {
+    let tree = CategoryRepository::tree(&state.db.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    let (category, breadcrumbs) = find_in_tree(&tree, &slug).ok_or_else(|| {
+        (StatusCode::NOT_FOUND, Json(ApiError::not_found("Category not found")))
+    })?;
+
+    Ok(Json(CategoryResponse {
+        category: category.clone(),
+        breadcrumbs,
+    }))
+}
+
+/// Check the name and slug of a new or renamed category, returning the slug
+async fn validate_category(
+    state: &AppState,
+    name: &str,
+    slug: Option<&str>,
+    parent_id: Option<Uuid>,
+    except: Option<Uuid>,
+) -> Result<String, (StatusCode, Json<ApiError>)> {
+    let error = |message: &str| {
+        Err((StatusCode::BAD_REQUEST, Json(ApiError::validation_error(message))))
+    };
+
+    if name.trim().is_empty() {
+        return error("Category name must not be empty");
+    }
+
+    let slug = slug.map(str::to_string).unwrap_or_else(|| slugify(name));
+    if slug.is_empty() || slug != slugify(&slug) {
+        return error("Slug may only contain lowercase letters, digits and single hyphens");
+    }
+
+    let taken = CategoryRepository::name_or_slug_taken(&state.db.pool, name.trim(), &slug, except)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if taken {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ApiError::new("CATEGORY_EXISTS", "A category with this name or slug already exists")),
+        ));
+    }
+
+    if let Some(parent_id) = parent_id {
+        let parent = CategoryRepository::get_by_id(&state.db.pool, parent_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+        if parent.is_none() {
+            return error("Parent category not found");
+        }
+    }
+
+    Ok(slug)
+}
+
+pub async fn create_category(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateCategoryRequest>,
+) -> Result<(StatusCode, Json<ProductCategory>), (StatusCode, Json<ApiError>)> {
+    let slug = validate_category(&state, &req.name, req.slug.as_deref(), req.parent_id, None).await?;
+
+    let category = CategoryRepository::create(&state.db.pool, &slug, &req)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok((StatusCode::CREATED, Json(category)))
+}
+
+pub async fn update_category(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateCategoryRequest>,
+) -> Result<Json<ProductCategory>, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid category ID")))
+    })?;
+
+    let current = CategoryRepository::get_by_id(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Category not found")))
+        })?;
+
+    let name = req.name.as_deref().unwrap_or(&current.name);
+    let slug = req.slug.as_deref().unwrap_or(&current.slug);
+    validate_category(&state, name, Some(slug), req.parent_id, Some(id)).await?;
+
+    // A category cannot move below itself
+    if let Some(parent_id) = req.parent_id {
+        let cycle = CategoryRepository::is_within(&state.db.pool, parent_id, id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+        if cycle {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ApiError::validation_error("A category cannot be moved into its own subtree")),
+            ));
+        }
+    }
+
+    let category = CategoryRepository::update(&state.db.pool, id, &req)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Category not found")))
+        })?;
+
+    if category.name != current.name {
+        refresh_suggestions(&state).await;
+    }
+
+    Ok(Json(category))
+}
+
+/// Only empty categories can be deleted; products and subcategories have to
+/// be moved first
+pub async fn delete_category(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid category ID")))
+    })?;
+
+    let (children, products) = CategoryRepository::usage(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if children > 0 || products > 0 {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ApiError::new(
+                "CATEGORY_IN_USE",
+                format!("Category still has {} subcategories and {} products", children, products),
+            )),
+        ));
+    }
+
+    let deleted = CategoryRepository::delete(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if !deleted {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiError::not_found("Category not found")),
+        ));
+    }
+
+    Ok(Json(MessageResponse {
+        message: "Category deleted successfully".to_string(),
+    }))
+}
@@ -0,0 +1,167 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+use shared::{
+    ApiError, CouponKind, CouponListResponse, CouponResponse, CreateCouponRequest, UpdateCouponRequest,
+};
+use crate::{AppState, db::{CategoryRepository, CouponRepository, ProductRepository}};
+
+/// Check a new coupon's code and discount terms, and that its restrictions
+/// name existing products and categories
+async fn validate_coupon(state: &AppState, req: &CreateCouponRequest) -> Result<(), (StatusCode, Json<ApiError>)> {
+    let error = |message: String| {
+        Err((StatusCode::BAD_REQUEST, Json(ApiError::validation_error(message))))
+    };
+
+    let code = req.code.trim();
+    if !(3..=32).contains(&code.len()) || !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return error("Code must be 3-32 letters, digits, '-' or '_'".to_string());
+    }
+
+    match req.kind {
+        CouponKind::Percentage if !matches!(req.percent_off, Some(1..=100)) => {
+            return error("Percentage coupons need percent_off between 1 and 100".to_string());
+        }
+        CouponKind::FixedAmount if !matches!(req.amount_off, Some(a) if a.amount > 0) => {
+            return error("Fixed-amount coupons need a positive amount_off".to_string());
+        }
+        _ => {}
+    }
+    if req.kind != CouponKind::Percentage && req.percent_off.is_some() {
+        return error("percent_off is only used by percentage coupons".to_string());
+    }
+    if req.kind != CouponKind::FixedAmount && req.amount_off.is_some() {
+        return error("amount_off is only used by fixed-amount coupons".to_string());
+    }
+
+    if let Some(minimum) = req.min_order_total {
+        if minimum.amount <= 0 {
+            return error("Minimum order total must be positive".to_string());
+        }
+        if req.amount_off.is_some_and(|a| a.currency != minimum.currency) {
+            return error("amount_off and min_order_total must be in the same currency".to_string());
+        }
+    }
+
+    validate_limits(req.max_uses, req.max_uses_per_user, req.starts_at, req.ends_at)
+        .map_err(|message| (StatusCode::BAD_REQUEST, Json(ApiError::validation_error(message))))?;
+
+    for product_id in &req.product_ids {
+        let product = ProductRepository::get_by_id(&state.db.pool, *product_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+        if product.is_none() {
+            return error(format!("Product {} does not exist", product_id));
+        }
+    }
+
+    for category_id in &req.category_ids {
+        let category = CategoryRepository::get_by_id(&state.db.pool, *category_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+        if category.is_none() {
+            return error(format!("Category {} does not exist", category_id));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_limits(
+    max_uses: Option<i32>,
+    max_uses_per_user: Option<i32>,
+    starts_at: Option<chrono::DateTime<chrono::Utc>>,
+    ends_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<(), String> {
+    if max_uses.is_some_and(|m| m < 1) || max_uses_per_user.is_some_and(|m| m < 1) {
+        return Err("Usage limits must be at least 1".to_string());
+    }
+    if let (Some(max_uses), Some(max_uses_per_user)) = (max_uses, max_uses_per_user) {
+        if max_uses_per_user > max_uses {
+            return Err("max_uses_per_user cannot exceed max_uses".to_string());
+        }
+    }
+    if let (Some(starts_at), Some(ends_at)) = (starts_at, ends_at) {
+        if ends_at <= starts_at {
+            return Err("ends_at must be after starts_at".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn list_coupons(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<CouponListResponse>, (StatusCode, Json<ApiError>)> {
+    let coupons = CouponRepository::list(&state.db.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok(Json(CouponListResponse { coupons }))
+}
+
+pub async fn create_coupon(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateCouponRequest>,
+) -> Result<(StatusCode, Json<CouponResponse>), (StatusCode, Json<ApiError>)> {
+    validate_coupon(&state, &req).await?;
+
+    let coupon = CouponRepository::create(&state.db.pool, &req)
+        .await
+        .map_err(|e| {
+            let msg = e.to_string();
+            if msg.contains("UNIQUE constraint failed") {
+                (StatusCode::CONFLICT, Json(ApiError::new("CONFLICT", "A coupon with this code already exists")))
+            } else {
+                (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(msg)))
+            }
+        })?;
+
+    Ok((StatusCode::CREATED, Json(CouponResponse { coupon })))
+}
+
+/// Deactivate a coupon, or change its description, limits or validity window
+pub async fn update_coupon(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateCouponRequest>,
+) -> Result<Json<CouponResponse>, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid coupon ID")))
+    })?;
+
+    let existing = CouponRepository::get(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Coupon not found")))
+        })?;
+
+    // Validate the coupon as it would be stored, not just the changed fields
+    let merged = CouponRepository::merge_update(existing, &req);
+    validate_limits(merged.max_uses, merged.max_uses_per_user, merged.starts_at, merged.ends_at)
+        .map_err(|message| (StatusCode::BAD_REQUEST, Json(ApiError::validation_error(message))))?;
+
+    if merged.max_uses.is_some_and(|max| max < merged.times_used) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::validation_error(format!(
+                "max_uses cannot be below the {} redemptions already made",
+                merged.times_used
+            ))),
+        ));
+    }
+
+    let coupon = CouponRepository::update(&state.db.pool, id, &req)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Coupon not found")))
+        })?;
+
+    Ok(Json(CouponResponse { coupon }))
+}
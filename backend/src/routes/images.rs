@@ -0,0 +1,187 @@
+use axum::{
+    extract::{multipart::MultipartError, Multipart, Path, State},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+use shared::{ApiError, MessageResponse, ProductImageResponse, UpdateProductImageRequest};
+use crate::{
+    AppState,
+    db::{ImageRepository, NewProductImage, ProductRepository},
+    storage::{process_upload, ACCEPTED_CONTENT_TYPES, MAX_UPLOAD_BYTES},
+};
+
+fn too_large() -> (StatusCode, Json<ApiError>) {
+    (
+        StatusCode::PAYLOAD_TOO_LARGE,
+        Json(ApiError::new(
+            "PAYLOAD_TOO_LARGE",
+            format!("Images must be at most {} MB", MAX_UPLOAD_BYTES / (1024 * 1024)),
+        )),
+    )
+}
+
+fn multipart_error(e: MultipartError) -> (StatusCode, Json<ApiError>) {
+    if e.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        return too_large();
+    }
+
+    (StatusCode::BAD_REQUEST, Json(ApiError::bad_request(e.body_text())))
+}
+
+/// Upload an image to a product's gallery as multipart form data: a `file`
+/// part plus optional `alt_text` and `primary` fields. A thumbnail is
+/// generated alongside the original.
+pub async fn upload_image(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<ProductImageResponse>), (StatusCode, Json<ApiError>)> {
+    let product_id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid product ID")))
+    })?;
+
+    let product = ProductRepository::get_by_id(&state.db.pool, product_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Product not found")))
+        })?;
+
+    let mut file = None;
+    let mut alt_text = None;
+    let mut make_primary = false;
+
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
+        match field.name() {
+            Some("file") => {
+                let content_type = field.content_type().unwrap_or_default().to_string();
+                if !ACCEPTED_CONTENT_TYPES.contains(&content_type.as_str()) {
+                    return Err((
+                        StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                        Json(ApiError::new(
+                            "UNSUPPORTED_MEDIA_TYPE",
+                            format!("Images must be one of: {}", ACCEPTED_CONTENT_TYPES.join(", ")),
+                        )),
+                    ));
+                }
+
+                let bytes = field.bytes().await.map_err(multipart_error)?;
+                if bytes.len() > MAX_UPLOAD_BYTES {
+                    return Err(too_large());
+                }
+                file = Some(bytes);
+            }
+            Some("alt_text") => {
+                alt_text = Some(field.text().await.map_err(multipart_error)?.trim().to_string());
+            }
+            Some("primary") => {
+                make_primary = matches!(field.text().await.map_err(multipart_error)?.trim(), "true" | "1");
+            }
+            _ => {}
+        }
+    }
+
+    let bytes = file.ok_or_else(|| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::validation_error("A file part is required")))
+    })?;
+
+    let original = bytes.clone();
+    let processed = tokio::task::spawn_blocking(move || process_upload(&original))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .map_err(|message| (StatusCode::BAD_REQUEST, Json(ApiError::new("INVALID_IMAGE", message))))?;
+
+    let image_id = Uuid::new_v4();
+    let storage_key = format!("products/{}/{}.{}", product_id, image_id, processed.extension);
+    let thumbnail_key = format!("products/{}/{}_thumb.{}", product_id, image_id, processed.extension);
+
+    state.blobs.put(&storage_key, &bytes)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+    state.blobs.put(&thumbnail_key, &processed.thumbnail)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    let new_image = NewProductImage {
+        id: image_id,
+        product_id,
+        url: state.blobs.url(&storage_key),
+        thumbnail_url: state.blobs.url(&thumbnail_key),
+        storage_key,
+        thumbnail_key,
+        alt_text: alt_text.filter(|a| !a.is_empty()).unwrap_or(product.name),
+        width: processed.width,
+        height: processed.height,
+        make_primary,
+    };
+
+    let image = match ImageRepository::create(&state.db.pool, &new_image).await {
+        Ok(image) => image,
+        Err(e) => {
+            // Don't leave orphaned files behind
+            for key in [&new_image.storage_key, &new_image.thumbnail_key] {
+                let _ = state.blobs.delete(key).await;
+            }
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))));
+        }
+    };
+
+    Ok((StatusCode::CREATED, Json(ProductImageResponse { image })))
+}
+
+/// Edit alt text, move an image within its gallery or make it the primary image
+pub async fn update_image(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateProductImageRequest>,
+) -> Result<Json<ProductImageResponse>, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid image ID")))
+    })?;
+
+    if req.position.is_some_and(|p| p < 0) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::validation_error("Position must not be negative")),
+        ));
+    }
+
+    let image = ImageRepository::update(&state.db.pool, id, &req)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Image not found")))
+        })?;
+
+    Ok(Json(ProductImageResponse { image }))
+}
+
+/// Remove an image and its files; the next image becomes primary if needed
+pub async fn delete_image(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid image ID")))
+    })?;
+
+    let keys = ImageRepository::delete(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Image not found")))
+        })?;
+
+    // The row is gone either way; a file left behind is only logged
+    for key in keys {
+        if let Err(e) = state.blobs.delete(&key).await {
+            tracing::warn!("Failed to delete blob {} from {} store: {}", key, state.blobs.name(), e);
+        }
+    }
+
+    Ok(Json(MessageResponse {
+        message: "Image deleted successfully".to_string(),
+    }))
+}
@@ -2,3 +2,15 @@ pub mod auth;
 pub mod products;
 pub mod cart;
 pub mod orders;
+pub mod admin;
+pub mod addresses;
+pub mod payments;
+pub mod returns;
+pub mod two_factor;
+pub mod variants;
+pub mod categories;
+pub mod images;
+pub mod stock;
+pub mod subscriptions;
+pub mod notifications;
+pub mod coupons;
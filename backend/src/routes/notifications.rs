@@ -0,0 +1,45 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+use shared::{AdminNotificationListParams, AdminNotificationListResponse, AdminNotificationResponse, ApiError};
+use crate::{AppState, db::NotificationRepository};
+
+/// Admin notifications, newest first, optionally only the unread ones
+pub async fn list_notifications(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AdminNotificationListParams>,
+) -> Result<Json<AdminNotificationListResponse>, (StatusCode, Json<ApiError>)> {
+    let limit = params.limit.unwrap_or(50).clamp(1, 200);
+
+    let notifications = NotificationRepository::list(&state.db.pool, params.unread, limit)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    let unread = NotificationRepository::count_unread(&state.db.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok(Json(AdminNotificationListResponse { notifications, unread }))
+}
+
+pub async fn mark_notification_read(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<AdminNotificationResponse>, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid notification ID")))
+    })?;
+
+    let notification = NotificationRepository::mark_read(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Notification not found")))
+        })?;
+
+    Ok(Json(AdminNotificationResponse { notification }))
+}
@@ -1,38 +1,19 @@
 use axum::{
     extract::{Path, State},
-    http::{header, StatusCode},
+    http::StatusCode,
     Json,
 };
 use std::sync::Arc;
 use uuid::Uuid;
-use shared::{CreateOrderRequest, OrderResponse, OrderListResponse, MessageResponse, ApiError, OrderStatus};
-use crate::{AppState, auth, db::{CartRepository, OrderRepository, ProductRepository}};
-
-// Helper to extract user from token
-async fn get_user_id(
-    state: &AppState,
-    auth_header: Option<&str>,
-) -> Result<Uuid, (StatusCode, Json<ApiError>)> {
-    let token = auth_header
-        .and_then(|h| h.strip_prefix("Bearer "))
-        .ok_or_else(|| {
-            (StatusCode::UNAUTHORIZED, Json(ApiError::unauthorized("Missing authorization header")))
-        })?;
-
-    let claims = auth::verify_token(token, &state.jwt_secret)
-        .map_err(|_| {
-            (StatusCode::UNAUTHORIZED, Json(ApiError::unauthorized("Invalid or expired token")))
-        })?;
-
-    Ok(claims.sub)
-}
+use shared::{CreateOrderRequest, OrderResponse, OrderListResponse, MessageResponse, ApiError};
+use crate::{AppState, auth::CurrentUser, db::{AddressRepository, CartRepository, OrderRepository, UserRepository}};
+use super::addresses::normalize_address;
 
 pub async fn list_orders(
     State(state): State<Arc<AppState>>,
-    headers: axum::http::HeaderMap,
+    CurrentUser(claims): CurrentUser,
 ) -> Result<Json<OrderListResponse>, (StatusCode, Json<ApiError>)> {
-    let auth_header = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok());
-    let user_id = get_user_id(&state, auth_header).await?;
+    let user_id = claims.sub;
 
     let orders = OrderRepository::list_by_user(&state.db.pool, user_id)
         .await
@@ -45,14 +26,27 @@ pub async fn list_orders(
 
 pub async fn create_order(
     State(state): State<Arc<AppState>>,
-    headers: axum::http::HeaderMap,
-    Json(_req): Json<CreateOrderRequest>,
+    CurrentUser(claims): CurrentUser,
+    Json(req): Json<CreateOrderRequest>,
 ) -> Result<Json<OrderResponse>, (StatusCode, Json<ApiError>)> {
-    let auth_header = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok());
-    let user_id = get_user_id(&state, auth_header).await?;
+    let user_id = claims.sub;
+
+    if state.require_verified_email {
+        let user = UserRepository::find_by_id(&state.db.pool, user_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+            .ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(ApiError::unauthorized("User no longer exists"))))?;
+
+        if user.email_verified_at.is_none() {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(ApiError::new("EMAIL_NOT_VERIFIED", "Please verify your email address before placing an order")),
+            ));
+        }
+    }
 
     // Get cart items first (outside transaction for read)
-    let cart = CartRepository::get_cart(&state.db.pool, user_id)
+    let cart = CartRepository::get_cart(&state.db.pool, user_id, state.shipping_fee)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
 
@@ -63,28 +57,63 @@ pub async fn create_order(
         ));
     }
 
-    // Use transaction for atomic stock check, update, order creation, and cart clear
-    let order_with_items = OrderRepository::create_order_atomic(&state.db.pool, user_id, &cart.items)
-        .await
-        .map_err(|e| {
-            let msg = e.to_string();
-            if msg.contains("Insufficient stock") || msg.contains("not found") {
-                (StatusCode::BAD_REQUEST, Json(ApiError::bad_request(msg)))
-            } else {
-                (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(msg)))
-            }
-        })?;
+    // Don't silently charge full price for a coupon that stopped applying
+    if let Some(reason) = cart.coupon_error {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::new("INVALID_COUPON", format!("Remove the coupon to continue: {}", reason))),
+        ));
+    }
+
+    // Resolve the shipping address; it is snapshotted onto the order
+    let shipping_address = match (req.address_id, req.shipping_address) {
+        (Some(address_id), _) => AddressRepository::get(&state.db.pool, user_id, address_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+            .ok_or_else(|| {
+                (StatusCode::NOT_FOUND, Json(ApiError::not_found("Address not found")))
+            })?
+            .address,
+        (None, Some(address)) => normalize_address(address)?,
+        (None, None) => AddressRepository::get_default(&state.db.pool, user_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+            .ok_or_else(|| {
+                (StatusCode::BAD_REQUEST, Json(ApiError::validation_error("A shipping address is required")))
+            })?
+            .address,
+    };
+
+    // Use transaction for atomic stock check, stock hold, coupon redemption, order creation, and cart clear
+    let order_with_items = OrderRepository::create_order_atomic(
+        &state.db.pool,
+        user_id,
+        &cart.items,
+        &shipping_address,
+        cart.shipping,
+        state.reservation_ttl,
+    )
+    .await
+    .map_err(|e| {
+        let msg = e.to_string();
+        if msg.contains("cannot be used") {
+            (StatusCode::BAD_REQUEST, Json(ApiError::new("INVALID_COUPON", msg)))
+        } else if msg.contains("Insufficient stock") || msg.contains("not found") {
+            (StatusCode::BAD_REQUEST, Json(ApiError::bad_request(msg)))
+        } else {
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(msg)))
+        }
+    })?;
 
     Ok(Json(OrderResponse { order: order_with_items }))
 }
 
 pub async fn get_order(
     State(state): State<Arc<AppState>>,
-    headers: axum::http::HeaderMap,
+    CurrentUser(claims): CurrentUser,
     Path(id): Path<String>,
 ) -> Result<Json<OrderResponse>, (StatusCode, Json<ApiError>)> {
-    let auth_header = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok());
-    let user_id = get_user_id(&state, auth_header).await?;
+    let user_id = claims.sub;
 
     let id: Uuid = id.parse().map_err(|_| {
         (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid order ID")))
@@ -110,11 +139,10 @@ pub async fn get_order(
 
 pub async fn cancel_order(
     State(state): State<Arc<AppState>>,
-    headers: axum::http::HeaderMap,
+    CurrentUser(claims): CurrentUser,
     Path(id): Path<String>,
 ) -> Result<Json<MessageResponse>, (StatusCode, Json<ApiError>)> {
-    let auth_header = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok());
-    let user_id = get_user_id(&state, auth_header).await?;
+    let user_id = claims.sub;
 
     let id: Uuid = id.parse().map_err(|_| {
         (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid order ID")))
@@ -143,16 +171,16 @@ pub async fn cancel_order(
         ));
     }
 
-    // Update status
-    OrderRepository::update_status(&state.db.pool, id, OrderStatus::Cancelled)
+    // Update status and release the held stock atomically
+    let cancelled = OrderRepository::cancel(&state.db.pool, id, &order_with_items.order.status, Some(user_id))
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
 
-    // Restore stock
-    for item in &order_with_items.items {
-        ProductRepository::update_stock(&state.db.pool, item.product_id, item.quantity)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+    if !cancelled {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ApiError::new("CONFLICT", "Order status changed, please refresh")),
+        ));
     }
 
     Ok(Json(MessageResponse {
@@ -0,0 +1,239 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+use shared::{Money, OrderStatus, PayOrderRequest, Payment, PaymentResponse, PaymentStatus, MessageResponse, ApiError};
+use crate::{
+    AppState,
+    auth::CurrentUser,
+    db::{OrderRepository, PaymentRepository, ReservationRepository, WebhookOutcome, WebhookRepository},
+    payments::PaymentError,
+};
+
+/// Header carrying the hex HMAC-SHA256 of the raw webhook body
+const SIGNATURE_HEADER: &str = "x-payment-signature";
+
+pub fn provider_error(e: PaymentError) -> (StatusCode, Json<ApiError>) {
+    tracing::error!("Payment provider error: {}", e);
+    (StatusCode::BAD_GATEWAY, Json(ApiError::new("PAYMENT_ERROR", e.to_string())))
+}
+
+/// Create a provider intent for `amount` and record it as a pending payment
+async fn start_payment(
+    state: &AppState,
+    order_id: Uuid,
+    amount: Money,
+) -> Result<Payment, (StatusCode, Json<ApiError>)> {
+    let intent = state
+        .payments
+        .create_intent(order_id, amount)
+        .await
+        .map_err(provider_error)?;
+
+    PaymentRepository::create(&state.db.pool, order_id, state.payments.name(), &intent.id, intent.amount)
+        .await
+        .map_err(|e| {
+            let msg = e.to_string();
+            if msg.contains("UNIQUE constraint failed") {
+                (StatusCode::CONFLICT, Json(ApiError::new("CONFLICT", "A payment for this order is already in progress")))
+            } else {
+                (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(msg)))
+            }
+        })
+}
+
+/// Charge a pending order through the configured payment provider
+pub async fn pay_order(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Path(id): Path<String>,
+    Json(req): Json<PayOrderRequest>,
+) -> Result<Json<PaymentResponse>, (StatusCode, Json<ApiError>)> {
+    let user_id = claims.sub;
+
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid order ID")))
+    })?;
+
+    let order_with_items = OrderRepository::get_by_id(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Order not found")))
+        })?;
+
+    // Verify ownership
+    if order_with_items.order.user_id != user_id {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiError::new("FORBIDDEN", "You don't have access to this order")),
+        ));
+    }
+
+    if order_with_items.order.status != OrderStatus::Pending {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::bad_request("Only pending orders can be paid")),
+        ));
+    }
+
+    // Keep the stock held while the charge is made; if the holds expired,
+    // take it again or refuse before charging
+    ReservationRepository::renew(&state.db.pool, id, state.reservation_ttl)
+        .await
+        .map_err(|e| {
+            let msg = e.to_string();
+            if msg.contains("Insufficient stock") {
+                (StatusCode::CONFLICT, Json(ApiError::new("OUT_OF_STOCK", msg)))
+            } else if msg.contains("cannot be used") {
+                (StatusCode::CONFLICT, Json(ApiError::new("INVALID_COUPON", msg)))
+            } else {
+                (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(msg)))
+            }
+        })?;
+
+    // Resume an interrupted attempt instead of creating a second intent
+    let existing = PaymentRepository::get_pending_for_order(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    let resumed = existing.is_some();
+    let mut payment = match existing {
+        Some(payment) => payment,
+        None => start_payment(&state, id, order_with_items.order.total).await?,
+    };
+
+    let mut confirmed = state
+        .payments
+        .confirm(&payment.provider_payment_id, req.payment_method.as_deref())
+        .await;
+
+    // The provider no longer knows the resumed intent (the mock gateway keeps
+    // them in memory, so a restart loses them); drop it and charge afresh
+    if resumed && matches!(confirmed, Err(PaymentError::NotFound(_))) {
+        PaymentRepository::mark_failed(&state.db.pool, payment.id, "Payment intent no longer exists")
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+        payment = start_payment(&state, id, order_with_items.order.total).await?;
+        confirmed = state
+            .payments
+            .confirm(&payment.provider_payment_id, req.payment_method.as_deref())
+            .await;
+    }
+
+    match confirmed {
+        Ok(()) => {}
+        Err(PaymentError::Declined(reason)) => {
+            PaymentRepository::mark_failed(&state.db.pool, payment.id, &reason)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+            return Err((
+                StatusCode::PAYMENT_REQUIRED,
+                Json(ApiError::new("PAYMENT_DECLINED", reason)),
+            ));
+        }
+        Err(e) => return Err(provider_error(e)),
+    }
+
+    let paid = PaymentRepository::mark_succeeded(&state.db.pool, payment.id, id, Some(user_id))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    // A webhook may have recorded the success first
+    let already_recorded = !paid
+        && PaymentRepository::get(&state.db.pool, payment.id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+            .is_some_and(|p| p.status == PaymentStatus::Succeeded);
+
+    if !paid && !already_recorded {
+        // The order was cancelled while the charge was in flight; give the money back
+        state
+            .payments
+            .refund(&payment.provider_payment_id, payment.amount)
+            .await
+            .map_err(provider_error)?;
+        PaymentRepository::mark_refunded(&state.db.pool, payment.id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ApiError::new("CONFLICT", "Order status changed during payment; the charge has been refunded")),
+        ));
+    }
+
+    let payment = PaymentRepository::get(&state.db.pool, payment.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Payment not found")))
+        })?;
+
+    let order = OrderRepository::get_by_id(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Order not found")))
+        })?;
+
+    Ok(Json(PaymentResponse { payment, order }))
+}
+
+/// Receive a payment provider webhook. Deliveries are verified, stored and
+/// applied at most once per provider event id.
+pub async fn payment_webhook(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<ApiError>)> {
+    let signature = headers
+        .get(SIGNATURE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| {
+            (StatusCode::UNAUTHORIZED, Json(ApiError::unauthorized("Missing webhook signature")))
+        })?;
+
+    state
+        .payments
+        .verify_webhook_signature(&body, signature)
+        .map_err(|_| {
+            (StatusCode::UNAUTHORIZED, Json(ApiError::unauthorized("Invalid webhook signature")))
+        })?;
+
+    let event = state
+        .payments
+        .parse_webhook_event(&body)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ApiError::bad_request(e.to_string()))))?;
+
+    let payload = String::from_utf8_lossy(&body);
+    let provider = state.payments.name();
+
+    let outcome = match WebhookRepository::process(&state.db.pool, provider, &event, &payload).await {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            tracing::error!("Failed to process webhook event {}: {}", event.id, e);
+            if let Err(record_err) =
+                WebhookRepository::record_failure(&state.db.pool, provider, &event, &payload, &e.to_string()).await
+            {
+                tracing::error!("Failed to record webhook event {}: {}", event.id, record_err);
+            }
+            // A non-2xx response makes the provider redeliver later
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))));
+        }
+    };
+
+    let message = match outcome {
+        WebhookOutcome::Processed => "Event processed".to_string(),
+        WebhookOutcome::Ignored(reason) => format!("Event ignored: {}", reason),
+        WebhookOutcome::Duplicate => "Event already processed".to_string(),
+    };
+
+    Ok(Json(MessageResponse { message }))
+}
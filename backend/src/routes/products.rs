@@ -5,37 +5,48 @@ use axum::{
 };
 use std::sync::Arc;
 use uuid::Uuid;
-use shared::{ProductListParams, ProductListResponse, ProductResponse, CategoryListResponse, ApiError};
-use crate::{AppState, db::ProductRepository};
-
-pub async fn list_products(
-    State(state): State<Arc<AppState>>,
-    Query(params): Query<ProductListParams>,
+use shared::{
+    ProductListParams, ProductListResponse, ProductResponse, SuggestParams, SuggestResponse,
+    ApiError,
+};
+use crate::{AppState, db::{CategoryRepository, ProductFilter, ProductRepository}};
+use super::variants;
+
+/// Run a product query and wrap it as a paginated list response
+async fn query_products(
+    state: &AppState,
+    filter: &ProductFilter,
+    page: u32,
+    limit: u32,
 ) -> Result<Json<ProductListResponse>, (StatusCode, Json<ApiError>)> {
-    let page = params.page.unwrap_or(1).max(1);
-    let limit = params.limit.unwrap_or(12).min(100);
-
-    let (products, total) = ProductRepository::list(
-        &state.db.pool,
-        page,
-        limit,
-        params.sort_by.as_deref(),
-        params.sort_order.as_deref(),
-    )
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+    let result = ProductRepository::query(&state.db.pool, filter, page, limit)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
 
-    let total_pages = (total as f64 / limit as f64).ceil() as u32;
+    let total_pages = (result.total as f64 / limit as f64).ceil() as u32;
 
     Ok(Json(ProductListResponse {
-        products,
-        total,
+        products: result.products,
+        total: result.total,
         page,
         limit,
         total_pages,
+        highlights: result.highlights,
+        facets: Some(result.facets),
     }))
 }
 
+/// Product listing with optional category, price, stock, text and sort filters
+pub async fn list_products(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ProductListParams>,
+) -> Result<Json<ProductListResponse>, (StatusCode, Json<ApiError>)> {
+    let page = params.page.unwrap_or(1).max(1);
+    let limit = params.limit.unwrap_or(12).clamp(1, 100);
+
+    query_products(&state, &ProductFilter::from_params(&params), page, limit).await
+}
+
 pub async fn get_product(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
@@ -51,60 +62,55 @@ pub async fn get_product(
             (StatusCode::NOT_FOUND, Json(ApiError::not_found("Product not found")))
         })?;
 
-    Ok(Json(ProductResponse { product }))
+    Ok(Json(variants::product_response(&state, product, false).await?))
 }
 
 pub async fn search_products(
     State(state): State<Arc<AppState>>,
     Query(params): Query<ProductListParams>,
 ) -> Result<Json<ProductListResponse>, (StatusCode, Json<ApiError>)> {
-    let query = params.search.unwrap_or_default();
-    let limit = params.limit.unwrap_or(20).min(100);
+    let page = params.page.unwrap_or(1).max(1);
+    let limit = params.limit.unwrap_or(20).clamp(1, 100);
 
-    let products = ProductRepository::search(&state.db.pool, &query, limit)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+    let mut filter = ProductFilter::from_params(&params);
+    // A blank query searches for nothing rather than listing everything
+    filter.search = Some(filter.search.unwrap_or_default());
 
-    let total = products.len() as u32;
+    query_products(&state, &filter, page, limit).await
+}
 
-    Ok(Json(ProductListResponse {
-        products,
-        total,
-        page: 1,
-        limit,
-        total_pages: 1,
-    }))
+/// Type-ahead completions for product names and categories, served from
+/// the in-memory index
+pub async fn suggest_products(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<SuggestParams>,
+) -> Json<SuggestResponse> {
+    let limit = params.limit.unwrap_or(8).clamp(1, 20) as usize;
+
+    Json(SuggestResponse {
+        suggestions: state.suggestions.suggest(&params.q, limit),
+    })
 }
 
+/// Products of a category, given by slug or name, and of all its subcategories
 pub async fn products_by_category(
     State(state): State<Arc<AppState>>,
     Path(category): Path<String>,
     Query(params): Query<ProductListParams>,
 ) -> Result<Json<ProductListResponse>, (StatusCode, Json<ApiError>)> {
     let page = params.page.unwrap_or(1).max(1);
-    let limit = params.limit.unwrap_or(12).min(100);
+    let limit = params.limit.unwrap_or(12).clamp(1, 100);
 
-    let (products, total) = ProductRepository::filter_by_category(&state.db.pool, &category, page, limit)
+    let category = CategoryRepository::find(&state.db.pool, &category)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Category not found")))
+        })?;
 
-    let total_pages = (total as f64 / limit as f64).ceil() as u32;
+    let mut filter = ProductFilter::from_params(&params);
+    filter.categories = vec![category.slug];
 
-    Ok(Json(ProductListResponse {
-        products,
-        total,
-        page,
-        limit,
-        total_pages,
-    }))
+    query_products(&state, &filter, page, limit).await
 }
 
-pub async fn list_categories(
-    State(state): State<Arc<AppState>>,
-) -> Result<Json<CategoryListResponse>, (StatusCode, Json<ApiError>)> {
-    let categories = ProductRepository::list_categories(&state.db.pool)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
-
-    Ok(Json(CategoryListResponse { categories }))
-}
@@ -0,0 +1,240 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use std::collections::HashSet;
+use std::sync::Arc;
+use uuid::Uuid;
+use shared::{
+    AdminReturnListParams, CreateReturnRequest, ReturnListResponse, ReturnResponse, ReturnStatus,
+    ReviewReturnRequest, ApiError,
+};
+use crate::{AppState, auth::CurrentUser, db::{OrderRepository, PaymentRepository, ReturnRepository}, payments::PaymentError};
+use super::payments::provider_error;
+
+pub async fn create_return(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Path(id): Path<String>,
+    Json(req): Json<CreateReturnRequest>,
+) -> Result<(StatusCode, Json<ReturnResponse>), (StatusCode, Json<ApiError>)> {
+    let user_id = claims.sub;
+
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid order ID")))
+    })?;
+
+    let reason = req.reason.trim();
+    if reason.is_empty() || reason.chars().count() > 500 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::validation_error("Reason must be between 1 and 500 characters")),
+        ));
+    }
+
+    if req.items.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::validation_error("Select at least one item to return")),
+        ));
+    }
+
+    let mut seen = HashSet::new();
+    for item in &req.items {
+        if item.quantity < 1 {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ApiError::validation_error("Return quantity must be at least 1")),
+            ));
+        }
+        if !seen.insert(item.order_item_id) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ApiError::validation_error("Each item may only be listed once")),
+            ));
+        }
+    }
+
+    let order_with_items = OrderRepository::get_by_id(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Order not found")))
+        })?;
+
+    // Verify ownership
+    if order_with_items.order.user_id != user_id {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiError::new("FORBIDDEN", "You don't have access to this order")),
+        ));
+    }
+
+    if !order_with_items.order.can_request_return() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::bad_request("Returns can only be requested for delivered orders")),
+        ));
+    }
+
+    let return_request = ReturnRepository::create(&state.db.pool, id, user_id, reason, &req.items)
+        .await
+        .map_err(|e| {
+            let msg = e.to_string();
+            if msg.contains("Cannot return") || msg.contains("not found in this order") {
+                (StatusCode::BAD_REQUEST, Json(ApiError::bad_request(msg)))
+            } else {
+                (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(msg)))
+            }
+        })?;
+
+    Ok((StatusCode::CREATED, Json(ReturnResponse { return_request })))
+}
+
+pub async fn list_order_returns(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Path(id): Path<String>,
+) -> Result<Json<ReturnListResponse>, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid order ID")))
+    })?;
+
+    let order_with_items = OrderRepository::get_by_id(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Order not found")))
+        })?;
+
+    // Verify ownership
+    if order_with_items.order.user_id != claims.sub {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiError::new("FORBIDDEN", "You don't have access to this order")),
+        ));
+    }
+
+    let returns = ReturnRepository::list_by_order(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok(Json(ReturnListResponse { returns }))
+}
+
+pub async fn list_returns(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AdminReturnListParams>,
+) -> Result<Json<ReturnListResponse>, (StatusCode, Json<ApiError>)> {
+    let returns = ReturnRepository::list_all(&state.db.pool, params.status.as_ref())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok(Json(ReturnListResponse { returns }))
+}
+
+/// Approve or reject a return. Approval refunds the returned items through
+/// the payment provider and optionally puts them back into stock; approving
+/// again finishes an approval that failed part way.
+pub async fn review_return(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Path(id): Path<String>,
+    Json(req): Json<ReviewReturnRequest>,
+) -> Result<Json<ReturnResponse>, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid return ID")))
+    })?;
+
+    let note = req.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+
+    let return_request = ReturnRepository::get(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Return request not found")))
+        })?;
+
+    // An approval that failed after it was claimed is finished by approving again
+    let resuming = return_request.status == ReturnStatus::Approved && return_request.completed_at.is_none();
+
+    if resuming && !req.approve {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::bad_request("Return request has already been approved")),
+        ));
+    }
+    if return_request.status != ReturnStatus::Requested && !resuming {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::bad_request("Return request has already been reviewed")),
+        ));
+    }
+
+    if !resuming {
+        let claimed = if req.approve {
+            ReturnRepository::claim_approval(&state.db.pool, id, claims.sub, note, req.restock).await
+        } else {
+            ReturnRepository::reject(&state.db.pool, id, claims.sub, note).await
+        }
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+        if !claimed {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(ApiError::new("CONFLICT", "Return request was reviewed concurrently, please refresh")),
+            ));
+        }
+    }
+
+    if req.approve {
+        let mut payment = PaymentRepository::get_succeeded_for_order(&state.db.pool, return_request.order_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+        // Orders marked paid by hand have no provider payment; refund those
+        // offline. A refund already issued for this return is not repeated.
+        if let (Some(provider_payment), None) = (&payment, &return_request.refund_id) {
+            match state.payments.refund(&provider_payment.provider_payment_id, return_request.refund_amount).await {
+                Ok(refund_id) => {
+                    ReturnRepository::record_refund(&state.db.pool, id, &refund_id)
+                        .await
+                        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+                }
+                // The provider has lost the charge (the mock gateway forgets
+                // it on restart), so it can only be refunded offline too
+                Err(PaymentError::NotFound(intent_id)) => {
+                    tracing::warn!("Payment intent {} is unknown to the provider; return {} must be refunded offline", intent_id, id);
+                    payment = None;
+                }
+                Err(e) => {
+                    if let Err(release_err) = ReturnRepository::release_approval(&state.db.pool, id).await {
+                        tracing::error!("Failed to release return {}: {}", id, release_err);
+                    }
+                    return Err(provider_error(e));
+                }
+            }
+        }
+
+        let approved = ReturnRepository::get(&state.db.pool, id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+            .ok_or_else(|| {
+                (StatusCode::NOT_FOUND, Json(ApiError::not_found("Return request not found")))
+            })?;
+
+        ReturnRepository::complete_approval(&state.db.pool, &approved, payment.map(|p| p.id), Some(claims.sub))
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+    }
+
+    let return_request = ReturnRepository::get(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Return request not found")))
+        })?;
+
+    Ok(Json(ReturnResponse { return_request }))
+}
@@ -0,0 +1,119 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+use shared::{
+    AdjustStockRequest, ApiError, Product, StockMovementListParams, StockMovementListResponse, StockMovementReason,
+    StockMovementResponse,
+};
+use crate::{AppState, auth::CurrentUser, db::{ProductRepository, StockChange, StockRepository, VariantRepository}};
+
+async fn find_product(state: &AppState, id: &str) -> Result<Product, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid product ID")))
+    })?;
+
+    ProductRepository::get_by_id(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Product not found")))
+        })
+}
+
+/// Check that the variant belongs to the product. Products sold by variant
+/// keep their stock per variant, so one must be given for them and not
+/// for other products.
+async fn check_variant(
+    state: &AppState,
+    product: &Product,
+    variant_id: Option<Uuid>,
+) -> Result<(), (StatusCode, Json<ApiError>)> {
+    let variants = VariantRepository::list_variants(&state.db.pool, product.id, true)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    match variant_id {
+        Some(variant_id) if !variants.iter().any(|v| v.id == variant_id) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiError::not_found("Variant not found for this product")),
+        )),
+        None if !variants.is_empty() => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::validation_error("Stock of a product with variants is adjusted per variant")),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Add or remove stock by hand, e.g. after a stock count or for damaged goods
+pub async fn adjust_stock(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Path(id): Path<String>,
+    Json(req): Json<AdjustStockRequest>,
+) -> Result<(StatusCode, Json<StockMovementResponse>), (StatusCode, Json<ApiError>)> {
+    if req.quantity == 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::validation_error("Quantity must not be zero")),
+        ));
+    }
+
+    if req.note.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::validation_error("A note explaining the adjustment is required")),
+        ));
+    }
+
+    let product = find_product(&state, &id).await?;
+    check_variant(&state, &product, req.variant_id).await?;
+
+    let change = StockChange {
+        product_id: product.id,
+        variant_id: req.variant_id,
+        quantity: req.quantity,
+        reason: StockMovementReason::Adjustment,
+        note: Some(req.note.trim().to_string()),
+        actor_id: Some(claims.sub),
+        reference_id: None,
+    };
+
+    let (movement, stock) = StockRepository::adjust(&state.db.pool, &change)
+        .await
+        .map_err(|e| {
+            let msg = e.to_string();
+            if msg.contains("Insufficient stock") {
+                (StatusCode::CONFLICT, Json(ApiError::new("OUT_OF_STOCK", msg)))
+            } else {
+                (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(msg)))
+            }
+        })?;
+
+    Ok((StatusCode::CREATED, Json(StockMovementResponse { movement, stock })))
+}
+
+/// A product's stock movements, newest first
+pub async fn list_movements(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<StockMovementListParams>,
+) -> Result<Json<StockMovementListResponse>, (StatusCode, Json<ApiError>)> {
+    let product = find_product(&state, &id).await?;
+
+    if let Some(variant_id) = params.variant_id {
+        check_variant(&state, &product, Some(variant_id)).await?;
+    }
+
+    let limit = params.limit.unwrap_or(100).clamp(1, 500);
+
+    let movements = StockRepository::list(&state.db.pool, product.id, params.variant_id, limit)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok(Json(StockMovementListResponse { movements }))
+}
@@ -0,0 +1,75 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+use shared::{
+    ApiError, CreateStockSubscriptionRequest, MessageResponse, StockSubscriptionListResponse, StockSubscriptionResponse,
+};
+use crate::{AppState, auth::CurrentUser, db::{ProductRepository, StockSubscriptionRepository}};
+
+/// The current user's back-in-stock subscriptions that are still waiting
+pub async fn list_subscriptions(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<Json<StockSubscriptionListResponse>, (StatusCode, Json<ApiError>)> {
+    let subscriptions = StockSubscriptionRepository::list_waiting(&state.db.pool, claims.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok(Json(StockSubscriptionListResponse { subscriptions }))
+}
+
+/// Ask to be emailed when an out-of-stock product can be bought again.
+/// Subscribing twice returns the existing subscription.
+pub async fn create_subscription(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Json(req): Json<CreateStockSubscriptionRequest>,
+) -> Result<(StatusCode, Json<StockSubscriptionResponse>), (StatusCode, Json<ApiError>)> {
+    let product = ProductRepository::get_by_id(&state.db.pool, req.product_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .filter(|product| product.is_active)
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Product not found")))
+        })?;
+
+    if product.is_available() {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ApiError::new("IN_STOCK", format!("{} is in stock", product.name))),
+        ));
+    }
+
+    let (subscription, created) = StockSubscriptionRepository::create(&state.db.pool, claims.sub, product.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    let status = if created { StatusCode::CREATED } else { StatusCode::OK };
+    Ok((status, Json(StockSubscriptionResponse { subscription })))
+}
+
+pub async fn delete_subscription(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Path(id): Path<String>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid subscription ID")))
+    })?;
+
+    let deleted = StockSubscriptionRepository::delete(&state.db.pool, id, claims.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if !deleted {
+        return Err((StatusCode::NOT_FOUND, Json(ApiError::not_found("Subscription not found"))));
+    }
+
+    Ok(Json(MessageResponse {
+        message: "Subscription cancelled".to_string(),
+    }))
+}
@@ -0,0 +1,209 @@
+use axum::{
+    extract::{ConnectInfo, State},
+    http::StatusCode,
+    Json,
+};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use chrono::Utc;
+use uuid::Uuid;
+use shared::{
+    ApiError, AuthResponse, MessageResponse, TwoFactorCodeRequest, TwoFactorEnabledResponse, TwoFactorLoginRequest,
+    TwoFactorSetupResponse, TwoFactorStatusResponse,
+};
+use crate::{
+    AppState,
+    auth::{self, totp, CurrentUser},
+    db::{TwoFactorRepository, UserRepository},
+};
+use super::auth::{login_throttled, record_login_failure, start_session};
+
+/// Issuer shown in authenticator apps
+const TOTP_ISSUER: &str = "RustShop";
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Check a TOTP code or, failing that, a recovery code. Accepted codes are
+/// consumed: TOTP steps cannot be reused and recovery codes work once.
+async fn check_second_factor(state: &AppState, user_id: Uuid, code: &str) -> anyhow::Result<bool> {
+    let two_factor = TwoFactorRepository::get(&state.db.pool, user_id).await?;
+    let Some(secret) = two_factor.secret.filter(|_| two_factor.enabled) else {
+        return Ok(false);
+    };
+
+    if let Some(step) = totp::verify_code(&secret, code, Utc::now().timestamp(), two_factor.last_step) {
+        return TwoFactorRepository::record_step(&state.db.pool, user_id, step).await;
+    }
+
+    let recovery_code = totp::normalize_recovery_code(code);
+    if recovery_code.is_empty() {
+        return Ok(false);
+    }
+    TwoFactorRepository::use_recovery_code(&state.db.pool, user_id, &recovery_code).await
+}
+
+pub async fn status(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<Json<TwoFactorStatusResponse>, (StatusCode, Json<ApiError>)> {
+    let two_factor = TwoFactorRepository::get(&state.db.pool, claims.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    let recovery_codes_remaining = TwoFactorRepository::remaining_recovery_codes(&state.db.pool, claims.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok(Json(TwoFactorStatusResponse {
+        enabled: two_factor.enabled,
+        recovery_codes_remaining,
+    }))
+}
+
+/// Generate a secret for enrollment. It only takes effect after `confirm`.
+pub async fn setup(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<Json<TwoFactorSetupResponse>, (StatusCode, Json<ApiError>)> {
+    let secret = totp::generate_secret();
+
+    let stored = TwoFactorRepository::set_pending_secret(&state.db.pool, claims.sub, &secret)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if !stored {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ApiError::new("CONFLICT", "Two-factor authentication is already enabled")),
+        ));
+    }
+
+    Ok(Json(TwoFactorSetupResponse {
+        otpauth_uri: totp::otpauth_uri(&secret, &claims.email, TOTP_ISSUER),
+        secret,
+    }))
+}
+
+/// Enable 2FA once the user proves their app produces valid codes.
+/// Returns the recovery codes; they are not retrievable later.
+pub async fn confirm(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Json(req): Json<TwoFactorCodeRequest>,
+) -> Result<Json<TwoFactorEnabledResponse>, (StatusCode, Json<ApiError>)> {
+    let two_factor = TwoFactorRepository::get(&state.db.pool, claims.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    let secret = match (two_factor.enabled, two_factor.secret) {
+        (false, Some(secret)) => secret,
+        (true, _) => {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(ApiError::new("CONFLICT", "Two-factor authentication is already enabled")),
+            ));
+        }
+        (false, None) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(ApiError::bad_request("Start two-factor setup first")),
+            ));
+        }
+    };
+
+    let step = totp::verify_code(&secret, &req.code, Utc::now().timestamp(), None).ok_or_else(|| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid authentication code")))
+    })?;
+
+    let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| totp::generate_recovery_code()).collect();
+    let normalized: Vec<String> = recovery_codes.iter().map(|c| totp::normalize_recovery_code(c)).collect();
+
+    let enabled = TwoFactorRepository::enable(&state.db.pool, claims.sub, step, &normalized)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if !enabled {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ApiError::new("CONFLICT", "Two-factor setup changed; start again")),
+        ));
+    }
+
+    Ok(Json(TwoFactorEnabledResponse { recovery_codes }))
+}
+
+/// Turn 2FA off; requires a current code so a stolen session cannot do it
+pub async fn disable(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Json(req): Json<TwoFactorCodeRequest>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<ApiError>)> {
+    let valid = check_second_factor(&state, claims.sub, &req.code)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if !valid {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiError::bad_request("Invalid authentication code")),
+        ));
+    }
+
+    TwoFactorRepository::disable(&state.db.pool, claims.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok(Json(MessageResponse {
+        message: "Two-factor authentication disabled".to_string(),
+    }))
+}
+
+/// Second login step: exchange the challenge token and a TOTP or recovery
+/// code for a session
+pub async fn verify_login(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Json(req): Json<TwoFactorLoginRequest>,
+) -> Result<Json<AuthResponse>, (StatusCode, Json<ApiError>)> {
+    let ip_key = format!("ip:{}", addr.ip());
+
+    let decision = state.rate_limits.login.check(&ip_key);
+    if !decision.allowed {
+        return Err(login_throttled(&decision, false));
+    }
+
+    let challenge = auth::verify_challenge_token(&req.challenge_token, &state.jwt_secret).map_err(|_| {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(ApiError::unauthorized("Login challenge expired; please sign in again")),
+        )
+    })?;
+
+    let user = UserRepository::find_by_id(&state.db.pool, challenge.sub)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, Json(ApiError::unauthorized("User no longer exists"))))?;
+
+    // Guessing codes counts towards the same account lockout as passwords
+    let account_key = format!("email:{}", user.email.to_lowercase());
+    let decision = state.rate_limits.account.check(&account_key);
+    if !decision.allowed {
+        return Err(login_throttled(&decision, true));
+    }
+
+    let valid = check_second_factor(&state, user.id, &req.code)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if !valid {
+        record_login_failure(&state, &ip_key, &account_key);
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ApiError::unauthorized("Invalid authentication code")),
+        ));
+    }
+
+    state.rate_limits.login.reset(&ip_key);
+    state.rate_limits.account.reset(&account_key);
+
+    start_session(&state, user, true).await
+}
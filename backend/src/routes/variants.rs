@@ -0,0 +1,226 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+use shared::{
+    ApiError, CreateVariantRequest, MessageResponse, Money, Product, ProductResponse, ProductVariantResponse,
+    SetProductOptionsRequest, UpdateVariantRequest,
+};
+use crate::{AppState, auth::CurrentUser, db::{variant_title, ImageRepository, ProductRepository, VariantRepository}};
+
+/// Product with its options, variants and image gallery. Storefront callers only see
+/// active variants; admins also get the deactivated ones.
+pub async fn product_response(
+    state: &AppState,
+    product: Product,
+    include_inactive: bool,
+) -> Result<ProductResponse, (StatusCode, Json<ApiError>)> {
+    let options = VariantRepository::list_options(&state.db.pool, product.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    let variants = VariantRepository::list_variants(&state.db.pool, product.id, include_inactive)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    let images = ImageRepository::list(&state.db.pool, product.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok(ProductResponse { product, options, variants, images })
+}
+
+/// Validate variant fields shared by create and update
+fn validate_variant_fields(
+    product: &Product,
+    sku: Option<&str>,
+    price: Option<Money>,
+    stock: Option<i32>,
+) -> Result<(), (StatusCode, Json<ApiError>)> {
+    let error = |message: &str| {
+        Err((StatusCode::BAD_REQUEST, Json(ApiError::validation_error(message))))
+    };
+
+    if sku.is_some_and(|s| s.trim().is_empty()) {
+        return error("SKU must not be empty");
+    }
+    if price.is_some_and(|p| p.is_negative()) {
+        return error("Price must not be negative");
+    }
+    if price.is_some_and(|p| p.currency != product.price.currency) {
+        return error("Variant price must be in the product's currency");
+    }
+    if stock.is_some_and(|s| s < 0) {
+        return error("Stock must not be negative");
+    }
+
+    Ok(())
+}
+
+async fn find_product(state: &AppState, id: &str) -> Result<Product, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid product ID")))
+    })?;
+
+    ProductRepository::get_by_id(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Product not found")))
+        })
+}
+
+async fn ensure_sku_free(state: &AppState, sku: &str) -> Result<(), (StatusCode, Json<ApiError>)> {
+    let taken = VariantRepository::sku_exists(&state.db.pool, sku.trim())
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if taken {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ApiError::new("SKU_EXISTS", format!("SKU {} is already in use", sku.trim()))),
+        ));
+    }
+
+    Ok(())
+}
+
+pub async fn set_options(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<SetProductOptionsRequest>,
+) -> Result<Json<ProductResponse>, (StatusCode, Json<ApiError>)> {
+    let product = find_product(&state, &id).await?;
+
+    let error = |message: String| {
+        Err((StatusCode::BAD_REQUEST, Json(ApiError::validation_error(message))))
+    };
+
+    for (i, option) in req.options.iter().enumerate() {
+        if option.name.trim().is_empty() {
+            return error("Option names must not be empty".to_string());
+        }
+        if req.options[..i].iter().any(|o| o.name == option.name) {
+            return error(format!("Option '{}' is listed twice", option.name));
+        }
+        if option.values.is_empty() || option.values.iter().any(|v| v.trim().is_empty()) {
+            return error(format!("Option '{}' needs at least one non-empty value", option.name));
+        }
+        if option.values.iter().enumerate().any(|(j, v)| option.values[..j].contains(v)) {
+            return error(format!("Option '{}' has duplicate values", option.name));
+        }
+    }
+
+    let replaced = VariantRepository::set_options(&state.db.pool, product.id, &req.options)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if !replaced {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ApiError::new("HAS_VARIANTS", "Delete the product's variants before changing its options")),
+        ));
+    }
+
+    Ok(Json(product_response(&state, product, true).await?))
+}
+
+pub async fn create_variant(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Path(id): Path<String>,
+    Json(req): Json<CreateVariantRequest>,
+) -> Result<(StatusCode, Json<ProductVariantResponse>), (StatusCode, Json<ApiError>)> {
+    let product = find_product(&state, &id).await?;
+
+    validate_variant_fields(&product, Some(&req.sku), req.price, Some(req.stock))?;
+
+    let options = VariantRepository::list_options(&state.db.pool, product.id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    let title = variant_title(&options, &req.options).map_err(|message| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::validation_error(message)))
+    })?;
+
+    ensure_sku_free(&state, &req.sku).await?;
+
+    let duplicate = VariantRepository::combination_exists(&state.db.pool, product.id, &req.options)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if duplicate {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ApiError::new("VARIANT_EXISTS", format!("Variant {} already exists", title))),
+        ));
+    }
+
+    let variant = VariantRepository::create(&state.db.pool, product.id, &title, &req, Some(claims.sub))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    Ok((StatusCode::CREATED, Json(ProductVariantResponse { variant })))
+}
+
+pub async fn update_variant(
+    State(state): State<Arc<AppState>>,
+    CurrentUser(claims): CurrentUser,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateVariantRequest>,
+) -> Result<Json<ProductVariantResponse>, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid variant ID")))
+    })?;
+
+    let current = VariantRepository::get_by_id(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Variant not found")))
+        })?;
+
+    let product = find_product(&state, &current.product_id.to_string()).await?;
+
+    validate_variant_fields(&product, req.sku.as_deref(), req.price, req.stock)?;
+
+    if let Some(sku) = req.sku.as_deref().filter(|sku| sku.trim() != current.sku) {
+        ensure_sku_free(&state, sku).await?;
+    }
+
+    let variant = VariantRepository::update(&state.db.pool, id, &req, Some(claims.sub))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?
+        .ok_or_else(|| {
+            (StatusCode::NOT_FOUND, Json(ApiError::not_found("Variant not found")))
+        })?;
+
+    Ok(Json(ProductVariantResponse { variant }))
+}
+
+pub async fn delete_variant(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<MessageResponse>, (StatusCode, Json<ApiError>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (StatusCode::BAD_REQUEST, Json(ApiError::bad_request("Invalid variant ID")))
+    })?;
+
+    let deleted = VariantRepository::delete(&state.db.pool, id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ApiError::internal_error(e.to_string()))))?;
+
+    if !deleted {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiError::not_found("Variant not found")),
+        ));
+    }
+
+    Ok(Json(MessageResponse {
+        message: "Variant deleted successfully".to_string(),
+    }))
+}
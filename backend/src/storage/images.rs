@@ -0,0 +1,103 @@
+use std::io::Cursor;
+use image::{imageops::FilterType, ImageFormat, ImageReader, Limits};
+
+/// Largest accepted upload
+pub const MAX_UPLOAD_BYTES: usize = 5 * 1024 * 1024;
+
+/// Content types an upload may declare
+pub const ACCEPTED_CONTENT_TYPES: &[&str] = &["image/jpeg", "image/png", "image/webp"];
+
+/// Larger images are rejected before decoding to bound memory use
+const MAX_DIMENSION: u32 = 8_000;
+
+/// Thumbnails fit within a square of this many pixels
+const THUMBNAIL_SIZE: u32 = 320;
+
+#[derive(Debug)]
+pub struct ProcessedImage {
+    /// File extension matching the detected format
+    pub extension: &'static str,
+    pub width: u32,
+    pub height: u32,
+    /// Thumbnail encoded in the same format as the upload
+    pub thumbnail: Vec<u8>,
+}
+
+/// Check that the uploaded bytes are an accepted image, whatever the client
+/// claimed, and render its thumbnail. Errors are meant for the uploader.
+/// Decoding is CPU-bound; call this from a blocking task.
+pub fn process_upload(bytes: &[u8]) -> Result<ProcessedImage, String> {
+    let format = image::guess_format(bytes).map_err(|_| "File is not a recognised image".to_string())?;
+    let extension = match format {
+        ImageFormat::Jpeg => "jpg",
+        ImageFormat::Png => "png",
+        ImageFormat::WebP => "webp",
+        _ => return Err("Only JPEG, PNG and WebP images are accepted".to_string()),
+    };
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+
+    let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+    reader.limits(limits);
+    let image = reader
+        .decode()
+        .map_err(|e| format!("Image could not be read: {}", e))?;
+
+    // Small images are their own thumbnail rather than being scaled up
+    let thumbnail = if image.width() > THUMBNAIL_SIZE || image.height() > THUMBNAIL_SIZE {
+        image.resize(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Triangle)
+    } else {
+        image.clone()
+    };
+
+    let mut encoded = Cursor::new(Vec::new());
+    thumbnail
+        .write_to(&mut encoded, format)
+        .map_err(|e| format!("Thumbnail could not be created: {}", e))?;
+
+    Ok(ProcessedImage {
+        extension,
+        width: image.width(),
+        height: image.height(),
+        thumbnail: encoded.into_inner(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, GenericImageView, RgbImage};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(RgbImage::new(width, height))
+            .write_to(&mut bytes, ImageFormat::Png)
+            .unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn thumbnail_keeps_aspect_ratio_within_bounds() {
+        let processed = process_upload(&png(1200, 600)).unwrap();
+        assert_eq!(processed.extension, "png");
+        assert_eq!((processed.width, processed.height), (1200, 600));
+
+        let thumbnail = image::load_from_memory(&processed.thumbnail).unwrap();
+        assert_eq!(thumbnail.dimensions(), (320, 160));
+
+        let small = process_upload(&png(100, 50)).unwrap();
+        assert_eq!(image::load_from_memory(&small.thumbnail).unwrap().dimensions(), (100, 50));
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_accepted_images() {
+        assert!(process_upload(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>").is_err());
+        assert!(process_upload(b"GIF89a\x01\x00\x01\x00").is_err());
+
+        let mut truncated = png(40, 40);
+        truncated.truncate(30);
+        assert!(process_upload(&truncated).is_err());
+    }
+}
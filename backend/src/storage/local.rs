@@ -0,0 +1,85 @@
+use std::path::{Component, Path, PathBuf};
+use async_trait::async_trait;
+use super::BlobStore;
+
+/// Keeps blobs as files below a directory, served under `base_url`
+pub struct LocalBlobStore {
+    dir: PathBuf,
+    base_url: String,
+}
+
+impl LocalBlobStore {
+    pub fn new(dir: impl Into<PathBuf>, base_url: &str) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Resolve a key inside the storage directory, rejecting anything that
+    /// could escape it
+    fn path(&self, key: &str) -> anyhow::Result<PathBuf> {
+        let relative = Path::new(key);
+        let safe = !key.is_empty() && relative.components().all(|c| matches!(c, Component::Normal(_)));
+        if !safe {
+            anyhow::bail!("Invalid blob key '{}'", key);
+        }
+
+        Ok(self.dir.join(relative))
+    }
+}
+
+#[async_trait]
+impl BlobStore for LocalBlobStore {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn put(&self, key: &str, bytes: &[u8]) -> anyhow::Result<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        tokio::fs::write(path, bytes).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}", self.base_url, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn stores_and_deletes_below_its_directory() {
+        let dir = std::env::temp_dir().join(format!("blob-test-{}", Uuid::new_v4()));
+        let store = LocalBlobStore::new(&dir, "http://localhost:3000/uploads/").unwrap();
+
+        store.put("products/a/b.png", b"data").await.unwrap();
+        assert_eq!(std::fs::read(dir.join("products/a/b.png")).unwrap(), b"data");
+        assert_eq!(store.url("products/a/b.png"), "http://localhost:3000/uploads/products/a/b.png");
+
+        store.delete("products/a/b.png").await.unwrap();
+        store.delete("products/a/b.png").await.unwrap();
+        assert!(!dir.join("products/a/b.png").exists());
+
+        assert!(store.put("../escape.png", b"x").await.is_err());
+        assert!(store.put("/etc/escape.png", b"x").await.is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,27 @@
+//! Storage for uploaded files
+//!
+//! Handlers store blobs through a `BlobStore` trait object addressed by
+//! slash-separated keys. The local implementation writes into a directory
+//! that the server also serves statically; an object store can be added later.
+
+mod images;
+mod local;
+
+pub use images::{process_upload, ACCEPTED_CONTENT_TYPES, MAX_UPLOAD_BYTES};
+pub use local::LocalBlobStore;
+
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    /// Short identifier for logs, e.g. "local"
+    fn name(&self) -> &'static str;
+
+    async fn put(&self, key: &str, bytes: &[u8]) -> anyhow::Result<()>;
+
+    /// Remove a blob; deleting a missing key is not an error
+    async fn delete(&self, key: &str) -> anyhow::Result<()>;
+
+    /// Public URL the blob is served from
+    fn url(&self, key: &str) -> String;
+}
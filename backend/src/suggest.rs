@@ -0,0 +1,166 @@
+//! In-memory prefix index for search-as-you-type suggestions
+//!
+//! Every word position of each active product name and category is stored
+//! as a sorted key, so a lookup is a binary search plus a short scan and
+//! never touches the database. `ProductRepository::refresh_suggestions`
+//! rebuilds the index whenever the catalog changes.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use shared::{Suggestion, SuggestionKind};
+
+/// Upper bound on keys examined per lookup, keeping short prefixes cheap
+const MAX_SCANNED_KEYS: usize = 2_000;
+
+struct Entry {
+    suggestion: Suggestion,
+    /// Popularity used to break ties: product count for categories,
+    /// 1 for products in stock
+    weight: u32,
+}
+
+#[derive(Default)]
+struct Index {
+    entries: Vec<Entry>,
+    /// (normalized text from a word boundary onwards, word position, entry index), sorted
+    keys: Vec<(String, usize, usize)>,
+}
+
+#[derive(Default)]
+pub struct SuggestionIndex {
+    inner: RwLock<Index>,
+}
+
+impl SuggestionIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the indexed suggestions
+    pub fn rebuild(&self, suggestions: impl IntoIterator<Item = (Suggestion, u32)>) {
+        let mut index = Index::default();
+
+        for (suggestion, weight) in suggestions {
+            let entry = index.entries.len();
+            let words: Vec<String> = normalize(&suggestion.text).split(' ').map(String::from).collect();
+            for position in 0..words.len() {
+                index.keys.push((words[position..].join(" "), position, entry));
+            }
+            index.entries.push(Entry { suggestion, weight });
+        }
+
+        index.keys.sort();
+        *self.inner.write().unwrap() = index;
+    }
+
+    /// Best completions for `query`: matches at the start of a name rank
+    /// above matches on a later word, categories above products, then by
+    /// popularity and length
+    pub fn suggest(&self, query: &str, limit: usize) -> Vec<Suggestion> {
+        let query = normalize(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let index = self.inner.read().unwrap();
+        let start = index.keys.partition_point(|(key, ..)| key.as_str() < query.as_str());
+
+        // Earliest matching word position per entry
+        let mut matches: HashMap<usize, usize> = HashMap::new();
+        for (key, position, entry) in index.keys[start..].iter().take(MAX_SCANNED_KEYS) {
+            if !key.starts_with(&query) {
+                break;
+            }
+            matches
+                .entry(*entry)
+                .and_modify(|best| *best = (*best).min(*position))
+                .or_insert(*position);
+        }
+
+        let mut ranked: Vec<(usize, usize)> = matches.into_iter().collect();
+        ranked.sort_by_key(|&(entry, position)| {
+            let entry = &index.entries[entry];
+            (
+                position > 0,
+                entry.suggestion.kind != SuggestionKind::Category,
+                Reverse(entry.weight),
+                entry.suggestion.text.len(),
+                entry.suggestion.text.clone(),
+            )
+        });
+
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|(entry, _)| index.entries[entry].suggestion.clone())
+            .collect()
+    }
+}
+
+/// Lowercase and collapse whitespace so keys and queries compare directly
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn product(name: &str) -> (Suggestion, u32) {
+        let suggestion = Suggestion {
+            text: name.to_string(),
+            kind: SuggestionKind::Product,
+            product_id: Some(Uuid::new_v4()),
+        };
+        (suggestion, 1)
+    }
+
+    fn category(name: &str, count: u32) -> (Suggestion, u32) {
+        let suggestion = Suggestion {
+            text: name.to_string(),
+            kind: SuggestionKind::Category,
+            product_id: None,
+        };
+        (suggestion, count)
+    }
+
+    fn texts(suggestions: Vec<Suggestion>) -> Vec<String> {
+        suggestions.into_iter().map(|s| s.text).collect()
+    }
+
+    #[test]
+    fn ranks_leading_matches_and_categories_first() {
+        let index = SuggestionIndex::new();
+        index.rebuild([
+            product("Wireless Mouse"),
+            product("Wireless Bluetooth Headphones"),
+            product("Gaming Mouse Pad"),
+            category("Wireless Audio", 4),
+            product("Desk Lamp"),
+        ]);
+
+        assert_eq!(
+            texts(index.suggest("wire", 10)),
+            vec!["Wireless Audio", "Wireless Mouse", "Wireless Bluetooth Headphones"]
+        );
+        assert_eq!(texts(index.suggest("  MOUSE ", 10)), vec!["Wireless Mouse", "Gaming Mouse Pad"]);
+        assert_eq!(texts(index.suggest("mouse pad", 10)), vec!["Gaming Mouse Pad"]);
+        assert_eq!(texts(index.suggest("wire", 1)), vec!["Wireless Audio"]);
+    }
+
+    #[test]
+    fn empty_or_unknown_queries_return_nothing() {
+        let index = SuggestionIndex::new();
+        index.rebuild([product("Desk Lamp")]);
+
+        assert!(index.suggest("", 5).is_empty());
+        assert!(index.suggest("   ", 5).is_empty());
+        assert!(index.suggest("chair", 5).is_empty());
+
+        index.rebuild([product("Office Chair")]);
+        assert_eq!(texts(index.suggest("chair", 5)), vec!["Office Chair"]);
+        assert!(index.suggest("desk", 5).is_empty());
+    }
+}
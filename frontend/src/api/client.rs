@@ -1,6 +1,6 @@
-use gloo_net::http::{Request, RequestBuilder};
+use gloo_net::http::{Request, RequestBuilder, Response};
 use serde::{de::DeserializeOwned, Serialize};
-use shared::ApiError;
+use shared::{ApiError, AuthResponse, RefreshRequest};
 
 const API_BASE: &str = "http://localhost:3000/api";
 
@@ -12,13 +12,19 @@ fn add_auth_header(builder: RequestBuilder) -> RequestBuilder {
     }
 }
 
-pub async fn get<T: DeserializeOwned>(endpoint: &str) -> Result<T, ApiError> {
-    let url = format!("{}{}", API_BASE, endpoint);
+/// Send a request built by `build`. On 401 the access token is renewed with
+/// the stored refresh token once and the request is rebuilt and retried.
+async fn send<T: DeserializeOwned>(build: impl Fn() -> Result<Request, ApiError>) -> Result<T, ApiError> {
+    let mut response = build()?.send().await.map_err(|e| ApiError::internal_error(e.to_string()))?;
 
-    let request = add_auth_header(Request::get(&url));
+    if response.status() == 401 && refresh_session().await {
+        response = build()?.send().await.map_err(|e| ApiError::internal_error(e.to_string()))?;
+    }
 
-    let response = request.send().await.map_err(|e| ApiError::internal_error(e.to_string()))?;
+    parse_response(response).await
+}
 
+async fn parse_response<T: DeserializeOwned>(response: Response) -> Result<T, ApiError> {
     if response.ok() {
         response
             .json()
@@ -33,75 +39,83 @@ pub async fn get<T: DeserializeOwned>(endpoint: &str) -> Result<T, ApiError> {
     }
 }
 
-pub async fn post<T: DeserializeOwned, B: Serialize>(endpoint: &str, body: &B) -> Result<T, ApiError> {
-    let url = format!("{}{}", API_BASE, endpoint);
-
-    let request = add_auth_header(
-        Request::post(&url)
-            .header("Content-Type", "application/json")
-    )
-    .json(body)
-    .map_err(|e| ApiError::internal_error(e.to_string()))?;
+/// Rotate the refresh token for a new access token. Clears both tokens if the
+/// session is gone, so the user has to log in again.
+async fn refresh_session() -> bool {
+    let Some(refresh_token) = super::get_refresh_token() else {
+        return false;
+    };
+
+    let request = Request::post(&format!("{}/auth/refresh", API_BASE))
+        .header("Content-Type", "application/json")
+        .json(&RefreshRequest { refresh_token });
+    let Ok(request) = request else {
+        return false;
+    };
+
+    match request.send().await {
+        Ok(response) if response.ok() => match response.json::<AuthResponse>().await {
+            Ok(auth) => {
+                super::set_token(&auth.token);
+                super::set_refresh_token(&auth.refresh_token);
+                true
+            }
+            Err(_) => false,
+        },
+        Ok(_) => {
+            super::remove_token();
+            false
+        }
+        Err(_) => false,
+    }
+}
 
-    let response = request.send().await.map_err(|e| ApiError::internal_error(e.to_string()))?;
+pub async fn get<T: DeserializeOwned>(endpoint: &str) -> Result<T, ApiError> {
+    let url = format!("{}{}", API_BASE, endpoint);
 
-    if response.ok() {
-        response
-            .json()
-            .await
+    send(|| {
+        add_auth_header(Request::get(&url))
+            .build()
             .map_err(|e| ApiError::internal_error(e.to_string()))
-    } else {
-        let error: ApiError = response
-            .json()
-            .await
-            .unwrap_or_else(|_| ApiError::internal_error("Unknown error"));
-        Err(error)
-    }
+    })
+    .await
 }
 
-pub async fn put<T: DeserializeOwned, B: Serialize>(endpoint: &str, body: &B) -> Result<T, ApiError> {
+pub async fn post<T: DeserializeOwned, B: Serialize>(endpoint: &str, body: &B) -> Result<T, ApiError> {
     let url = format!("{}{}", API_BASE, endpoint);
 
-    let request = add_auth_header(
-        Request::put(&url)
-            .header("Content-Type", "application/json")
-    )
-    .json(body)
-    .map_err(|e| ApiError::internal_error(e.to_string()))?;
+    send(|| {
+        add_auth_header(
+            Request::post(&url)
+                .header("Content-Type", "application/json")
+        )
+        .json(body)
+        .map_err(|e| ApiError::internal_error(e.to_string()))
+    })
+    .await
+}
 
-    let response = request.send().await.map_err(|e| ApiError::internal_error(e.to_string()))?;
+pub async fn put<T: DeserializeOwned, B: Serialize>(endpoint: &str, body: &B) -> Result<T, ApiError> {
+    let url = format!("{}{}", API_BASE, endpoint);
 
-    if response.ok() {
-        response
-            .json()
-            .await
-            .map_err(|e| ApiError::internal_error(e.to_string()))
-    } else {
-        let error: ApiError = response
-            .json()
-            .await
-            .unwrap_or_else(|_| ApiError::internal_error("Unknown error"));
-        Err(error)
-    }
+    send(|| {
+        add_auth_header(
+            Request::put(&url)
+                .header("Content-Type", "application/json")
+        )
+        .json(body)
+        .map_err(|e| ApiError::internal_error(e.to_string()))
+    })
+    .await
 }
 
 pub async fn delete<T: DeserializeOwned>(endpoint: &str) -> Result<T, ApiError> {
     let url = format!("{}{}", API_BASE, endpoint);
 
-    let request = add_auth_header(Request::delete(&url));
-
-    let response = request.send().await.map_err(|e| ApiError::internal_error(e.to_string()))?;
-
-    if response.ok() {
-        response
-            .json()
-            .await
+    send(|| {
+        add_auth_header(Request::delete(&url))
+            .build()
             .map_err(|e| ApiError::internal_error(e.to_string()))
-    } else {
-        let error: ApiError = response
-            .json()
-            .await
-            .unwrap_or_else(|_| ApiError::internal_error("Unknown error"));
-        Err(error)
-    }
+    })
+    .await
 }
@@ -5,6 +5,7 @@ pub use client::*;
 use gloo_storage::{LocalStorage, Storage};
 
 const TOKEN_KEY: &str = "auth_token";
+const REFRESH_TOKEN_KEY: &str = "refresh_token";
 
 pub fn get_token() -> Option<String> {
     LocalStorage::get(TOKEN_KEY).ok()
@@ -14,6 +15,16 @@ pub fn set_token(token: &str) {
     let _ = LocalStorage::set(TOKEN_KEY, token);
 }
 
+pub fn get_refresh_token() -> Option<String> {
+    LocalStorage::get(REFRESH_TOKEN_KEY).ok()
+}
+
+pub fn set_refresh_token(token: &str) {
+    let _ = LocalStorage::set(REFRESH_TOKEN_KEY, token);
+}
+
+/// Forget both the access and the refresh token
 pub fn remove_token() {
     LocalStorage::delete(TOKEN_KEY);
+    LocalStorage::delete(REFRESH_TOKEN_KEY);
 }
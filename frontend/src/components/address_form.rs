@@ -0,0 +1,111 @@
+use yew::prelude::*;
+use web_sys::HtmlInputElement;
+use shared::Address;
+
+#[derive(Properties, PartialEq)]
+pub struct AddressFormProps {
+    pub address: Address,
+    pub on_change: Callback<Address>,
+}
+
+#[function_component(AddressForm)]
+pub fn address_form(props: &AddressFormProps) -> Html {
+    // Build an input handler that writes one field of the address
+    let field = |setter: fn(&mut Address, String)| {
+        let address = props.address.clone();
+        let on_change = props.on_change.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let mut updated = address.clone();
+            setter(&mut updated, input.value());
+            on_change.emit(updated);
+        })
+    };
+
+    let address = &props.address;
+
+    html! {
+        <div class="address-form">
+            <div class="form-group">
+                <label for="address-name">{"Full name"}</label>
+                <input
+                    id="address-name"
+                    value={address.name.clone()}
+                    oninput={field(|a, v| a.name = v)}
+                    required=true
+                />
+            </div>
+            <div class="form-group">
+                <label for="address-line1">{"Address line 1"}</label>
+                <input
+                    id="address-line1"
+                    value={address.line1.clone()}
+                    oninput={field(|a, v| a.line1 = v)}
+                    required=true
+                />
+            </div>
+            <div class="form-group">
+                <label for="address-line2">{"Address line 2 (optional)"}</label>
+                <input
+                    id="address-line2"
+                    value={address.line2.clone().unwrap_or_default()}
+                    oninput={field(|a, v| a.line2 = non_empty(v))}
+                />
+            </div>
+            <div class="form-row">
+                <div class="form-group">
+                    <label for="address-city">{"City"}</label>
+                    <input
+                        id="address-city"
+                        value={address.city.clone()}
+                        oninput={field(|a, v| a.city = v)}
+                        required=true
+                    />
+                </div>
+                <div class="form-group">
+                    <label for="address-region">{"State / Province"}</label>
+                    <input
+                        id="address-region"
+                        value={address.region.clone().unwrap_or_default()}
+                        oninput={field(|a, v| a.region = non_empty(v))}
+                    />
+                </div>
+            </div>
+            <div class="form-row">
+                <div class="form-group">
+                    <label for="address-postal-code">{"Postal code"}</label>
+                    <input
+                        id="address-postal-code"
+                        value={address.postal_code.clone()}
+                        oninput={field(|a, v| a.postal_code = v)}
+                        required=true
+                    />
+                </div>
+                <div class="form-group">
+                    <label for="address-country">{"Country code"}</label>
+                    <input
+                        id="address-country"
+                        maxlength="2"
+                        placeholder="US"
+                        value={address.country.clone()}
+                        oninput={field(|a, v| a.country = v.to_uppercase())}
+                        required=true
+                    />
+                </div>
+            </div>
+            <div class="form-group">
+                <label for="address-phone">{"Phone (optional)"}</label>
+                <input
+                    id="address-phone"
+                    type="tel"
+                    value={address.phone.clone().unwrap_or_default()}
+                    oninput={field(|a, v| a.phone = non_empty(v))}
+                />
+            </div>
+        </div>
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    Some(value).filter(|v| !v.is_empty())
+}
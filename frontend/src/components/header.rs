@@ -1,5 +1,7 @@
 use yew::prelude::*;
 use yew_router::prelude::*;
+use shared::{LogoutRequest, MessageResponse};
+use crate::api;
 use crate::routes::Route;
 use crate::state::{use_auth, AuthAction};
 
@@ -12,8 +14,15 @@ pub fn header() -> Html {
         let auth = auth.clone();
         let navigator = navigator.clone();
         Callback::from(move |_| {
-            auth.dispatch(AuthAction::Logout);
-            navigator.push(&Route::Home);
+            let auth = auth.clone();
+            let navigator = navigator.clone();
+
+            // Revoke the session server-side; local tokens go either way
+            wasm_bindgen_futures::spawn_local(async move {
+                let _ = api::post::<MessageResponse, _>("/auth/logout", &LogoutRequest::default()).await;
+                auth.dispatch(AuthAction::Logout);
+                navigator.push(&Route::Home);
+            });
         })
     };
 
@@ -37,6 +46,9 @@ pub fn header() -> Html {
                             <Link<Route> to={Route::Orders} classes="nav-link">
                                 {"Orders"}
                             </Link<Route>>
+                            <Link<Route> to={Route::Security} classes="nav-link">
+                                {"Security"}
+                            </Link<Route>>
                             <button class="btn btn-secondary" onclick={on_logout}>
                                 {"Logout"}
                             </button>
@@ -1,9 +1,17 @@
 mod header;
 mod footer;
 mod product_card;
+mod product_filters;
 mod loading;
+mod address_form;
+mod return_form;
+mod search_suggest;
 
 pub use header::Header;
 pub use footer::Footer;
 pub use product_card::ProductCard;
+pub use product_filters::{PriceRange, ProductFilters};
 pub use loading::Loading;
+pub use address_form::AddressForm;
+pub use return_form::ReturnForm;
+pub use search_suggest::SearchSuggest;
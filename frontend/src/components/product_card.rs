@@ -1,11 +1,14 @@
 use yew::prelude::*;
 use yew_router::prelude::*;
-use shared::Product;
+use shared::{Product, SearchHighlight};
 use crate::routes::Route;
 
 #[derive(Properties, PartialEq)]
 pub struct ProductCardProps {
     pub product: Product,
+    /// Matched terms when the card is a search result
+    #[prop_or_default]
+    pub highlight: Option<SearchHighlight>,
 }
 
 #[function_component(ProductCard)]
@@ -16,17 +19,22 @@ pub fn product_card(props: &ProductCardProps) -> Html {
         <div class="product-card">
             <Link<Route> to={Route::ProductDetail { id: product.id.to_string() }}>
                 <div class="product-image">
-                    if let Some(url) = &product.image_url {
-                        <img src={url.clone()} alt={product.name.clone()} />
+                    if let Some(url) = product.thumbnail_url.as_ref().or(product.image_url.as_ref()) {
+                        <img src={url.clone()} alt={product.name.clone()} loading="lazy" />
                     } else {
                         <div class="placeholder-image">{"No Image"}</div>
                     }
                 </div>
                 <div class="product-info">
-                    <h3 class="product-name">{&product.name}</h3>
+                    if let Some(highlight) = &props.highlight {
+                        <h3 class="product-name">{highlighted(&highlight.name)}</h3>
+                        <p class="product-snippet">{highlighted(&highlight.snippet)}</p>
+                    } else {
+                        <h3 class="product-name">{&product.name}</h3>
+                    }
                     <p class="product-category">{&product.category}</p>
-                    <p class="product-price">{format!("${:.2}", product.price)}</p>
-                    if product.stock > 0 {
+                    <p class="product-price">{product.price.to_string()}</p>
+                    if product.available_stock() > 0 {
                         <span class="in-stock">{"In Stock"}</span>
                     } else {
                         <span class="out-of-stock">{"Out of Stock"}</span>
@@ -36,3 +44,37 @@ pub fn product_card(props: &ProductCardProps) -> Html {
         </div>
     }
 }
+
+/// Render `<mark>`-delimited search highlights. Everything is emitted as text
+/// nodes, so markup in product data is never interpreted.
+fn highlighted(text: &str) -> Html {
+    let mut parts = Vec::new();
+    let mut marked = false;
+    let mut rest = text;
+
+    loop {
+        let marker = if marked { "</mark>" } else { "<mark>" };
+        let (segment, next) = match rest.find(marker) {
+            Some(index) => (&rest[..index], Some(&rest[index + marker.len()..])),
+            None => (rest, None),
+        };
+
+        if !segment.is_empty() {
+            if marked {
+                parts.push(html! { <mark>{segment}</mark> });
+            } else {
+                parts.push(html! { {segment} });
+            }
+        }
+
+        match next {
+            Some(next) => {
+                rest = next;
+                marked = !marked;
+            }
+            None => break,
+        }
+    }
+
+    html! { <>{for parts}</> }
+}
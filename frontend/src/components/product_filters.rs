@@ -0,0 +1,95 @@
+use yew::prelude::*;
+use web_sys::HtmlInputElement;
+use shared::{Currency, Money, PriceRangeFacet, ProductFacets};
+
+/// A selected price bucket: inclusive min and exclusive max in minor units
+pub type PriceRange = (i64, Option<i64>);
+
+#[derive(Properties, PartialEq)]
+pub struct ProductFiltersProps {
+    pub facets: ProductFacets,
+    pub selected_categories: Vec<String>,
+    pub selected_price: Option<PriceRange>,
+    pub in_stock: bool,
+    pub on_toggle_category: Callback<String>,
+    pub on_select_price: Callback<Option<PriceRange>>,
+    pub on_toggle_in_stock: Callback<bool>,
+}
+
+#[function_component(ProductFilters)]
+pub fn product_filters(props: &ProductFiltersProps) -> Html {
+    let on_in_stock = {
+        let on_toggle_in_stock = props.on_toggle_in_stock.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            on_toggle_in_stock.emit(input.checked());
+        })
+    };
+
+    let on_any_price = {
+        let on_select_price = props.on_select_price.clone();
+        Callback::from(move |_: Event| on_select_price.emit(None))
+    };
+
+    html! {
+        <aside class="product-filters">
+            <div class="filter-group">
+                <h3>{"Category"}</h3>
+                {for props.facets.categories.iter().map(|facet| {
+                    let category = facet.category.clone();
+                    let checked = props.selected_categories.contains(&category);
+                    let onchange = props.on_toggle_category.reform(move |_: Event| category.clone());
+                    html! {
+                        <label class="filter-option">
+                            <input type="checkbox" {checked} {onchange} />
+                            <span>{&facet.category}</span>
+                            <span class="facet-count">{facet.count}</span>
+                        </label>
+                    }
+                })}
+            </div>
+
+            <div class="filter-group">
+                <h3>{"Price"}</h3>
+                <label class="filter-option">
+                    <input
+                        type="radio"
+                        name="price"
+                        checked={props.selected_price.is_none()}
+                        onchange={on_any_price}
+                    />
+                    <span>{"Any price"}</span>
+                </label>
+                {for props.facets.price_ranges.iter().filter(|r| r.count > 0).map(|range| {
+                    let value = (range.min, range.max);
+                    let checked = props.selected_price == Some(value);
+                    let onchange = props.on_select_price.reform(move |_: Event| Some(value));
+                    html! {
+                        <label class="filter-option">
+                            <input type="radio" name="price" {checked} {onchange} />
+                            <span>{price_label(range)}</span>
+                            <span class="facet-count">{range.count}</span>
+                        </label>
+                    }
+                })}
+            </div>
+
+            <div class="filter-group">
+                <label class="filter-option">
+                    <input type="checkbox" checked={props.in_stock} onchange={on_in_stock} />
+                    <span>{"In stock only"}</span>
+                </label>
+            </div>
+        </aside>
+    }
+}
+
+fn price_label(range: &PriceRangeFacet) -> String {
+    let money = |amount| Money::new(amount, Currency::USD).to_string();
+
+    match range.max {
+        Some(max) if range.min == 0 => format!("Under {}", money(max)),
+        Some(max) => format!("{} – {}", money(range.min), money(max)),
+        None => format!("{} and up", money(range.min)),
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use yew::prelude::*;
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use uuid::Uuid;
+use shared::{CreateReturnRequest, OrderItem, ReturnItemRequest, ReturnRequest, ReturnResponse};
+use crate::api;
+
+#[derive(Properties, PartialEq)]
+pub struct ReturnFormProps {
+    pub order_id: Uuid,
+    pub items: Vec<OrderItem>,
+    pub on_created: Callback<ReturnRequest>,
+    pub on_cancel: Callback<()>,
+}
+
+/// Pick quantities of delivered items to send back, with a reason
+#[function_component(ReturnForm)]
+pub fn return_form(props: &ReturnFormProps) -> Html {
+    let quantities = use_state(HashMap::<Uuid, i32>::new);
+    let reason = use_state(String::new);
+    let submitting = use_state(|| false);
+    let error = use_state(|| Option::<String>::None);
+
+    let on_reason = {
+        let reason = reason.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlTextAreaElement = e.target_unchecked_into();
+            reason.set(input.value());
+        })
+    };
+
+    let on_submit = {
+        let quantities = quantities.clone();
+        let reason = reason.clone();
+        let submitting = submitting.clone();
+        let error = error.clone();
+        let order_id = props.order_id;
+        let on_created = props.on_created.clone();
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+
+            let items: Vec<ReturnItemRequest> = quantities
+                .iter()
+                .filter(|(_, quantity)| **quantity > 0)
+                .map(|(order_item_id, quantity)| ReturnItemRequest {
+                    order_item_id: *order_item_id,
+                    quantity: *quantity,
+                })
+                .collect();
+
+            if items.is_empty() {
+                error.set(Some("Select at least one item to return".to_string()));
+                return;
+            }
+
+            let req = CreateReturnRequest {
+                items,
+                reason: (*reason).clone(),
+            };
+            let submitting = submitting.clone();
+            let error = error.clone();
+            let on_created = on_created.clone();
+
+            submitting.set(true);
+            error.set(None);
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::post::<ReturnResponse, _>(&format!("/orders/{}/returns", order_id), &req).await {
+                    Ok(response) => on_created.emit(response.return_request),
+                    Err(e) => error.set(Some(e.message)),
+                }
+                submitting.set(false);
+            });
+        })
+    };
+
+    let on_cancel = {
+        let on_cancel = props.on_cancel.clone();
+        Callback::from(move |_: MouseEvent| on_cancel.emit(()))
+    };
+
+    html! {
+        <form class="return-form" onsubmit={on_submit}>
+            <h3>{"Request a Return"}</h3>
+            {for props.items.iter().map(|item| {
+                let item_id = item.id;
+                let oninput = {
+                    let quantities = quantities.clone();
+                    Callback::from(move |e: InputEvent| {
+                        let input: HtmlInputElement = e.target_unchecked_into();
+                        let mut updated = (*quantities).clone();
+                        updated.insert(item_id, input.value().parse().unwrap_or(0));
+                        quantities.set(updated);
+                    })
+                };
+
+                let name = match &item.variant_title {
+                    Some(title) => format!("{} - {}", item.product_name, title),
+                    None => item.product_name.clone(),
+                };
+
+                html! {
+                    <div class="return-item">
+                        <label for={format!("return-{}", item.id)}>
+                            {format!("{} (ordered {})", name, item.quantity)}
+                        </label>
+                        <input
+                            id={format!("return-{}", item.id)}
+                            type="number"
+                            min="0"
+                            max={item.quantity.to_string()}
+                            value={quantities.get(&item.id).copied().unwrap_or(0).to_string()}
+                            {oninput}
+                        />
+                    </div>
+                }
+            })}
+            <div class="form-group">
+                <label for="return-reason">{"Reason"}</label>
+                <textarea
+                    id="return-reason"
+                    maxlength="500"
+                    value={(*reason).clone()}
+                    oninput={on_reason}
+                    required=true
+                />
+            </div>
+            if let Some(err) = (*error).clone() {
+                <div class="error-message">{err}</div>
+            }
+            <div class="form-actions">
+                <button type="submit" class="btn btn-primary" disabled={*submitting}>
+                    if *submitting {
+                        {"Submitting..."}
+                    } else {
+                        {"Submit Return"}
+                    }
+                </button>
+                <button type="button" class="btn btn-secondary" onclick={on_cancel}>
+                    {"Cancel"}
+                </button>
+            </div>
+        </form>
+    }
+}
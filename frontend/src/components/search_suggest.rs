@@ -0,0 +1,177 @@
+use yew::prelude::*;
+use web_sys::HtmlInputElement;
+use gloo_timers::callback::Timeout;
+use shared::{SuggestResponse, Suggestion, SuggestionKind};
+use crate::api;
+
+/// Wait this long after the last keystroke before asking for suggestions
+const SUGGEST_DEBOUNCE_MS: u32 = 200;
+
+#[derive(Properties, PartialEq)]
+pub struct SearchSuggestProps {
+    pub value: String,
+    pub on_input: Callback<String>,
+    pub on_select: Callback<Suggestion>,
+    #[prop_or("Search products...".into())]
+    pub placeholder: AttrValue,
+}
+
+/// Search input with a debounced type-ahead dropdown
+#[function_component(SearchSuggest)]
+pub fn search_suggest(props: &SearchSuggestProps) -> Html {
+    let suggestions = use_state(Vec::<Suggestion>::new);
+    let open = use_state(|| false);
+    let active = use_state(|| Option::<usize>::None);
+    let timer = use_mut_ref(|| Option::<Timeout>::None);
+    // Only the latest request may update the list; slower earlier ones are dropped
+    let latest_request = use_mut_ref(|| 0u32);
+
+    let oninput = {
+        let on_input = props.on_input.clone();
+        let suggestions = suggestions.clone();
+        let open = open.clone();
+        let active = active.clone();
+        let timer = timer.clone();
+        let latest_request = latest_request.clone();
+
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let value = input.value();
+            on_input.emit(value.clone());
+            active.set(None);
+
+            // Dropping the previous timeout cancels it
+            timer.borrow_mut().take();
+            *latest_request.borrow_mut() += 1;
+
+            if value.trim().is_empty() {
+                suggestions.set(Vec::new());
+                open.set(false);
+                return;
+            }
+
+            let suggestions = suggestions.clone();
+            let open = open.clone();
+            let latest_request = latest_request.clone();
+            let request = *latest_request.borrow();
+
+            *timer.borrow_mut() = Some(Timeout::new(SUGGEST_DEBOUNCE_MS, move || {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let encoded = String::from(js_sys::encode_uri_component(value.trim()));
+                    let endpoint = format!("/products/suggest?q={}", encoded);
+
+                    if let Ok(response) = api::get::<SuggestResponse>(&endpoint).await {
+                        if *latest_request.borrow() == request {
+                            open.set(!response.suggestions.is_empty());
+                            suggestions.set(response.suggestions);
+                        }
+                    }
+                });
+            }));
+        })
+    };
+
+    let select = {
+        let on_select = props.on_select.clone();
+        let open = open.clone();
+        let active = active.clone();
+        let timer = timer.clone();
+
+        move |suggestion: Suggestion| {
+            timer.borrow_mut().take();
+            open.set(false);
+            active.set(None);
+            on_select.emit(suggestion);
+        }
+    };
+
+    let onkeydown = {
+        let suggestions = suggestions.clone();
+        let open = open.clone();
+        let active = active.clone();
+        let select = select.clone();
+
+        Callback::from(move |e: KeyboardEvent| {
+            if !*open || suggestions.is_empty() {
+                return;
+            }
+
+            let last = suggestions.len() - 1;
+            match e.key().as_str() {
+                "ArrowDown" => {
+                    e.prevent_default();
+                    active.set(Some(active.map_or(0, |i| (i + 1).min(last))));
+                }
+                "ArrowUp" => {
+                    e.prevent_default();
+                    active.set(active.and_then(|i| i.checked_sub(1)));
+                }
+                "Enter" => {
+                    if let Some(suggestion) = active.and_then(|i| suggestions.get(i)).cloned() {
+                        e.prevent_default();
+                        select(suggestion);
+                    }
+                }
+                "Escape" => {
+                    open.set(false);
+                    active.set(None);
+                }
+                _ => {}
+            }
+        })
+    };
+
+    let onblur = {
+        let open = open.clone();
+        Callback::from(move |_: FocusEvent| open.set(false))
+    };
+
+    let onfocus = {
+        let open = open.clone();
+        let suggestions = suggestions.clone();
+        Callback::from(move |_: FocusEvent| open.set(!suggestions.is_empty()))
+    };
+
+    html! {
+        <div class="search-suggest">
+            <input
+                type="search"
+                placeholder={props.placeholder.clone()}
+                value={props.value.clone()}
+                autocomplete="off"
+                {oninput}
+                {onkeydown}
+                {onblur}
+                {onfocus}
+            />
+            if *open {
+                <ul class="suggestions" role="listbox">
+                    {for suggestions.iter().enumerate().map(|(index, suggestion)| {
+                        let select = select.clone();
+                        let chosen = suggestion.clone();
+                        // mousedown fires before the input loses focus and closes the list
+                        let onmousedown = Callback::from(move |e: MouseEvent| {
+                            e.prevent_default();
+                            select(chosen.clone());
+                        });
+                        let kind = match suggestion.kind {
+                            SuggestionKind::Product => "Product",
+                            SuggestionKind::Category => "Category",
+                        };
+
+                        html! {
+                            <li
+                                class={classes!("suggestion", (*active == Some(index)).then_some("active"))}
+                                role="option"
+                                {onmousedown}
+                            >
+                                <span class="suggestion-text">{&suggestion.text}</span>
+                                <span class="suggestion-kind">{kind}</span>
+                            </li>
+                        }
+                    })}
+                </ul>
+            }
+        </div>
+    }
+}
@@ -33,11 +33,16 @@ fn switch(route: Route) -> Html {
         Route::Home => html! { <HomePage /> },
         Route::Products => html! { <ProductListPage /> },
         Route::ProductDetail { id } => html! { <ProductDetailPage {id} /> },
+        Route::Category { slug } => html! { <CategoryPage key={slug.clone()} slug={slug.clone()} /> },
         Route::Cart => html! { <CartPage /> },
         Route::Orders => html! { <OrderListPage /> },
         Route::OrderDetail { id } => html! { <OrderDetailPage {id} /> },
         Route::Login => html! { <LoginPage /> },
         Route::Register => html! { <RegisterPage /> },
+        Route::ForgotPassword => html! { <ForgotPasswordPage /> },
+        Route::ResetPassword => html! { <ResetPasswordPage /> },
+        Route::VerifyEmail => html! { <VerifyEmailPage /> },
+        Route::Security => html! { <SecurityPage /> },
         Route::NotFound => html! { <NotFoundPage /> },
     }
 }
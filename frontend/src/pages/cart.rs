@@ -1,8 +1,13 @@
 use yew::prelude::*;
 use yew_router::prelude::*;
-use shared::{Cart, CartResponse, UpdateCartItemRequest, CreateOrderRequest, OrderResponse, MessageResponse};
+use uuid::Uuid;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use shared::{
+    Address, AddressListResponse, ApplyCouponRequest, Cart, CartResponse, UpdateCartItemRequest, CreateOrderRequest,
+    OrderResponse, MessageResponse, SavedAddress,
+};
 use crate::api;
-use crate::components::Loading;
+use crate::components::{AddressForm, Loading};
 use crate::state::use_auth;
 use crate::routes::Route;
 
@@ -14,6 +19,16 @@ pub fn cart_page() -> Html {
     let loading = use_state(|| true);
     let error = use_state(|| Option::<String>::None);
     let processing = use_state(|| false);
+    let addresses = use_state(Vec::<SavedAddress>::new);
+    // None means "ship to a new address" entered in the form below
+    let selected_address = use_state(|| Option::<Uuid>::None);
+    let new_address = use_state(Address::default);
+    let checkout_error = use_state(|| Option::<String>::None);
+    // Set when checkout is refused until the email address is verified
+    let needs_verification = use_state(|| false);
+    let verification_message = use_state(|| Option::<String>::None);
+    let coupon_input = use_state(String::new);
+    let coupon_message = use_state(|| Option::<String>::None);
 
     // Redirect if not logged in
     if auth.user.is_none() {
@@ -25,6 +40,8 @@ pub fn cart_page() -> Html {
         let cart = cart.clone();
         let loading = loading.clone();
         let error = error.clone();
+        let addresses = addresses.clone();
+        let selected_address = selected_address.clone();
 
         use_effect_with((), move |_| {
             wasm_bindgen_futures::spawn_local(async move {
@@ -36,19 +53,37 @@ pub fn cart_page() -> Html {
                         error.set(Some(e.message));
                     }
                 }
+                if let Ok(response) = api::get::<AddressListResponse>("/addresses").await {
+                    let default = response.addresses.iter().find(|a| a.is_default).map(|a| a.id);
+                    selected_address.set(default);
+                    addresses.set(response.addresses);
+                }
                 loading.set(false);
             });
             || ()
         });
     }
 
+    let on_select_address = {
+        let selected_address = selected_address.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            selected_address.set(select.value().parse::<Uuid>().ok());
+        })
+    };
+
+    let on_new_address_change = {
+        let new_address = new_address.clone();
+        Callback::from(move |address: Address| new_address.set(address))
+    };
+
     let on_update_quantity = {
         let cart = cart.clone();
-        Callback::from(move |(product_id, quantity): (String, i32)| {
+        Callback::from(move |(item_path, quantity): (String, i32)| {
             let cart = cart.clone();
             wasm_bindgen_futures::spawn_local(async move {
                 let req = UpdateCartItemRequest { quantity };
-                if let Ok(response) = api::put::<CartResponse, _>(&format!("/cart/{}", product_id), &req).await {
+                if let Ok(response) = api::put::<CartResponse, _>(&format!("/cart/{}", item_path), &req).await {
                     cart.set(Some(response.cart));
                 }
             });
@@ -57,10 +92,10 @@ pub fn cart_page() -> Html {
 
     let on_remove_item = {
         let cart = cart.clone();
-        Callback::from(move |product_id: String| {
+        Callback::from(move |item_path: String| {
             let cart = cart.clone();
             wasm_bindgen_futures::spawn_local(async move {
-                if let Ok(_) = api::delete::<MessageResponse>(&format!("/cart/{}", product_id)).await {
+                if let Ok(_) = api::delete::<MessageResponse>(&format!("/cart/{}", item_path)).await {
                     // Refresh cart
                     if let Ok(response) = api::get::<CartResponse>("/cart").await {
                         cart.set(Some(response.cart));
@@ -70,22 +105,96 @@ pub fn cart_page() -> Html {
         })
     };
 
+    let on_coupon_input = {
+        let coupon_input = coupon_input.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            coupon_input.set(input.value());
+        })
+    };
+
+    let on_apply_coupon = {
+        let cart = cart.clone();
+        let coupon_input = coupon_input.clone();
+        let coupon_message = coupon_message.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let cart = cart.clone();
+            let coupon_input = coupon_input.clone();
+            let coupon_message = coupon_message.clone();
+            let req = ApplyCouponRequest { code: (*coupon_input).trim().to_string() };
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::post::<CartResponse, _>("/cart/coupon", &req).await {
+                    Ok(response) => {
+                        cart.set(Some(response.cart));
+                        coupon_input.set(String::new());
+                        coupon_message.set(None);
+                    }
+                    Err(e) => coupon_message.set(Some(e.message)),
+                }
+            });
+        })
+    };
+
+    let on_remove_coupon = {
+        let cart = cart.clone();
+        let coupon_message = coupon_message.clone();
+        Callback::from(move |_| {
+            let cart = cart.clone();
+            let coupon_message = coupon_message.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::delete::<CartResponse>("/cart/coupon").await {
+                    Ok(response) => {
+                        cart.set(Some(response.cart));
+                        coupon_message.set(None);
+                    }
+                    Err(e) => coupon_message.set(Some(e.message)),
+                }
+            });
+        })
+    };
+
     let on_checkout = {
         let processing = processing.clone();
         let navigator = navigator.clone();
+        let selected_address = selected_address.clone();
+        let new_address = new_address.clone();
+        let checkout_error = checkout_error.clone();
+        let needs_verification = needs_verification.clone();
 
         Callback::from(move |_| {
             let processing = processing.clone();
             let navigator = navigator.clone();
+            let checkout_error = checkout_error.clone();
+            let needs_verification = needs_verification.clone();
+            let req = match *selected_address {
+                Some(address_id) => CreateOrderRequest {
+                    address_id: Some(address_id),
+                    shipping_address: None,
+                },
+                None => CreateOrderRequest {
+                    address_id: None,
+                    shipping_address: Some((*new_address).clone()),
+                },
+            };
+
+            if let Some(address) = &req.shipping_address {
+                if let Err(msg) = address.validate() {
+                    checkout_error.set(Some(msg));
+                    return;
+                }
+            }
 
             processing.set(true);
+            checkout_error.set(None);
             wasm_bindgen_futures::spawn_local(async move {
-                let req = CreateOrderRequest { shipping_address: None };
                 match api::post::<OrderResponse, _>("/orders", &req).await {
                     Ok(response) => {
                         navigator.push(&Route::OrderDetail { id: response.order.order.id.to_string() });
                     }
-                    Err(_) => {
+                    Err(e) => {
+                        needs_verification.set(e.code == "EMAIL_NOT_VERIFIED");
+                        checkout_error.set(Some(e.message));
                         processing.set(false);
                     }
                 }
@@ -93,6 +202,20 @@ pub fn cart_page() -> Html {
         })
     };
 
+    let on_resend_verification = {
+        let verification_message = verification_message.clone();
+
+        Callback::from(move |_| {
+            let verification_message = verification_message.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::post::<MessageResponse, _>("/auth/resend-verification", &()).await {
+                    Ok(response) => verification_message.set(Some(response.message)),
+                    Err(e) => verification_message.set(Some(e.message)),
+                }
+            });
+        })
+    };
+
     if *loading {
         return html! { <Loading message="Loading cart..." /> };
     }
@@ -124,18 +247,25 @@ pub fn cart_page() -> Html {
             } else {
                 <div class="cart-items">
                     {for cart_data.items.clone().into_iter().map(|item| {
-                        let product_id = item.product_id.to_string();
+                        // Variant lines are addressed by product and variant
+                        let item_path = match item.variant_id {
+                            Some(variant_id) => format!("{}?variant_id={}", item.product_id, variant_id),
+                            None => item.product_id.to_string(),
+                        };
                         let on_update = on_update_quantity.clone();
                         let on_remove = on_remove_item.clone();
-                        let pid_update = product_id.clone();
-                        let pid_remove = product_id.clone();
+                        let pid_update = item_path.clone();
+                        let pid_remove = item_path.clone();
                         let quantity = item.quantity;
 
                         html! {
                             <div class="cart-item">
                                 <div class="item-info">
                                     <h3>{item.product_name.clone()}</h3>
-                                    <p class="price">{format!("${:.2}", item.product_price)}</p>
+                                    if let Some(title) = &item.variant_title {
+                                        <p class="variant-title">{title}</p>
+                                    }
+                                    <p class="price">{item.product_price.to_string()}</p>
                                 </div>
                                 <div class="item-quantity">
                                     <button
@@ -154,7 +284,7 @@ pub fn cart_page() -> Html {
                                     </button>
                                 </div>
                                 <div class="item-subtotal">
-                                    {format!("${:.2}", item.subtotal)}
+                                    {item.subtotal.to_string()}
                                 </div>
                                 <button
                                     class="btn btn-danger"
@@ -167,10 +297,98 @@ pub fn cart_page() -> Html {
                     })}
                 </div>
 
+                <div class="shipping-address">
+                    <h2>{"Shipping Address"}</h2>
+                    if !addresses.is_empty() {
+                        <select onchange={on_select_address}>
+                            {for addresses.iter().map(|a| {
+                                let label = match &a.label {
+                                    Some(label) => format!("{} - {}, {}", label, a.address.line1, a.address.city),
+                                    None => format!("{}, {}", a.address.line1, a.address.city),
+                                };
+                                html! {
+                                    <option
+                                        value={a.id.to_string()}
+                                        selected={*selected_address == Some(a.id)}
+                                    >
+                                        {label}
+                                    </option>
+                                }
+                            })}
+                            <option value="new" selected={selected_address.is_none()}>
+                                {"Ship to a new address"}
+                            </option>
+                        </select>
+                    }
+                    if selected_address.is_none() {
+                        <AddressForm address={(*new_address).clone()} on_change={on_new_address_change} />
+                    }
+                </div>
+
+                if let Some(msg) = (*checkout_error).clone() {
+                    <div class="error-message">{msg}</div>
+                }
+
+                if *needs_verification {
+                    <div class="verification-notice">
+                        if let Some(msg) = (*verification_message).clone() {
+                            <p>{msg}</p>
+                        } else {
+                            <p>{"Didn't get the email?"}</p>
+                        }
+                        <button class="btn btn-secondary" onclick={on_resend_verification}>
+                            {"Resend verification email"}
+                        </button>
+                    </div>
+                }
+
+                <div class="coupon">
+                    if let Some(code) = &cart_data.coupon_code {
+                        <div class="coupon-applied">
+                            <span>{"Coupon "}<strong>{code}</strong></span>
+                            <button class="btn btn-secondary" onclick={on_remove_coupon}>{"Remove"}</button>
+                        </div>
+                        if let Some(reason) = &cart_data.coupon_error {
+                            <p class="coupon-error">{reason}</p>
+                        }
+                    } else {
+                        <form class="coupon-form" onsubmit={on_apply_coupon}>
+                            <input
+                                type="text"
+                                placeholder="Coupon code"
+                                value={(*coupon_input).clone()}
+                                oninput={on_coupon_input}
+                            />
+                            <button type="submit" class="btn btn-secondary" disabled={coupon_input.trim().is_empty()}>
+                                {"Apply"}
+                            </button>
+                        </form>
+                    }
+                    if let Some(msg) = (*coupon_message).clone() {
+                        <p class="coupon-error">{msg}</p>
+                    }
+                </div>
+
                 <div class="cart-summary">
+                    <div class="summary-line">
+                        <span>{"Subtotal:"}</span>
+                        <span>{cart_data.subtotal.to_string()}</span>
+                    </div>
+                    {for cart_data.discounts.iter().map(|line| html! {
+                        <div class="summary-line discount">
+                            <span>{format!("{} ({}):", line.description, line.code)}</span>
+                            <span>{format!("-{}", line.amount)}</span>
+                        </div>
+                    })}
+                    if !cart_data.shipping.is_zero() {
+                        <div class="summary-line">
+                            <span>{"Shipping:"}</span>
+                            <span>{cart_data.shipping.to_string()}</span>
+                        </div>
+                    }
                     <div class="total">
                         <span>{"Total:"}</span>
-                        <span class="total-amount">{format!("${:.2}", cart_data.total)}</span>
+                        <span class="total-amount">{cart_data.total.to_string()}</span>
                     </div>
                     <button
                         class="btn btn-primary btn-large"
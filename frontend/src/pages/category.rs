@@ -0,0 +1,149 @@
+use yew::prelude::*;
+use yew_router::prelude::*;
+use shared::{CategoryResponse, Product, ProductListResponse};
+use crate::api;
+use crate::components::{Loading, ProductCard};
+use crate::routes::Route;
+
+#[derive(Properties, PartialEq)]
+pub struct CategoryProps {
+    pub slug: String,
+}
+
+/// A category with its subcategories and the products of its whole subtree.
+/// Rendered keyed by slug, so moving to another category starts on page one.
+#[function_component(CategoryPage)]
+pub fn category_page(props: &CategoryProps) -> Html {
+    let category = use_state(|| Option::<CategoryResponse>::None);
+    let products = use_state(Vec::<Product>::new);
+    let total_pages = use_state(|| 0u32);
+    let page = use_state(|| 1u32);
+    let loading = use_state(|| true);
+    let error = use_state(|| Option::<String>::None);
+
+    let slug = props.slug.clone();
+
+    {
+        let category = category.clone();
+        let error = error.clone();
+
+        use_effect_with(slug.clone(), move |slug| {
+            let endpoint = format!("/categories/{}", js_sys::encode_uri_component(slug));
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::get::<CategoryResponse>(&endpoint).await {
+                    Ok(response) => {
+                        category.set(Some(response));
+                        error.set(None);
+                    }
+                    Err(e) => {
+                        error.set(Some(e.message));
+                    }
+                }
+            });
+            || ()
+        });
+    }
+
+    {
+        let products = products.clone();
+        let total_pages = total_pages.clone();
+        let loading = loading.clone();
+
+        use_effect_with((slug.clone(), *page), move |(slug, page)| {
+            let endpoint = format!("/products/category/{}?page={}", js_sys::encode_uri_component(slug), page);
+
+            loading.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Ok(response) = api::get::<ProductListResponse>(&endpoint).await {
+                    total_pages.set(response.total_pages);
+                    products.set(response.products);
+                }
+                loading.set(false);
+            });
+            || ()
+        });
+    }
+
+    let on_prev = {
+        let page = page.clone();
+        Callback::from(move |_: MouseEvent| page.set((*page).saturating_sub(1).max(1)))
+    };
+
+    let on_next = {
+        let page = page.clone();
+        Callback::from(move |_: MouseEvent| page.set(*page + 1))
+    };
+
+    if let Some(err) = (*error).clone() {
+        return html! {
+            <div class="error-message">
+                <p>{"Error: "}{err}</p>
+            </div>
+        };
+    }
+
+    let Some(response) = (*category).clone() else {
+        return html! { <Loading message="Loading category..." /> };
+    };
+    let node = response.category;
+
+    html! {
+        <div class="category-page">
+            <nav class="breadcrumbs">
+                <Link<Route> to={Route::Products}>{"Products"}</Link<Route>>
+                {for response.breadcrumbs.iter().map(|ancestor| html! {
+                    <>
+                        <span class="breadcrumb-separator">{"/"}</span>
+                        <Link<Route> to={Route::Category { slug: ancestor.slug.clone() }}>
+                            {ancestor.name.clone()}
+                        </Link<Route>>
+                    </>
+                })}
+                <span class="breadcrumb-separator">{"/"}</span>
+                <span>{&node.category.name}</span>
+            </nav>
+
+            <h1>{&node.category.name}</h1>
+            if !node.category.description.is_empty() {
+                <p class="category-description">{&node.category.description}</p>
+            }
+
+            if !node.children.is_empty() {
+                <div class="subcategories">
+                    {for node.children.iter().map(|child| html! {
+                        <Link<Route> to={Route::Category { slug: child.category.slug.clone() }} classes="subcategory">
+                            {child.category.name.clone()}
+                            <span class="facet-count">{child.product_count}</span>
+                        </Link<Route>>
+                    })}
+                </div>
+            }
+
+            if *loading {
+                <Loading message="Loading products..." />
+            } else {
+                <div class="product-grid">
+                    {for products.iter().map(|product| html! {
+                        <ProductCard product={product.clone()} />
+                    })}
+                </div>
+
+                if products.is_empty() {
+                    <p class="no-products">{"No products in this category yet"}</p>
+                }
+
+                if *total_pages > 1 {
+                    <div class="pagination">
+                        <button class="btn btn-secondary" onclick={on_prev} disabled={*page <= 1}>
+                            {"Previous"}
+                        </button>
+                        <span>{format!("Page {} of {}", *page, *total_pages)}</span>
+                        <button class="btn btn-secondary" onclick={on_next} disabled={*page >= *total_pages}>
+                            {"Next"}
+                        </button>
+                    </div>
+                }
+            }
+        </div>
+    }
+}
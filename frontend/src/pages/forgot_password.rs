@@ -0,0 +1,90 @@
+use yew::prelude::*;
+use yew_router::prelude::*;
+use web_sys::HtmlInputElement;
+use shared::{ForgotPasswordRequest, MessageResponse};
+use crate::api;
+use crate::routes::Route;
+
+#[function_component(ForgotPasswordPage)]
+pub fn forgot_password_page() -> Html {
+    let email = use_state(String::new);
+    let message = use_state(|| Option::<String>::None);
+    let error = use_state(|| Option::<String>::None);
+    let loading = use_state(|| false);
+
+    let on_email_change = {
+        let email = email.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            email.set(input.value());
+        })
+    };
+
+    let on_submit = {
+        let email = email.clone();
+        let message = message.clone();
+        let error = error.clone();
+        let loading = loading.clone();
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+
+            let req = ForgotPasswordRequest { email: (*email).clone() };
+            let message = message.clone();
+            let error = error.clone();
+            let loading = loading.clone();
+
+            loading.set(true);
+            error.set(None);
+
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::post::<MessageResponse, _>("/auth/forgot-password", &req).await {
+                    Ok(response) => message.set(Some(response.message)),
+                    Err(e) => error.set(Some(e.message)),
+                }
+                loading.set(false);
+            });
+        })
+    };
+
+    html! {
+        <div class="auth-page">
+            <div class="auth-form-container">
+                <h1>{"Forgot Password"}</h1>
+
+                if let Some(err) = (*error).clone() {
+                    <div class="error-message">{err}</div>
+                }
+
+                if let Some(msg) = (*message).clone() {
+                    <div class="success-message">{msg}</div>
+                } else {
+                    <form onsubmit={on_submit}>
+                        <div class="form-group">
+                            <label for="email">{"Email"}</label>
+                            <input
+                                type="email"
+                                id="email"
+                                value={(*email).clone()}
+                                oninput={on_email_change}
+                                required=true
+                            />
+                        </div>
+
+                        <button type="submit" class="btn btn-primary btn-full" disabled={*loading}>
+                            if *loading {
+                                {"Sending..."}
+                            } else {
+                                {"Send Reset Link"}
+                            }
+                        </button>
+                    </form>
+                }
+
+                <p class="auth-link">
+                    <Link<Route> to={Route::Login}>{"Back to login"}</Link<Route>>
+                </p>
+            </div>
+        </div>
+    }
+}
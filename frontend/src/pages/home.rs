@@ -1,9 +1,25 @@
 use yew::prelude::*;
 use yew_router::prelude::*;
+use shared::{CategoryListResponse, CategoryNode};
+use crate::api;
 use crate::routes::Route;
 
 #[function_component(HomePage)]
 pub fn home_page() -> Html {
+    let categories = use_state(Vec::<CategoryNode>::new);
+
+    {
+        let categories = categories.clone();
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Ok(response) = api::get::<CategoryListResponse>("/categories").await {
+                    categories.set(response.categories);
+                }
+            });
+            || ()
+        });
+    }
+
     html! {
         <div class="home-page">
             <section class="hero">
@@ -14,6 +30,20 @@ pub fn home_page() -> Html {
                 </Link<Route>>
             </section>
 
+            if !categories.is_empty() {
+                <section class="home-categories">
+                    <h2>{"Shop by Category"}</h2>
+                    <div class="category-tiles">
+                        {for categories.iter().map(|node| html! {
+                            <Link<Route> to={Route::Category { slug: node.category.slug.clone() }} classes="category-tile">
+                                <h3>{&node.category.name}</h3>
+                                <p>{format!("{} products", node.product_count)}</p>
+                            </Link<Route>>
+                        })}
+                    </div>
+                </section>
+            }
+
             <section class="features">
                 <div class="feature">
                     <h3>{"Fast & Secure"}</h3>
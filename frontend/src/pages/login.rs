@@ -1,7 +1,7 @@
 use yew::prelude::*;
 use yew_router::prelude::*;
 use web_sys::HtmlInputElement;
-use shared::{LoginRequest, AuthResponse};
+use shared::{LoginRequest, LoginResponse, AuthResponse, TwoFactorLoginRequest};
 use crate::api;
 use crate::state::{use_auth, AuthAction};
 use crate::routes::Route;
@@ -13,6 +13,9 @@ pub fn login_page() -> Html {
 
     let email = use_state(String::new);
     let password = use_state(String::new);
+    // Set after the password step when the account has 2FA enabled
+    let challenge = use_state(|| Option::<String>::None);
+    let code = use_state(String::new);
     let error = use_state(|| Option::<String>::None);
     let loading = use_state(|| false);
 
@@ -38,9 +41,19 @@ pub fn login_page() -> Html {
         })
     };
 
+    let on_code_change = {
+        let code = code.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            code.set(input.value());
+        })
+    };
+
     let on_submit = {
         let email = email.clone();
         let password = password.clone();
+        let challenge = challenge.clone();
+        let code = code.clone();
         let error = error.clone();
         let loading = loading.clone();
         let auth = auth.clone();
@@ -51,6 +64,9 @@ pub fn login_page() -> Html {
 
             let email_val = (*email).clone();
             let password_val = (*password).clone();
+            let challenge_val = (*challenge).clone();
+            let code_val = (*code).clone();
+            let challenge = challenge.clone();
             let error = error.clone();
             let loading = loading.clone();
             let auth = auth.clone();
@@ -60,19 +76,40 @@ pub fn login_page() -> Html {
             error.set(None);
 
             wasm_bindgen_futures::spawn_local(async move {
-                let req = LoginRequest {
-                    email: email_val,
-                    password: password_val,
+                let result = match challenge_val {
+                    Some(challenge_token) => {
+                        let req = TwoFactorLoginRequest {
+                            challenge_token,
+                            code: code_val,
+                        };
+                        api::post::<AuthResponse, _>("/auth/2fa/verify", &req).await.map(Some)
+                    }
+                    None => {
+                        let req = LoginRequest {
+                            email: email_val,
+                            password: password_val,
+                        };
+                        match api::post::<LoginResponse, _>("/auth/login", &req).await {
+                            Ok(LoginResponse::Authenticated(response)) => Ok(Some(response)),
+                            Ok(LoginResponse::TwoFactorRequired { challenge_token, .. }) => {
+                                challenge.set(Some(challenge_token));
+                                Ok(None)
+                            }
+                            Err(e) => Err(e),
+                        }
+                    }
                 };
 
-                match api::post::<AuthResponse, _>("/auth/login", &req).await {
-                    Ok(response) => {
+                match result {
+                    Ok(Some(response)) => {
                         auth.dispatch(AuthAction::Login {
                             user: response.user,
                             token: response.token,
+                            refresh_token: response.refresh_token,
                         });
                         navigator.push(&Route::Home);
                     }
+                    Ok(None) => {}
                     Err(e) => {
                         error.set(Some(e.message));
                     }
@@ -92,37 +129,64 @@ pub fn login_page() -> Html {
                 }
 
                 <form onsubmit={on_submit}>
-                    <div class="form-group">
-                        <label for="email">{"Email"}</label>
-                        <input
-                            type="email"
-                            id="email"
-                            value={(*email).clone()}
-                            oninput={on_email_change}
-                            required=true
-                        />
-                    </div>
-
-                    <div class="form-group">
-                        <label for="password">{"Password"}</label>
-                        <input
-                            type="password"
-                            id="password"
-                            value={(*password).clone()}
-                            oninput={on_password_change}
-                            required=true
-                        />
-                    </div>
-
-                    <button type="submit" class="btn btn-primary btn-full" disabled={*loading}>
-                        if *loading {
-                            {"Logging in..."}
-                        } else {
-                            {"Login"}
-                        }
-                    </button>
+                    if challenge.is_some() {
+                        <div class="form-group">
+                            <label for="code">{"Authentication code"}</label>
+                            <input
+                                type="text"
+                                id="code"
+                                autocomplete="one-time-code"
+                                placeholder="123456 or a recovery code"
+                                value={(*code).clone()}
+                                oninput={on_code_change}
+                                required=true
+                            />
+                        </div>
+
+                        <button type="submit" class="btn btn-primary btn-full" disabled={*loading}>
+                            if *loading {
+                                {"Verifying..."}
+                            } else {
+                                {"Verify"}
+                            }
+                        </button>
+                    } else {
+                        <div class="form-group">
+                            <label for="email">{"Email"}</label>
+                            <input
+                                type="email"
+                                id="email"
+                                value={(*email).clone()}
+                                oninput={on_email_change}
+                                required=true
+                            />
+                        </div>
+
+                        <div class="form-group">
+                            <label for="password">{"Password"}</label>
+                            <input
+                                type="password"
+                                id="password"
+                                value={(*password).clone()}
+                                oninput={on_password_change}
+                                required=true
+                            />
+                        </div>
+
+                        <button type="submit" class="btn btn-primary btn-full" disabled={*loading}>
+                            if *loading {
+                                {"Logging in..."}
+                            } else {
+                                {"Login"}
+                            }
+                        </button>
+                    }
                 </form>
 
+                <p class="auth-link">
+                    <Link<Route> to={Route::ForgotPassword}>{"Forgot your password?"}</Link<Route>>
+                </p>
+
                 <p class="auth-link">
                     {"Don't have an account? "}
                     <Link<Route> to={Route::Register}>{"Register"}</Link<Route>>
@@ -1,19 +1,29 @@
 mod home;
 mod product_list;
 mod product_detail;
+mod category;
 mod cart;
 mod order_list;
 mod order_detail;
 mod login;
 mod register;
+mod forgot_password;
+mod reset_password;
+mod verify_email;
+mod security;
 mod not_found;
 
 pub use home::HomePage;
 pub use product_list::ProductListPage;
 pub use product_detail::ProductDetailPage;
+pub use category::CategoryPage;
 pub use cart::CartPage;
 pub use order_list::OrderListPage;
 pub use order_detail::OrderDetailPage;
 pub use login::LoginPage;
 pub use register::RegisterPage;
+pub use forgot_password::ForgotPasswordPage;
+pub use reset_password::ResetPasswordPage;
+pub use verify_email::VerifyEmailPage;
+pub use security::SecurityPage;
 pub use not_found::NotFoundPage;
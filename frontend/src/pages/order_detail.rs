@@ -1,8 +1,11 @@
 use yew::prelude::*;
 use yew_router::prelude::*;
-use shared::{OrderWithItems, OrderResponse, MessageResponse, OrderStatus};
+use shared::{
+    OrderWithItems, OrderResponse, MessageResponse, OrderStatus, PayOrderRequest, PaymentResponse,
+    ReturnListResponse, ReturnRequest, ReturnStatus,
+};
 use crate::api;
-use crate::components::Loading;
+use crate::components::{Loading, ReturnForm};
 use crate::state::use_auth;
 use crate::routes::Route;
 
@@ -19,6 +22,10 @@ pub fn order_detail_page(props: &OrderDetailProps) -> Html {
     let loading = use_state(|| true);
     let error = use_state(|| Option::<String>::None);
     let cancelling = use_state(|| false);
+    let paying = use_state(|| false);
+    let pay_error = use_state(|| Option::<String>::None);
+    let returns = use_state(Vec::<ReturnRequest>::new);
+    let show_return_form = use_state(|| false);
 
     let id = props.id.clone();
 
@@ -30,6 +37,7 @@ pub fn order_detail_page(props: &OrderDetailProps) -> Html {
 
     {
         let order = order.clone();
+        let returns = returns.clone();
         let loading = loading.clone();
         let error = error.clone();
         let id = id.clone();
@@ -44,6 +52,9 @@ pub fn order_detail_page(props: &OrderDetailProps) -> Html {
                         error.set(Some(e.message));
                     }
                 }
+                if let Ok(response) = api::get::<ReturnListResponse>(&format!("/orders/{}/returns", id)).await {
+                    returns.set(response.returns);
+                }
                 loading.set(false);
             });
             || ()
@@ -76,6 +87,57 @@ pub fn order_detail_page(props: &OrderDetailProps) -> Html {
         })
     };
 
+    let on_pay = {
+        let order = order.clone();
+        let paying = paying.clone();
+        let pay_error = pay_error.clone();
+        let id = id.clone();
+
+        Callback::from(move |_| {
+            let order = order.clone();
+            let paying = paying.clone();
+            let pay_error = pay_error.clone();
+            let id = id.clone();
+
+            paying.set(true);
+            pay_error.set(None);
+            wasm_bindgen_futures::spawn_local(async move {
+                let req = PayOrderRequest::default();
+                match api::post::<PaymentResponse, _>(&format!("/orders/{}/pay", id), &req).await {
+                    Ok(response) => {
+                        order.set(Some(response.order));
+                    }
+                    Err(e) => {
+                        pay_error.set(Some(e.message));
+                    }
+                }
+                paying.set(false);
+            });
+        })
+    };
+
+    let on_return_created = {
+        let returns = returns.clone();
+        let show_return_form = show_return_form.clone();
+
+        Callback::from(move |created: ReturnRequest| {
+            let mut updated = (*returns).clone();
+            updated.insert(0, created);
+            returns.set(updated);
+            show_return_form.set(false);
+        })
+    };
+
+    let toggle_return_form = {
+        let show_return_form = show_return_form.clone();
+        Callback::from(move |_: MouseEvent| show_return_form.set(!*show_return_form))
+    };
+
+    let close_return_form = {
+        let show_return_form = show_return_form.clone();
+        Callback::from(move |_| show_return_form.set(false))
+    };
+
     if *loading {
         return html! { <Loading message="Loading order..." /> };
     }
@@ -93,20 +155,14 @@ pub fn order_detail_page(props: &OrderDetailProps) -> Html {
         None => return html! { <div>{"Order not found"}</div> },
     };
 
-    let status_class = match order_data.order.status {
-        OrderStatus::Pending => "status-pending",
-        OrderStatus::Paid => "status-paid",
-        OrderStatus::Shipped => "status-shipped",
-        OrderStatus::Delivered => "status-delivered",
-        OrderStatus::Cancelled => "status-cancelled",
-    };
+    let order_status_class = status_class(&order_data.order.status);
 
     html! {
         <div class="order-detail-page">
             <h1>{format!("Order #{}", &order_data.order.id.to_string()[..8])}</h1>
 
             <div class="order-status-section">
-                <span class={classes!("order-status", "large", status_class)}>
+                <span class={classes!("order-status", "large", order_status_class)}>
                     {format!("{:?}", order_data.order.status)}
                 </span>
                 <p class="order-date">
@@ -114,6 +170,47 @@ pub fn order_detail_page(props: &OrderDetailProps) -> Html {
                 </p>
             </div>
 
+            if let Some(address) = &order_data.order.shipping_address {
+                <div class="order-shipping-address">
+                    <h2>{"Shipping Address"}</h2>
+                    <p>{&address.name}</p>
+                    <p>{&address.line1}</p>
+                    if let Some(line2) = &address.line2 {
+                        <p>{line2}</p>
+                    }
+                    <p>
+                        {&address.city}
+                        if let Some(region) = &address.region {
+                            {", "}{region}
+                        }
+                        {" "}{&address.postal_code}
+                    </p>
+                    <p>{&address.country}</p>
+                    if let Some(phone) = &address.phone {
+                        <p>{phone}</p>
+                    }
+                </div>
+            }
+
+            if !order_data.timeline.is_empty() {
+                <div class="order-timeline">
+                    <h2>{"Tracking"}</h2>
+                    <ol class="timeline">
+                        {for order_data.timeline.iter().map(|event| {
+                            html! {
+                                <li class="timeline-event">
+                                    <span class={classes!("timeline-dot", status_class(&event.to_status))}></span>
+                                    <span class="timeline-status">{format!("{:?}", event.to_status)}</span>
+                                    <span class="timeline-date">
+                                        {event.created_at.format("%Y-%m-%d %H:%M").to_string()}
+                                    </span>
+                                </li>
+                            }
+                        })}
+                    </ol>
+                </div>
+            }
+
             <div class="order-items">
                 <h2>{"Items"}</h2>
                 {for order_data.items.iter().map(|item| {
@@ -121,23 +218,102 @@ pub fn order_detail_page(props: &OrderDetailProps) -> Html {
                         <div class="order-item">
                             <div class="item-info">
                                 <h3>{&item.product_name}</h3>
+                                if let Some(title) = &item.variant_title {
+                                    <p class="variant-title">{title}</p>
+                                }
                                 <p>{format!("Quantity: {}", item.quantity)}</p>
-                                <p>{format!("Price: ${:.2}", item.price)}</p>
+                                <p>{format!("Price: {}", item.price)}</p>
                             </div>
                             <div class="item-subtotal">
-                                {format!("${:.2}", item.subtotal)}
+                                {item.subtotal.to_string()}
                             </div>
                         </div>
                     }
                 })}
             </div>
 
+            {for order_data.discounts.iter().map(|line| html! {
+                <div class="summary-line discount">
+                    <span>{format!("{} ({}):", line.description, line.code)}</span>
+                    <span>{format!("-{}", line.amount)}</span>
+                </div>
+            })}
+            if let Some(shipping) = order_data.shipping.filter(|s| !s.is_zero()) {
+                <div class="summary-line">
+                    <span>{"Shipping:"}</span>
+                    <span>{shipping.to_string()}</span>
+                </div>
+            }
+
             <div class="order-total">
                 <span>{"Total:"}</span>
-                <span class="total-amount">{format!("${:.2}", order_data.order.total)}</span>
+                <span class="total-amount">{order_data.order.total.to_string()}</span>
             </div>
 
+            if !returns.is_empty() {
+                <div class="order-returns">
+                    <h2>{"Returns"}</h2>
+                    {for returns.iter().map(|ret| {
+                        html! {
+                            <div class="return-request">
+                                <div class="return-header">
+                                    <span class={classes!("order-status", return_status_class(&ret.status))}>
+                                        {format!("{:?}", ret.status)}
+                                    </span>
+                                    <span>{ret.created_at.format("%Y-%m-%d").to_string()}</span>
+                                    <span class="return-refund">{ret.refund_amount.to_string()}</span>
+                                </div>
+                                <ul>
+                                    {for ret.items.iter().map(|item| html! {
+                                        <li>{format!("{} x {}", item.quantity, item.product_name)}</li>
+                                    })}
+                                </ul>
+                                <p class="return-reason">{&ret.reason}</p>
+                                if let Some(note) = &ret.admin_note {
+                                    <p class="return-note">{"Note: "}{note}</p>
+                                }
+                            </div>
+                        }
+                    })}
+                </div>
+            }
+
+            if *show_return_form {
+                <ReturnForm
+                    order_id={order_data.order.id}
+                    items={order_data.items.clone()}
+                    on_created={on_return_created}
+                    on_cancel={close_return_form}
+                />
+            }
+
+            if let Some(err) = (*pay_error).clone() {
+                <div class="error-message">{err}</div>
+            }
+
+            if order_data.order.status == OrderStatus::Pending {
+                if let Some(until) = order_data.reserved_until {
+                    <p class="reservation-note">
+                        {"Your items are reserved until "}{until.format("%Y-%m-%d %H:%M").to_string()}
+                        {". Pay before then to be sure of them."}
+                    </p>
+                }
+            }
+
             <div class="order-actions">
+                if order_data.order.status == OrderStatus::Pending {
+                    <button
+                        class="btn btn-primary"
+                        onclick={on_pay}
+                        disabled={*paying || *cancelling}
+                    >
+                        if *paying {
+                            {"Processing payment..."}
+                        } else {
+                            {"Pay Now"}
+                        }
+                    </button>
+                }
                 if order_data.order.can_cancel() {
                     <button
                         class="btn btn-danger"
@@ -151,6 +327,11 @@ pub fn order_detail_page(props: &OrderDetailProps) -> Html {
                         }
                     </button>
                 }
+                if order_data.order.can_request_return() && !*show_return_form {
+                    <button class="btn btn-secondary" onclick={toggle_return_form}>
+                        {"Request Return"}
+                    </button>
+                }
                 <Link<Route> to={Route::Orders} classes="btn btn-secondary">
                     {"Back to Orders"}
                 </Link<Route>>
@@ -158,3 +339,23 @@ pub fn order_detail_page(props: &OrderDetailProps) -> Html {
         </div>
     }
 }
+
+fn status_class(status: &OrderStatus) -> &'static str {
+    match status {
+        OrderStatus::Pending => "status-pending",
+        OrderStatus::Paid => "status-paid",
+        OrderStatus::Shipped => "status-shipped",
+        OrderStatus::Delivered => "status-delivered",
+        OrderStatus::Cancelled => "status-cancelled",
+        OrderStatus::PartiallyRefunded => "status-partially-refunded",
+        OrderStatus::Refunded => "status-refunded",
+    }
+}
+
+fn return_status_class(status: &ReturnStatus) -> &'static str {
+    match status {
+        ReturnStatus::Requested => "status-pending",
+        ReturnStatus::Approved => "status-refunded",
+        ReturnStatus::Rejected => "status-cancelled",
+    }
+}
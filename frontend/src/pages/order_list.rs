@@ -73,6 +73,8 @@ pub fn order_list_page() -> Html {
                             shared::OrderStatus::Shipped => "status-shipped",
                             shared::OrderStatus::Delivered => "status-delivered",
                             shared::OrderStatus::Cancelled => "status-cancelled",
+                            shared::OrderStatus::PartiallyRefunded => "status-partially-refunded",
+                            shared::OrderStatus::Refunded => "status-refunded",
                         };
 
                         html! {
@@ -85,7 +87,7 @@ pub fn order_list_page() -> Html {
                                 </div>
                                 <div class="order-info">
                                     <p class="order-date">{order.created_at.format("%Y-%m-%d %H:%M").to_string()}</p>
-                                    <p class="order-total">{format!("${:.2}", order.total)}</p>
+                                    <p class="order-total">{order.total.to_string()}</p>
                                 </div>
                                 <Link<Route> to={Route::OrderDetail { id: order.id.to_string() }} classes="btn btn-secondary">
                                     {"View Details"}
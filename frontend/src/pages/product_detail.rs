@@ -1,5 +1,9 @@
+use std::collections::BTreeMap;
 use yew::prelude::*;
-use shared::{Product, ProductResponse, AddToCartRequest, CartResponse};
+use shared::{
+    Product, ProductImage, ProductOption, ProductVariant, ProductResponse, AddToCartRequest, CartResponse,
+    CreateStockSubscriptionRequest, StockSubscriptionListResponse, StockSubscriptionResponse,
+};
 use crate::api;
 use crate::components::Loading;
 use crate::state::use_auth;
@@ -13,16 +17,30 @@ pub struct ProductDetailProps {
 pub fn product_detail_page(props: &ProductDetailProps) -> Html {
     let auth = use_auth();
     let product = use_state(|| Option::<Product>::None);
+    let options = use_state(Vec::<ProductOption>::new);
+    let variants = use_state(Vec::<ProductVariant>::new);
+    let images = use_state(Vec::<ProductImage>::new);
+    // Index into `images` of the picture shown large
+    let active_image = use_state(|| 0usize);
+    // Option name to chosen value
+    let selected = use_state(BTreeMap::<String, String>::new);
     let loading = use_state(|| true);
     let error = use_state(|| Option::<String>::None);
     let quantity = use_state(|| 1i32);
     let adding = use_state(|| false);
     let message = use_state(|| Option::<String>::None);
+    // Whether the user is waiting for a back-in-stock email for this product
+    let subscribed = use_state(|| false);
 
     let id = props.id.clone();
 
     {
         let product = product.clone();
+        let options = options.clone();
+        let variants = variants.clone();
+        let images = images.clone();
+        let active_image = active_image.clone();
+        let selected = selected.clone();
         let loading = loading.clone();
         let error = error.clone();
         let id = id.clone();
@@ -31,7 +49,19 @@ pub fn product_detail_page(props: &ProductDetailProps) -> Html {
             wasm_bindgen_futures::spawn_local(async move {
                 match api::get::<ProductResponse>(&format!("/products/{}", id)).await {
                     Ok(response) => {
+                        // Preselect the first variant that can be bought
+                        let first = response
+                            .variants
+                            .iter()
+                            .find(|v| v.is_available())
+                            .or_else(|| response.variants.first());
+                        selected.set(first.map(|v| v.options.clone()).unwrap_or_default());
                         product.set(Some(response.product));
+                        options.set(response.options);
+                        variants.set(response.variants);
+                        // Start on the primary image
+                        active_image.set(response.images.iter().position(|i| i.is_primary).unwrap_or(0));
+                        images.set(response.images);
                     }
                     Err(e) => {
                         error.set(Some(e.message));
@@ -43,6 +73,23 @@ pub fn product_detail_page(props: &ProductDetailProps) -> Html {
         });
     }
 
+    {
+        let subscribed = subscribed.clone();
+        let logged_in = auth.user.is_some();
+
+        use_effect_with((id.clone(), logged_in), move |(id, logged_in)| {
+            if *logged_in {
+                let id = id.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    if let Ok(response) = api::get::<StockSubscriptionListResponse>("/stock-subscriptions").await {
+                        subscribed.set(response.subscriptions.iter().any(|s| s.product_id.to_string() == id));
+                    }
+                });
+            }
+            || ()
+        });
+    }
+
     let on_quantity_change = {
         let quantity = quantity.clone();
         Callback::from(move |e: Event| {
@@ -53,8 +100,20 @@ pub fn product_detail_page(props: &ProductDetailProps) -> Html {
         })
     };
 
+    let on_option_change = {
+        let selected = selected.clone();
+        Callback::from(move |(name, value): (String, String)| {
+            let mut updated = (*selected).clone();
+            updated.insert(name, value);
+            selected.set(updated);
+        })
+    };
+
+    let variant = variants.iter().find(|v| v.options == *selected).cloned();
+
     let on_add_to_cart = {
         let product = product.clone();
+        let variant_id = variant.as_ref().map(|v| v.id);
         let quantity = quantity.clone();
         let adding = adding.clone();
         let message = message.clone();
@@ -70,6 +129,7 @@ pub fn product_detail_page(props: &ProductDetailProps) -> Html {
                 wasm_bindgen_futures::spawn_local(async move {
                     let req = AddToCartRequest {
                         product_id,
+                        variant_id,
                         quantity: qty,
                     };
                     match api::post::<CartResponse, _>("/cart", &req).await {
@@ -86,6 +146,27 @@ pub fn product_detail_page(props: &ProductDetailProps) -> Html {
         })
     };
 
+    let on_notify_me = {
+        let product = product.clone();
+        let subscribed = subscribed.clone();
+        let message = message.clone();
+
+        Callback::from(move |_| {
+            if let Some(p) = (*product).clone() {
+                let subscribed = subscribed.clone();
+                let message = message.clone();
+
+                wasm_bindgen_futures::spawn_local(async move {
+                    let req = CreateStockSubscriptionRequest { product_id: p.id };
+                    match api::post::<StockSubscriptionResponse, _>("/stock-subscriptions", &req).await {
+                        Ok(_) => subscribed.set(true),
+                        Err(e) => message.set(Some(format!("Error: {}", e.message))),
+                    }
+                });
+            }
+        })
+    };
+
     if *loading {
         return html! { <Loading message="Loading product..." /> };
     }
@@ -103,32 +184,89 @@ pub fn product_detail_page(props: &ProductDetailProps) -> Html {
         None => return html! { <div>{"Product not found"}</div> },
     };
 
+    // A product with variants is priced and stocked by the chosen variant
+    let has_variants = !variants.is_empty();
+    let price = variant.as_ref().map(|v| v.price_or(product.price)).unwrap_or(product.price);
+    let stock = match &variant {
+        Some(v) if v.is_active => v.available_stock(),
+        Some(_) => 0,
+        None if has_variants => 0,
+        None => product.available_stock(),
+    };
+
     html! {
         <div class="product-detail-page">
             <div class="product-detail">
-                <div class="product-image-large">
-                    if let Some(url) = &product.image_url {
-                        <img src={url.clone()} alt={product.name.clone()} />
-                    } else {
-                        <div class="placeholder-image">{"No Image"}</div>
+                <div class="product-gallery">
+                    <div class="product-image-large">
+                        if let Some(image) = images.get(*active_image) {
+                            <img src={image.url.clone()} alt={image.alt_text.clone()} />
+                        } else if let Some(url) = &product.image_url {
+                            <img src={url.clone()} alt={product.name.clone()} />
+                        } else {
+                            <div class="placeholder-image">{"No Image"}</div>
+                        }
+                    </div>
+
+                    if images.len() > 1 {
+                        <div class="gallery-thumbnails">
+                            {for images.iter().enumerate().map(|(index, image)| {
+                                let active_image = active_image.clone();
+                                let class = if index == *active_image { "gallery-thumbnail active" } else { "gallery-thumbnail" };
+                                html! {
+                                    <button {class} onclick={Callback::from(move |_: MouseEvent| active_image.set(index))}>
+                                        <img src={image.thumbnail_url.clone()} alt={image.alt_text.clone()} />
+                                    </button>
+                                }
+                            })}
+                        </div>
                     }
                 </div>
 
                 <div class="product-info-detail">
                     <h1>{&product.name}</h1>
                     <p class="category">{"Category: "}{&product.category}</p>
-                    <p class="price">{format!("${:.2}", product.price)}</p>
+                    <p class="price">{price.to_string()}</p>
                     <p class="description">{&product.description}</p>
 
-                    if product.stock > 0 {
-                        <p class="stock in-stock">{format!("{} in stock", product.stock)}</p>
+                    if has_variants {
+                        <div class="variant-options">
+                            {for options.iter().map(|option| {
+                                let name = option.name.clone();
+                                let onchange = on_option_change.reform(move |e: Event| {
+                                    let select: web_sys::HtmlSelectElement = e.target_unchecked_into();
+                                    (name.clone(), select.value())
+                                });
+                                let current = selected.get(&option.name).cloned().unwrap_or_default();
+
+                                html! {
+                                    <label class="variant-option">
+                                        <span>{&option.name}</span>
+                                        <select {onchange}>
+                                            {for option.values.iter().map(|value| html! {
+                                                <option value={value.clone()} selected={*value == current}>
+                                                    {value}
+                                                </option>
+                                            })}
+                                        </select>
+                                    </label>
+                                }
+                            })}
+                        </div>
+                        if let Some(v) = &variant {
+                            <p class="variant-sku">{"SKU: "}{&v.sku}</p>
+                        }
+                    }
+
+                    if stock > 0 {
+                        <p class="stock in-stock">{format!("{} in stock", stock)}</p>
 
                         if auth.user.is_some() {
                             <div class="add-to-cart">
                                 <input
                                     type="number"
                                     min="1"
-                                    max={product.stock.to_string()}
+                                    max={stock.to_string()}
                                     value={quantity.to_string()}
                                     onchange={on_quantity_change}
                                 />
@@ -147,10 +285,23 @@ pub fn product_detail_page(props: &ProductDetailProps) -> Html {
                         } else {
                             <p class="login-prompt">{"Please login to add items to cart"}</p>
                         }
+                    } else if has_variants && variant.is_none() {
+                        <p class="stock out-of-stock">{"This combination is not available"}</p>
                     } else {
                         <p class="stock out-of-stock">{"Out of Stock"}</p>
                     }
 
+                    // Back-in-stock emails are per product, not per variant
+                    if !product.is_available() && auth.user.is_some() {
+                        if *subscribed {
+                            <p class="notify-me subscribed">{"We'll email you when it's back in stock"}</p>
+                        } else {
+                            <button class="btn btn-secondary notify-me" onclick={on_notify_me}>
+                                {"Notify me when available"}
+                            </button>
+                        }
+                    }
+
                     if let Some(msg) = (*message).clone() {
                         <p class="message">{msg}</p>
                     }
@@ -1,24 +1,135 @@
 use yew::prelude::*;
-use shared::{Product, ProductListResponse};
+use yew_router::prelude::*;
+use web_sys::HtmlSelectElement;
+use serde::{Deserialize, Serialize};
+use shared::{Product, ProductFacets, ProductListResponse, SearchHighlight, Suggestion, SuggestionKind};
 use crate::api;
-use crate::components::{ProductCard, Loading};
+use crate::components::{PriceRange, ProductCard, ProductFilters, SearchSuggest, Loading};
+use crate::routes::Route;
+
+/// Search, filters and page live in the URL, e.g.
+/// `/products?q=lamp&category=Office,Furniture&in_stock=true&page=2`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct ProductListQuery {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    q: Option<String>,
+    /// Comma-separated selected categories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_price: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_price: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    in_stock: Option<bool>,
+    /// One of the `SORT_OPTIONS` values
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sort: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    page: Option<u32>,
+}
+
+/// (value, label, sort_by, sort_order)
+const SORT_OPTIONS: &[(&str, &str, &str, &str)] = &[
+    ("newest", "Newest", "newest", "desc"),
+    ("price_asc", "Price: low to high", "price", "asc"),
+    ("price_desc", "Price: high to low", "price", "desc"),
+    ("name_asc", "Name: A to Z", "name", "asc"),
+    ("name_desc", "Name: Z to A", "name", "desc"),
+];
+
+impl ProductListQuery {
+    fn search(&self) -> &str {
+        self.q.as_deref().map(str::trim).unwrap_or_default()
+    }
+
+    fn categories(&self) -> Vec<String> {
+        self.category
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .filter(|c| !c.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    fn price(&self) -> Option<PriceRange> {
+        self.min_price.map(|min| (min, self.max_price))
+    }
+
+    /// The same query with a changed filter, back on the first page
+    fn with(&self, change: impl FnOnce(&mut Self)) -> Self {
+        let mut query = self.clone();
+        change(&mut query);
+        query.page = None;
+        query
+    }
+
+    fn api_endpoint(&self) -> String {
+        let mut params = vec![format!("page={}", self.page.unwrap_or(1))];
+
+        if !self.search().is_empty() {
+            params.push(format!("search={}", String::from(js_sys::encode_uri_component(self.search()))));
+        }
+        if let Some(category) = self.category.as_deref().filter(|c| !c.is_empty()) {
+            params.push(format!("category={}", String::from(js_sys::encode_uri_component(category))));
+        }
+        if let Some(min_price) = self.min_price {
+            params.push(format!("min_price={}", min_price));
+        }
+        if let Some(max_price) = self.max_price {
+            params.push(format!("max_price={}", max_price));
+        }
+        if self.in_stock == Some(true) {
+            params.push("in_stock=true".to_string());
+        }
+        if let Some((_, _, sort_by, sort_order)) = SORT_OPTIONS.iter().find(|(value, ..)| Some(*value) == self.sort.as_deref()) {
+            params.push(format!("sort_by={}&sort_order={}", sort_by, sort_order));
+        }
+
+        format!("/products?{}", params.join("&"))
+    }
+}
 
 #[function_component(ProductListPage)]
 pub fn product_list_page() -> Html {
+    let navigator = use_navigator().unwrap();
+    let query = use_location()
+        .and_then(|l| l.query::<ProductListQuery>().ok())
+        .unwrap_or_default();
+    let page = query.page.unwrap_or(1).max(1);
+
     let products = use_state(|| Vec::<Product>::new());
+    let highlights = use_state(Vec::<SearchHighlight>::new);
+    let facets = use_state(ProductFacets::default);
+    let total = use_state(|| 0u32);
+    let total_pages = use_state(|| 0u32);
+    let search_input = use_state(|| query.search().to_string());
     let loading = use_state(|| true);
     let error = use_state(|| Option::<String>::None);
 
     {
         let products = products.clone();
+        let highlights = highlights.clone();
+        let facets = facets.clone();
+        let total = total.clone();
+        let total_pages = total_pages.clone();
         let loading = loading.clone();
         let error = error.clone();
 
-        use_effect_with((), move |_| {
+        use_effect_with(query.clone(), move |query| {
+            let endpoint = query.api_endpoint();
+
+            loading.set(true);
             wasm_bindgen_futures::spawn_local(async move {
-                match api::get::<ProductListResponse>("/products").await {
+                match api::get::<ProductListResponse>(&endpoint).await {
                     Ok(response) => {
+                        total.set(response.total);
+                        total_pages.set(response.total_pages);
                         products.set(response.products);
+                        highlights.set(response.highlights);
+                        facets.set(response.facets.unwrap_or_default());
+                        error.set(None);
                     }
                     Err(e) => {
                         error.set(Some(e.message));
@@ -30,31 +141,192 @@ pub fn product_list_page() -> Html {
         });
     }
 
-    if *loading {
-        return html! { <Loading message="Loading products..." /> };
-    }
+    let go_to = {
+        let navigator = navigator.clone();
+        move |query: ProductListQuery| {
+            let _ = navigator.push_with_query(&Route::Products, &query);
+        }
+    };
+
+    let on_search_input = {
+        let search_input = search_input.clone();
+        Callback::from(move |value: String| search_input.set(value))
+    };
+
+    // Products open directly; categories become the category filter
+    let on_suggestion = {
+        let navigator = navigator.clone();
+        let search_input = search_input.clone();
+        let query = query.clone();
+        let go_to = go_to.clone();
+        Callback::from(move |suggestion: Suggestion| match (suggestion.kind, suggestion.product_id) {
+            (SuggestionKind::Product, Some(id)) => {
+                navigator.push(&Route::ProductDetail { id: id.to_string() });
+            }
+            _ => {
+                search_input.set(String::new());
+                go_to(query.with(|query| {
+                    query.q = None;
+                    query.category = Some(suggestion.text);
+                }));
+            }
+        })
+    };
+
+    let on_search = {
+        let search_input = search_input.clone();
+        let query = query.clone();
+        let go_to = go_to.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let q = search_input.trim().to_string();
+            go_to(query.with(|query| query.q = (!q.is_empty()).then_some(q)));
+        })
+    };
+
+    let on_sort = {
+        let query = query.clone();
+        let go_to = go_to.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            let value = select.value();
+            go_to(query.with(|query| query.sort = (!value.is_empty()).then_some(value)));
+        })
+    };
+
+    let on_toggle_category = {
+        let query = query.clone();
+        let go_to = go_to.clone();
+        Callback::from(move |category: String| {
+            let mut selected = query.categories();
+            match selected.iter().position(|c| *c == category) {
+                Some(index) => {
+                    selected.remove(index);
+                }
+                None => selected.push(category),
+            }
+            go_to(query.with(|query| query.category = (!selected.is_empty()).then(|| selected.join(","))));
+        })
+    };
+
+    let on_select_price = {
+        let query = query.clone();
+        let go_to = go_to.clone();
+        Callback::from(move |range: Option<PriceRange>| {
+            go_to(query.with(|query| {
+                query.min_price = range.map(|(min, _)| min);
+                query.max_price = range.and_then(|(_, max)| max);
+            }));
+        })
+    };
+
+    let on_toggle_in_stock = {
+        let query = query.clone();
+        let go_to = go_to.clone();
+        Callback::from(move |in_stock: bool| {
+            go_to(query.with(|query| query.in_stock = in_stock.then_some(true)));
+        })
+    };
+
+    let on_prev = {
+        let query = query.clone();
+        let go_to = go_to.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut query = query.clone();
+            query.page = (page > 2).then_some(page - 1);
+            go_to(query);
+        })
+    };
+
+    let on_next = {
+        let query = query.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut query = query.clone();
+            query.page = Some(page + 1);
+            go_to(query);
+        })
+    };
 
-    if let Some(err) = (*error).clone() {
-        return html! {
+    let results = if *loading {
+        html! { <Loading message="Loading products..." /> }
+    } else if let Some(err) = (*error).clone() {
+        html! {
             <div class="error-message">
                 <p>{"Error: "}{err}</p>
             </div>
-        };
-    }
+        }
+    } else {
+        html! {
+            <>
+                if !query.search().is_empty() {
+                    <p class="search-summary">{format!("{} results for \"{}\"", *total, query.search())}</p>
+                }
+
+                <div class="product-grid">
+                    {for products.iter().map(|product| {
+                        let highlight = highlights.iter().find(|h| h.product_id == product.id).cloned();
+                        html! { <ProductCard product={product.clone()} {highlight} /> }
+                    })}
+                </div>
+
+                if products.is_empty() {
+                    <p class="no-products">{"No products match your filters"}</p>
+                }
+
+                if *total_pages > 1 {
+                    <div class="pagination">
+                        <button class="btn btn-secondary" onclick={on_prev} disabled={page <= 1}>
+                            {"Previous"}
+                        </button>
+                        <span>{format!("Page {} of {}", page, *total_pages)}</span>
+                        <button class="btn btn-secondary" onclick={on_next} disabled={page >= *total_pages}>
+                            {"Next"}
+                        </button>
+                    </div>
+                }
+            </>
+        }
+    };
+
+    let current_sort = query.sort.clone().unwrap_or_default();
 
     html! {
         <div class="product-list-page">
             <h1>{"Products"}</h1>
 
-            <div class="product-grid">
-                {for products.iter().map(|product| {
-                    html! { <ProductCard product={product.clone()} /> }
-                })}
+            <div class="product-toolbar">
+                <form class="search-form" onsubmit={on_search}>
+                    <SearchSuggest
+                        value={(*search_input).clone()}
+                        on_input={on_search_input}
+                        on_select={on_suggestion}
+                    />
+                    <button type="submit" class="btn btn-primary">{"Search"}</button>
+                </form>
+                <select class="sort-select" onchange={on_sort}>
+                    <option value="" selected={current_sort.is_empty()}>
+                        {if query.search().is_empty() { "Sort: default" } else { "Sort: relevance" }}
+                    </option>
+                    {for SORT_OPTIONS.iter().map(|(value, label, ..)| html! {
+                        <option value={*value} selected={current_sort == *value}>{*label}</option>
+                    })}
+                </select>
             </div>
 
-            if products.is_empty() {
-                <p class="no-products">{"No products available"}</p>
-            }
+            <div class="product-browser">
+                <ProductFilters
+                    facets={(*facets).clone()}
+                    selected_categories={query.categories()}
+                    selected_price={query.price()}
+                    in_stock={query.in_stock == Some(true)}
+                    {on_toggle_category}
+                    {on_select_price}
+                    {on_toggle_in_stock}
+                />
+                <div class="product-results">
+                    {results}
+                </div>
+            </div>
         </div>
     }
 }
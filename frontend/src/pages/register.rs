@@ -104,6 +104,7 @@ pub fn register_page() -> Html {
                         auth.dispatch(AuthAction::Login {
                             user: response.user,
                             token: response.token,
+                            refresh_token: response.refresh_token,
                         });
                         navigator.push(&Route::Home);
                     }
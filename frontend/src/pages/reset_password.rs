@@ -0,0 +1,138 @@
+use yew::prelude::*;
+use yew_router::prelude::*;
+use web_sys::HtmlInputElement;
+use serde::Deserialize;
+use shared::{MessageResponse, ResetPasswordRequest};
+use crate::api;
+use crate::routes::Route;
+
+/// The emailed link carries the token as `?token=`
+#[derive(Deserialize)]
+struct ResetQuery {
+    token: String,
+}
+
+#[function_component(ResetPasswordPage)]
+pub fn reset_password_page() -> Html {
+    let location = use_location();
+    let token = location
+        .and_then(|l| l.query::<ResetQuery>().ok())
+        .map(|q| q.token);
+
+    let password = use_state(String::new);
+    let confirm = use_state(String::new);
+    let message = use_state(|| Option::<String>::None);
+    let error = use_state(|| Option::<String>::None);
+    let loading = use_state(|| false);
+
+    let on_password_change = {
+        let password = password.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            password.set(input.value());
+        })
+    };
+
+    let on_confirm_change = {
+        let confirm = confirm.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            confirm.set(input.value());
+        })
+    };
+
+    let on_submit = {
+        let token = token.clone();
+        let password = password.clone();
+        let confirm = confirm.clone();
+        let message = message.clone();
+        let error = error.clone();
+        let loading = loading.clone();
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+
+            let Some(token) = token.clone() else {
+                return;
+            };
+
+            if *password != *confirm {
+                error.set(Some("Passwords do not match".to_string()));
+                return;
+            }
+
+            let req = ResetPasswordRequest {
+                token,
+                new_password: (*password).clone(),
+            };
+            let message = message.clone();
+            let error = error.clone();
+            let loading = loading.clone();
+
+            loading.set(true);
+            error.set(None);
+
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::post::<MessageResponse, _>("/auth/reset-password", &req).await {
+                    Ok(response) => message.set(Some(response.message)),
+                    Err(e) => error.set(Some(e.message)),
+                }
+                loading.set(false);
+            });
+        })
+    };
+
+    html! {
+        <div class="auth-page">
+            <div class="auth-form-container">
+                <h1>{"Reset Password"}</h1>
+
+                if let Some(err) = (*error).clone() {
+                    <div class="error-message">{err}</div>
+                }
+
+                if let Some(msg) = (*message).clone() {
+                    <div class="success-message">{msg}</div>
+                } else if token.is_none() {
+                    <div class="error-message">{"This reset link is missing its token."}</div>
+                } else {
+                    <form onsubmit={on_submit}>
+                        <div class="form-group">
+                            <label for="password">{"New Password"}</label>
+                            <input
+                                type="password"
+                                id="password"
+                                value={(*password).clone()}
+                                oninput={on_password_change}
+                                required=true
+                            />
+                        </div>
+
+                        <div class="form-group">
+                            <label for="confirm">{"Confirm Password"}</label>
+                            <input
+                                type="password"
+                                id="confirm"
+                                value={(*confirm).clone()}
+                                oninput={on_confirm_change}
+                                required=true
+                            />
+                        </div>
+
+                        <button type="submit" class="btn btn-primary btn-full" disabled={*loading}>
+                            if *loading {
+                                {"Saving..."}
+                            } else {
+                                {"Set New Password"}
+                            }
+                        </button>
+                    </form>
+                }
+
+                <p class="auth-link">
+                    <Link<Route> to={Route::Login}>{"Back to login"}</Link<Route>>
+                </p>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,222 @@
+use yew::prelude::*;
+use yew_router::prelude::*;
+use web_sys::HtmlInputElement;
+use shared::{
+    MessageResponse, TwoFactorCodeRequest, TwoFactorEnabledResponse, TwoFactorSetupResponse, TwoFactorStatusResponse,
+};
+use crate::api;
+use crate::components::Loading;
+use crate::state::use_auth;
+use crate::routes::Route;
+
+/// Two-factor authentication enrollment and management
+#[function_component(SecurityPage)]
+pub fn security_page() -> Html {
+    let auth = use_auth();
+    let navigator = use_navigator().unwrap();
+    let status = use_state(|| Option::<TwoFactorStatusResponse>::None);
+    let setup = use_state(|| Option::<TwoFactorSetupResponse>::None);
+    let recovery_codes = use_state(Vec::<String>::new);
+    let code = use_state(String::new);
+    let error = use_state(|| Option::<String>::None);
+    let message = use_state(|| Option::<String>::None);
+    let busy = use_state(|| false);
+
+    // Redirect if not logged in
+    if auth.user.is_none() {
+        navigator.push(&Route::Login);
+        return html! {};
+    }
+
+    {
+        let status = status.clone();
+        let error = error.clone();
+
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::get::<TwoFactorStatusResponse>("/auth/2fa").await {
+                    Ok(response) => status.set(Some(response)),
+                    Err(e) => error.set(Some(e.message)),
+                }
+            });
+            || ()
+        });
+    }
+
+    let on_code_change = {
+        let code = code.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            code.set(input.value());
+        })
+    };
+
+    let on_start_setup = {
+        let setup = setup.clone();
+        let error = error.clone();
+
+        Callback::from(move |_| {
+            let setup = setup.clone();
+            let error = error.clone();
+            error.set(None);
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::post::<TwoFactorSetupResponse, _>("/auth/2fa/setup", &()).await {
+                    Ok(response) => setup.set(Some(response)),
+                    Err(e) => error.set(Some(e.message)),
+                }
+            });
+        })
+    };
+
+    let on_confirm = {
+        let code = code.clone();
+        let status = status.clone();
+        let setup = setup.clone();
+        let recovery_codes = recovery_codes.clone();
+        let error = error.clone();
+        let busy = busy.clone();
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+
+            let req = TwoFactorCodeRequest { code: (*code).clone() };
+            let code = code.clone();
+            let status = status.clone();
+            let setup = setup.clone();
+            let recovery_codes = recovery_codes.clone();
+            let error = error.clone();
+            let busy = busy.clone();
+
+            busy.set(true);
+            error.set(None);
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::post::<TwoFactorEnabledResponse, _>("/auth/2fa/confirm", &req).await {
+                    Ok(response) => {
+                        status.set(Some(TwoFactorStatusResponse {
+                            enabled: true,
+                            recovery_codes_remaining: response.recovery_codes.len() as i64,
+                        }));
+                        recovery_codes.set(response.recovery_codes);
+                        setup.set(None);
+                        code.set(String::new());
+                    }
+                    Err(e) => error.set(Some(e.message)),
+                }
+                busy.set(false);
+            });
+        })
+    };
+
+    let on_disable = {
+        let code = code.clone();
+        let status = status.clone();
+        let recovery_codes = recovery_codes.clone();
+        let error = error.clone();
+        let message = message.clone();
+        let busy = busy.clone();
+
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+
+            let req = TwoFactorCodeRequest { code: (*code).clone() };
+            let code = code.clone();
+            let status = status.clone();
+            let recovery_codes = recovery_codes.clone();
+            let error = error.clone();
+            let message = message.clone();
+            let busy = busy.clone();
+
+            busy.set(true);
+            error.set(None);
+            wasm_bindgen_futures::spawn_local(async move {
+                match api::post::<MessageResponse, _>("/auth/2fa/disable", &req).await {
+                    Ok(response) => {
+                        status.set(Some(TwoFactorStatusResponse {
+                            enabled: false,
+                            recovery_codes_remaining: 0,
+                        }));
+                        recovery_codes.set(Vec::new());
+                        message.set(Some(response.message));
+                        code.set(String::new());
+                    }
+                    Err(e) => error.set(Some(e.message)),
+                }
+                busy.set(false);
+            });
+        })
+    };
+
+    let Some(current) = (*status).clone() else {
+        return match (*error).clone() {
+            Some(err) => html! { <div class="error-message">{err}</div> },
+            None => html! { <Loading message="Loading security settings..." /> },
+        };
+    };
+
+    let code_input = html! {
+        <div class="form-group">
+            <label for="code">{"Authentication code"}</label>
+            <input
+                type="text"
+                id="code"
+                autocomplete="one-time-code"
+                value={(*code).clone()}
+                oninput={on_code_change}
+                required=true
+            />
+        </div>
+    };
+
+    html! {
+        <div class="security-page">
+            <h1>{"Two-Factor Authentication"}</h1>
+
+            if let Some(err) = (*error).clone() {
+                <div class="error-message">{err}</div>
+            }
+            if let Some(msg) = (*message).clone() {
+                <div class="success-message">{msg}</div>
+            }
+
+            if !recovery_codes.is_empty() {
+                <div class="recovery-codes">
+                    <h2>{"Recovery codes"}</h2>
+                    <p>{"Store these somewhere safe. Each code signs you in once if you lose your device. They will not be shown again."}</p>
+                    <ul>
+                        {for recovery_codes.iter().map(|c| html! { <li><code>{c}</code></li> })}
+                    </ul>
+                </div>
+            }
+
+            if current.enabled {
+                <p>{format!(
+                    "Two-factor authentication is on. {} recovery codes left.",
+                    current.recovery_codes_remaining
+                )}</p>
+                <form class="security-form" onsubmit={on_disable}>
+                    {code_input}
+                    <button type="submit" class="btn btn-danger" disabled={*busy}>
+                        {"Disable two-factor authentication"}
+                    </button>
+                </form>
+            } else if let Some(pending) = (*setup).clone() {
+                <div class="totp-setup">
+                    <p>{"Add this account to your authenticator app, then enter the 6-digit code it shows."}</p>
+                    <p><a href={pending.otpauth_uri.clone()}>{"Open in authenticator app"}</a></p>
+                    <p>{"Or enter the key manually: "}<code>{&pending.secret}</code></p>
+                </div>
+                <form class="security-form" onsubmit={on_confirm}>
+                    {code_input}
+                    <button type="submit" class="btn btn-primary" disabled={*busy}>
+                        {"Confirm and enable"}
+                    </button>
+                </form>
+            } else {
+                <p>{"Protect your account with a code from an authenticator app in addition to your password."}</p>
+                <button class="btn btn-primary" onclick={on_start_setup}>
+                    {"Set up two-factor authentication"}
+                </button>
+            }
+        </div>
+    }
+}
@@ -0,0 +1,59 @@
+use yew::prelude::*;
+use yew_router::prelude::*;
+use serde::Deserialize;
+use shared::MessageResponse;
+use crate::api;
+use crate::components::Loading;
+use crate::routes::Route;
+
+/// The emailed link carries the token as `?token=`
+#[derive(Deserialize)]
+struct VerifyQuery {
+    token: String,
+}
+
+#[function_component(VerifyEmailPage)]
+pub fn verify_email_page() -> Html {
+    let location = use_location();
+    let token = location
+        .and_then(|l| l.query::<VerifyQuery>().ok())
+        .map(|q| q.token);
+
+    let result = use_state(|| Option::<Result<String, String>>::None);
+
+    {
+        let result = result.clone();
+
+        use_effect_with(token, move |token| {
+            match token.clone() {
+                Some(token) => wasm_bindgen_futures::spawn_local(async move {
+                    // Tokens are hex, so they need no URL encoding
+                    match api::get::<MessageResponse>(&format!("/auth/verify-email?token={}", token)).await {
+                        Ok(response) => result.set(Some(Ok(response.message))),
+                        Err(e) => result.set(Some(Err(e.message))),
+                    }
+                }),
+                None => result.set(Some(Err("This verification link is missing its token.".to_string()))),
+            }
+            || ()
+        });
+    }
+
+    html! {
+        <div class="auth-page">
+            <div class="auth-form-container">
+                <h1>{"Email Verification"}</h1>
+
+                {match (*result).clone() {
+                    None => html! { <Loading message="Verifying..." /> },
+                    Some(Ok(msg)) => html! { <div class="success-message">{msg}</div> },
+                    Some(Err(err)) => html! { <div class="error-message">{err}</div> },
+                }}
+
+                <p class="auth-link">
+                    <Link<Route> to={Route::Products}>{"Continue shopping"}</Link<Route>>
+                </p>
+            </div>
+        </div>
+    }
+}
@@ -8,6 +8,8 @@ pub enum Route {
     Products,
     #[at("/products/:id")]
     ProductDetail { id: String },
+    #[at("/categories/:slug")]
+    Category { slug: String },
     #[at("/cart")]
     Cart,
     #[at("/orders")]
@@ -18,6 +20,14 @@ pub enum Route {
     Login,
     #[at("/register")]
     Register,
+    #[at("/forgot-password")]
+    ForgotPassword,
+    #[at("/reset-password")]
+    ResetPassword,
+    #[at("/verify-email")]
+    VerifyEmail,
+    #[at("/account/security")]
+    Security,
     #[not_found]
     #[at("/404")]
     NotFound,
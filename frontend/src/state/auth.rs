@@ -20,7 +20,7 @@ impl Default for AuthState {
 }
 
 pub enum AuthAction {
-    Login { user: UserProfile, token: String },
+    Login { user: UserProfile, token: String, refresh_token: String },
     Logout,
     SetLoading(bool),
 }
@@ -30,8 +30,9 @@ impl Reducible for AuthState {
 
     fn reduce(self: std::rc::Rc<Self>, action: Self::Action) -> std::rc::Rc<Self> {
         match action {
-            AuthAction::Login { user, token } => {
+            AuthAction::Login { user, token, refresh_token } => {
                 api::set_token(&token);
+                api::set_refresh_token(&refresh_token);
                 Self {
                     user: Some(user),
                     token: Some(token),
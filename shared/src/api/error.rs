@@ -23,6 +23,10 @@ impl ApiError {
         Self::new("UNAUTHORIZED", message)
     }
 
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::new("FORBIDDEN", message)
+    }
+
     pub fn bad_request(message: impl Into<String>) -> Self {
         Self::new("BAD_REQUEST", message)
     }
@@ -1,5 +1,8 @@
+use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use crate::models::{Address, CouponKind, Money, OrderStatus, ProductOption, ReturnStatus};
 
 // Auth requests
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,21 +18,104 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LogoutRequest {
+    /// Revoke every session of the user instead of only the current one
+    #[serde(default)]
+    pub all: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+/// A TOTP code, or a recovery code where the endpoint accepts one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFactorCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFactorLoginRequest {
+    pub challenge_token: String,
+    pub code: String,
+}
+
 // Product requests
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ProductListParams {
     pub page: Option<u32>,
     pub limit: Option<u32>,
+    /// Comma-separated categories; products in any of them match
     pub category: Option<String>,
+    /// Lower price bound in minor units, inclusive
+    pub min_price: Option<i64>,
+    /// Upper price bound in minor units, exclusive
+    pub max_price: Option<i64>,
+    /// Only products with stock left
+    pub in_stock: Option<bool>,
     pub search: Option<String>,
+    /// `price`, `name`, `newest` or `relevance` (the default when searching)
     pub sort_by: Option<String>,
+    /// `asc` (default) or `desc`
     pub sort_order: Option<String>,
 }
 
+/// Replace a product's options; only allowed while it has no variants
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetProductOptionsRequest {
+    pub options: Vec<ProductOption>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateVariantRequest {
+    pub sku: String,
+    /// A value for every product option
+    pub options: BTreeMap<String, String>,
+    /// Leave unset to use the product price
+    #[serde(default)]
+    pub price: Option<Money>,
+    pub stock: i32,
+}
+
+/// Partial update; fields left as `None` keep their current value
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateVariantRequest {
+    pub sku: Option<String>,
+    pub price: Option<Money>,
+    /// Drop the price override and fall back to the product price
+    #[serde(default)]
+    pub clear_price: bool,
+    pub stock: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SuggestParams {
+    #[serde(default)]
+    pub q: String,
+    pub limit: Option<u32>,
+}
+
 // Cart requests
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AddToCartRequest {
     pub product_id: Uuid,
+    /// Required for products that have variants
+    #[serde(default)]
+    pub variant_id: Option<Uuid>,
     pub quantity: i32,
 }
 
@@ -38,10 +124,52 @@ pub struct UpdateCartItemRequest {
     pub quantity: i32,
 }
 
-// Order requests
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplyCouponRequest {
+    pub code: String,
+}
+
+// Order requests
+/// Ship to a saved address (`address_id`) or a one-off `shipping_address`;
+/// with neither, the user's default address is used
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct CreateOrderRequest {
-    pub shipping_address: Option<String>,
+    #[serde(default)]
+    pub address_id: Option<Uuid>,
+    #[serde(default)]
+    pub shipping_address: Option<Address>,
+}
+
+/// Body of `POST /api/orders/:id/pay`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PayOrderRequest {
+    /// Provider-specific payment method token, e.g. a tokenized card
+    #[serde(default)]
+    pub payment_method: Option<String>,
+}
+
+// Return requests
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReturnItemRequest {
+    pub order_item_id: Uuid,
+    pub quantity: i32,
+}
+
+/// Body of `POST /api/orders/:id/returns`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateReturnRequest {
+    pub items: Vec<ReturnItemRequest>,
+    pub reason: String,
+}
+
+// Address book requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveAddressRequest {
+    pub label: Option<String>,
+    #[serde(flatten)]
+    pub address: Address,
+    #[serde(default)]
+    pub is_default: bool,
 }
 
 // Admin product requests
@@ -49,19 +177,179 @@ pub struct CreateOrderRequest {
 pub struct CreateProductRequest {
     pub name: String,
     pub description: String,
-    pub price: f64,
+    pub price: Money,
     pub stock: i32,
+    /// Name or slug of an existing category
     pub category: String,
     pub image_url: Option<String>,
+    #[serde(default)]
+    pub reorder_threshold: Option<i32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateProductRequest {
     pub name: Option<String>,
     pub description: Option<String>,
-    pub price: Option<f64>,
+    pub price: Option<Money>,
     pub stock: Option<i32>,
+    /// Name or slug of an existing category
     pub category: Option<String>,
     pub image_url: Option<String>,
     pub is_active: Option<bool>,
+    pub reorder_threshold: Option<i32>,
+    /// Stop low-stock notifications for the product
+    #[serde(default)]
+    pub clear_reorder_threshold: bool,
+}
+
+/// Partial update of a gallery image; fields left as `None` keep their current value
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateProductImageRequest {
+    pub alt_text: Option<String>,
+    /// New zero-based place in the gallery; later images shift down
+    pub position: Option<i32>,
+    /// Make this the primary image
+    #[serde(default)]
+    pub make_primary: bool,
+}
+
+/// Manual stock correction, recorded in the ledger as an adjustment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdjustStockRequest {
+    /// Required for products sold by variant
+    pub variant_id: Option<Uuid>,
+    /// Signed change in units, e.g. -2 for two damaged items
+    pub quantity: i32,
+    /// Why the stock changed
+    pub note: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct StockMovementListParams {
+    pub variant_id: Option<Uuid>,
+    pub limit: Option<u32>,
+}
+
+/// Ask to be emailed when an out-of-stock product is back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateStockSubscriptionRequest {
+    pub product_id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AdminNotificationListParams {
+    /// Only notifications not yet marked read
+    #[serde(default)]
+    pub unread: bool,
+    pub limit: Option<u32>,
+}
+
+// Admin category requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCategoryRequest {
+    pub name: String,
+    /// Derived from the name when omitted
+    #[serde(default)]
+    pub slug: Option<String>,
+    #[serde(default)]
+    pub parent_id: Option<Uuid>,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub sort_order: i32,
+    #[serde(default)]
+    pub image_url: Option<String>,
+}
+
+/// Partial update; fields left as `None` keep their current value
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateCategoryRequest {
+    pub name: Option<String>,
+    pub slug: Option<String>,
+    pub parent_id: Option<Uuid>,
+    /// Move the category to the top level
+    #[serde(default)]
+    pub clear_parent: bool,
+    pub description: Option<String>,
+    pub sort_order: Option<i32>,
+    pub image_url: Option<String>,
+}
+
+// Admin coupon requests
+/// A new discount code; `percent_off` is required for percentage coupons and
+/// `amount_off` for fixed-amount ones
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateCouponRequest {
+    pub code: String,
+    #[serde(default)]
+    pub description: String,
+    pub kind: CouponKind,
+    #[serde(default)]
+    pub percent_off: Option<i32>,
+    #[serde(default)]
+    pub amount_off: Option<Money>,
+    #[serde(default)]
+    pub min_order_total: Option<Money>,
+    /// Limit the discount to these products
+    #[serde(default)]
+    pub product_ids: Vec<Uuid>,
+    /// Limit the discount to these categories and their subcategories
+    #[serde(default)]
+    pub category_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub max_uses: Option<i32>,
+    #[serde(default)]
+    pub max_uses_per_user: Option<i32>,
+    #[serde(default)]
+    pub starts_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+/// Partial update; fields left as `None` keep their current value. The
+/// discount itself is fixed once the code exists.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateCouponRequest {
+    pub description: Option<String>,
+    pub is_active: Option<bool>,
+    pub max_uses: Option<i32>,
+    pub max_uses_per_user: Option<i32>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    /// Remove the usage limits and end date
+    #[serde(default)]
+    pub clear_limits: bool,
+}
+
+// Admin order requests
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AdminOrderListParams {
+    pub page: Option<u32>,
+    pub limit: Option<u32>,
+    pub status: Option<OrderStatus>,
+    pub user_id: Option<Uuid>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateOrderStatusRequest {
+    pub status: OrderStatus,
+}
+
+// Admin return requests
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AdminReturnListParams {
+    pub status: Option<ReturnStatus>,
+}
+
+/// Approve (refunding the items) or reject a return request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewReturnRequest {
+    pub approve: bool,
+    /// Put the returned items back into stock; only used when approving
+    #[serde(default)]
+    pub restock: bool,
+    #[serde(default)]
+    pub note: Option<String>,
 }
@@ -1,13 +1,46 @@
 use serde::{Deserialize, Serialize};
-use crate::models::{Product, UserProfile, Cart, Order, OrderWithItems};
+use uuid::Uuid;
+use crate::models::{CategoryNode, Product, ProductCategory, ProductImage, ProductOption, ProductVariant, UserProfile, Cart, Order, OrderWithItems, SavedAddress, Payment, ReturnRequest, StockMovement, StockSubscription, AdminNotification, Coupon};
 
 // Auth responses
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthResponse {
     pub token: String,
+    pub refresh_token: String,
     pub user: UserProfile,
 }
 
+/// Result of the password step of login. Accounts with 2FA get a short-lived
+/// challenge token to exchange, together with a code, at `/auth/2fa/verify`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum LoginResponse {
+    Authenticated(AuthResponse),
+    TwoFactorRequired {
+        challenge_token: String,
+        expires_in: i64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TwoFactorStatusResponse {
+    pub enabled: bool,
+    pub recovery_codes_remaining: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFactorSetupResponse {
+    /// Base32 secret for manual entry
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+/// Recovery codes are shown exactly once, when 2FA is enabled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFactorEnabledResponse {
+    pub recovery_codes: Vec<String>,
+}
+
 // Product responses
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProductListResponse {
@@ -16,11 +49,85 @@ pub struct ProductListResponse {
     pub page: u32,
     pub limit: u32,
     pub total_pages: u32,
+    /// Matched terms for each product, only present for search results
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub highlights: Vec<SearchHighlight>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub facets: Option<ProductFacets>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    Product,
+    Category,
+}
+
+/// A type-ahead completion; product suggestions link straight to the product
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub text: String,
+    pub kind: SuggestionKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestResponse {
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// Result counts for each filter value. Each facet is counted with every
+/// other active filter applied but not its own, so alternatives stay visible.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProductFacets {
+    pub categories: Vec<CategoryFacet>,
+    pub price_ranges: Vec<PriceRangeFacet>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategoryFacet {
+    pub category: String,
+    pub count: u32,
+}
+
+/// Price bucket in minor units; `max` is exclusive and `None` is unbounded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceRangeFacet {
+    pub min: i64,
+    pub max: Option<i64>,
+    pub count: u32,
+}
+
+/// Name and description excerpt with matched terms wrapped in `<mark>` and
+/// `</mark>`. The text is not HTML-escaped; render it as plain text segments.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHighlight {
+    pub product_id: Uuid,
+    pub name: String,
+    pub snippet: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProductResponse {
     pub product: Product,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<ProductOption>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variants: Vec<ProductVariant>,
+    /// Gallery in display order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<ProductImage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductImageResponse {
+    pub image: ProductImage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductVariantResponse {
+    pub variant: ProductVariant,
 }
 
 // Cart responses
@@ -41,13 +148,99 @@ pub struct OrderListResponse {
     pub total: u32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentResponse {
+    pub payment: Payment,
+    pub order: OrderWithItems,
+}
+
+// Return responses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReturnResponse {
+    pub return_request: ReturnRequest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReturnListResponse {
+    pub returns: Vec<ReturnRequest>,
+}
+
+// Address book responses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddressResponse {
+    pub address: SavedAddress,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddressListResponse {
+    pub addresses: Vec<SavedAddress>,
+}
+
+// Stock ledger responses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockMovementResponse {
+    pub movement: StockMovement,
+    /// On-hand stock after the movement
+    pub stock: i32,
+}
+
+/// Newest movements first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockMovementListResponse {
+    pub movements: Vec<StockMovement>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockSubscriptionResponse {
+    pub subscription: StockSubscription,
+}
+
+/// Subscriptions still waiting for their product
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockSubscriptionListResponse {
+    pub subscriptions: Vec<StockSubscription>,
+}
+
+// Admin notification responses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminNotificationResponse {
+    pub notification: AdminNotification,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminNotificationListResponse {
+    pub notifications: Vec<AdminNotification>,
+    /// Unread notifications in total, not just on this page
+    pub unread: i64,
+}
+
+// Coupon responses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CouponResponse {
+    pub coupon: Coupon,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CouponListResponse {
+    pub coupons: Vec<Coupon>,
+}
+
 // Generic responses
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageResponse {
     pub message: String,
 }
 
+/// The category tree, top-level categories first
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CategoryListResponse {
-    pub categories: Vec<String>,
+    pub categories: Vec<CategoryNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryResponse {
+    pub category: CategoryNode,
+    /// Ancestors from the top-level category down to the parent
+    #[serde(default)]
+    pub breadcrumbs: Vec<ProductCategory>,
 }
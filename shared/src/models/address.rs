@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+/// A postal address; also stored as an immutable snapshot on each order
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Address {
+    pub name: String,
+    pub line1: String,
+    pub line2: Option<String>,
+    pub city: String,
+    pub region: Option<String>,
+    pub postal_code: String,
+    /// ISO 3166-1 alpha-2 country code, e.g. "US" or "CN"
+    pub country: String,
+    pub phone: Option<String>,
+}
+
+impl Address {
+    /// Check required fields, lengths and formats.
+    /// Returns a message suitable for a validation error response.
+    pub fn validate(&self) -> Result<(), String> {
+        fn required(value: &str, field: &str, max: usize) -> Result<(), String> {
+            let value = value.trim();
+            if value.is_empty() {
+                return Err(format!("{} is required", field));
+            }
+            optional(Some(value), field, max)
+        }
+
+        fn optional(value: Option<&str>, field: &str, max: usize) -> Result<(), String> {
+            match value {
+                Some(v) if v.trim().chars().count() > max => {
+                    Err(format!("{} must be at most {} characters", field, max))
+                }
+                _ => Ok(()),
+            }
+        }
+
+        required(&self.name, "Recipient name", 100)?;
+        required(&self.line1, "Address line 1", 200)?;
+        optional(self.line2.as_deref(), "Address line 2", 200)?;
+        required(&self.city, "City", 100)?;
+        optional(self.region.as_deref(), "Region", 100)?;
+        required(&self.postal_code, "Postal code", 20)?;
+
+        if !self
+            .postal_code
+            .trim()
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-')
+        {
+            return Err("Postal code may only contain letters, digits, spaces and dashes".to_string());
+        }
+
+        let country = self.country.trim();
+        if country.len() != 2 || !country.chars().all(|c| c.is_ascii_uppercase()) {
+            return Err("Country must be a two-letter ISO code, e.g. \"US\"".to_string());
+        }
+
+        if let Some(phone) = self.phone.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+            let digits = phone.chars().filter(|c| c.is_ascii_digit()).count();
+            let allowed = phone
+                .chars()
+                .all(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | ' ' | '(' | ')'));
+            if !allowed || !(5..=20).contains(&digits) {
+                return Err("Phone number is invalid".to_string());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An entry in a user's address book
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SavedAddress {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub label: Option<String>,
+    #[serde(flatten)]
+    pub address: Address,
+    pub is_default: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
@@ -1,33 +1,64 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use super::{DiscountLine, Money, MoneyError};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CartItem {
     pub user_id: Uuid,
     pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
     pub quantity: i32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CartItemWithProduct {
     pub product_id: Uuid,
+    #[serde(default)]
+    pub variant_id: Option<Uuid>,
     pub product_name: String,
-    pub product_price: f64,
+    /// e.g. "M / Red" for a variant
+    #[serde(default)]
+    pub variant_title: Option<String>,
+    /// Used to match category-restricted coupons
+    #[serde(default)]
+    pub category_id: Option<Uuid>,
+    /// Unit price, taking the variant's price override into account
+    pub product_price: Money,
     pub product_image_url: Option<String>,
     pub quantity: i32,
-    pub subtotal: f64,
+    pub subtotal: Money,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Cart {
     pub user_id: Uuid,
     pub items: Vec<CartItemWithProduct>,
-    pub total: f64,
+    /// Code applied to the cart, whether or not it currently gives a discount
+    #[serde(default)]
+    pub coupon_code: Option<String>,
+    /// Sum of the items before discounts
+    pub subtotal: Money,
+    #[serde(default)]
+    pub discounts: Vec<DiscountLine>,
+    pub shipping: Money,
+    /// Amount due: subtotal less discounts plus shipping
+    pub total: Money,
+    /// Why the applied coupon currently gives no discount
+    #[serde(default)]
+    pub coupon_error: Option<String>,
 }
 
 impl Cart {
-    pub fn calculate_total(&mut self) {
-        self.total = self.items.iter().map(|item| item.subtotal).sum();
+    pub fn calculate_total(&mut self) -> Result<(), MoneyError> {
+        let currency = self
+            .items
+            .first()
+            .map(|item| item.subtotal.currency)
+            .unwrap_or_default();
+        self.subtotal = Money::checked_sum(currency, self.items.iter().map(|item| item.subtotal))?;
+        let discount = Money::checked_sum(currency, self.discounts.iter().map(|line| line.amount))?;
+        self.total = self.subtotal.checked_sub(discount)?.checked_add(self.shipping)?;
+        Ok(())
     }
 
     pub fn is_empty(&self) -> bool {
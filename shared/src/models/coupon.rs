@@ -0,0 +1,405 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+use super::{CartItemWithProduct, Money, MoneyError};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CouponKind {
+    /// `percent_off` of each eligible item
+    Percentage,
+    /// `amount_off` spread over the eligible items
+    FixedAmount,
+    /// Waives the shipping fee
+    FreeShipping,
+}
+
+impl CouponKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CouponKind::Percentage => "percentage",
+            CouponKind::FixedAmount => "fixed_amount",
+            CouponKind::FreeShipping => "free_shipping",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "percentage" => Some(CouponKind::Percentage),
+            "fixed_amount" => Some(CouponKind::FixedAmount),
+            "free_shipping" => Some(CouponKind::FreeShipping),
+            _ => None,
+        }
+    }
+}
+
+/// A discount code. Restrictions to products or categories limit which cart
+/// items it discounts; with neither, it applies to the whole cart.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Coupon {
+    pub id: Uuid,
+    /// Stored upper case; codes are matched case-insensitively
+    pub code: String,
+    pub description: String,
+    pub kind: CouponKind,
+    /// 1-100, for percentage coupons
+    pub percent_off: Option<i32>,
+    /// For fixed-amount coupons
+    pub amount_off: Option<Money>,
+    /// Cart subtotal required before any discount
+    pub min_order_total: Option<Money>,
+    pub product_ids: Vec<Uuid>,
+    /// Subcategories of these are included
+    pub category_ids: Vec<Uuid>,
+    /// Redemptions allowed across all customers
+    pub max_uses: Option<i32>,
+    pub max_uses_per_user: Option<i32>,
+    pub times_used: i32,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A discount shown on a cart or order
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DiscountLine {
+    pub code: String,
+    pub description: String,
+    pub amount: Money,
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum CouponError {
+    #[error("This coupon is no longer active")]
+    Inactive,
+
+    #[error("This coupon is not valid yet")]
+    NotStarted,
+
+    #[error("This coupon has expired")]
+    Expired,
+
+    #[error("This coupon has been fully redeemed")]
+    UsedUp,
+
+    #[error("You have already used this coupon")]
+    AlreadyUsed,
+
+    #[error("This coupon requires an order total of at least {0}")]
+    BelowMinimum(Money),
+
+    #[error("This coupon does not apply to any item in your cart")]
+    NoEligibleItems,
+
+    #[error("Your order has no shipping fee to waive")]
+    NothingToWaive,
+
+    #[error(transparent)]
+    Money(#[from] MoneyError),
+}
+
+/// The result of applying a coupon to a cart
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedCoupon {
+    pub line: DiscountLine,
+    /// Each item's share of the discount, in cart order; zero for items the
+    /// coupon does not cover and for free shipping
+    pub item_discounts: Vec<Money>,
+}
+
+impl Coupon {
+    /// Whether the coupon discounts `item`. `categories` is the coupon's
+    /// categories together with their subcategories.
+    pub fn applies_to(&self, item: &CartItemWithProduct, categories: &[Uuid]) -> bool {
+        if self.product_ids.is_empty() && self.category_ids.is_empty() {
+            return true;
+        }
+
+        self.product_ids.contains(&item.product_id)
+            || item.category_id.is_some_and(|id| categories.contains(&id))
+    }
+
+    /// Price the coupon against cart items and the shipping fee, given how
+    /// many times this customer has already redeemed it
+    pub fn apply(
+        &self,
+        items: &[CartItemWithProduct],
+        shipping: Money,
+        categories: &[Uuid],
+        uses_by_user: i64,
+        now: DateTime<Utc>,
+    ) -> Result<AppliedCoupon, CouponError> {
+        if !self.is_active {
+            return Err(CouponError::Inactive);
+        }
+        if self.starts_at.is_some_and(|at| now < at) {
+            return Err(CouponError::NotStarted);
+        }
+        if self.ends_at.is_some_and(|at| now >= at) {
+            return Err(CouponError::Expired);
+        }
+        if self.max_uses.is_some_and(|max| self.times_used >= max) {
+            return Err(CouponError::UsedUp);
+        }
+        if self.max_uses_per_user.is_some_and(|max| uses_by_user >= max as i64) {
+            return Err(CouponError::AlreadyUsed);
+        }
+
+        let currency = shipping.currency;
+        let subtotal = Money::checked_sum(currency, items.iter().map(|item| item.subtotal))?;
+        if let Some(minimum) = self.min_order_total {
+            if subtotal.checked_sub(minimum)?.is_negative() {
+                return Err(CouponError::BelowMinimum(minimum));
+            }
+        }
+
+        let eligible: Vec<bool> = items.iter().map(|item| self.applies_to(item, categories)).collect();
+        if !eligible.contains(&true) {
+            return Err(CouponError::NoEligibleItems);
+        }
+
+        let mut item_discounts = vec![Money::zero(currency); items.len()];
+        let amount = match self.kind {
+            CouponKind::Percentage => {
+                let percent = self.percent_off.unwrap_or(0).clamp(0, 100) as i64;
+                for (i, item) in items.iter().enumerate().filter(|(i, _)| eligible[*i]) {
+                    item_discounts[i] = Money::new(item.subtotal.amount * percent / 100, currency);
+                }
+                Money::checked_sum(currency, item_discounts.iter().copied())?
+            }
+            CouponKind::FixedAmount => {
+                let eligible_total = Money::checked_sum(
+                    currency,
+                    items.iter().zip(&eligible).filter(|(_, e)| **e).map(|(item, _)| item.subtotal),
+                )?;
+                let off = self.amount_off.unwrap_or(Money::zero(currency));
+                // Never discount more than the items cost
+                let discount = if eligible_total.checked_sub(off)?.is_negative() { eligible_total } else { off };
+                allocate(discount, items, &eligible, eligible_total, &mut item_discounts);
+                discount
+            }
+            CouponKind::FreeShipping => {
+                if shipping.is_zero() {
+                    return Err(CouponError::NothingToWaive);
+                }
+                shipping
+            }
+        };
+
+        Ok(AppliedCoupon {
+            line: DiscountLine {
+                code: self.code.clone(),
+                description: self.label(),
+                amount,
+            },
+            item_discounts,
+        })
+    }
+
+    /// The description, or a summary of the discount if there is none
+    pub fn label(&self) -> String {
+        if !self.description.is_empty() {
+            return self.description.clone();
+        }
+
+        match (self.kind, self.percent_off, self.amount_off) {
+            (CouponKind::Percentage, Some(percent), _) => format!("{}% off", percent),
+            (CouponKind::FixedAmount, _, Some(amount)) => format!("{} off", amount),
+            (CouponKind::FreeShipping, _, _) => "Free shipping".to_string(),
+            _ => self.code.clone(),
+        }
+    }
+}
+
+/// Split `discount` over the eligible items in proportion to their subtotals,
+/// handing leftover cents to the first eligible items
+fn allocate(
+    discount: Money,
+    items: &[CartItemWithProduct],
+    eligible: &[bool],
+    eligible_total: Money,
+    shares: &mut [Money],
+) {
+    if eligible_total.is_zero() {
+        return;
+    }
+
+    let mut left = discount.amount;
+    for (i, item) in items.iter().enumerate().filter(|(i, _)| eligible[*i]) {
+        let share = (discount.amount as i128 * item.subtotal.amount as i128 / eligible_total.amount as i128) as i64;
+        shares[i] = Money::new(share, discount.currency);
+        left -= share;
+    }
+
+    for (i, item) in items.iter().enumerate().filter(|(i, _)| eligible[*i]) {
+        if left == 0 {
+            break;
+        }
+        if shares[i].amount < item.subtotal.amount {
+            shares[i].amount += 1;
+            left -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Currency;
+
+    fn item(cents: i64, quantity: i32, category_id: Option<Uuid>) -> CartItemWithProduct {
+        let price = Money::new(cents, Currency::USD);
+        CartItemWithProduct {
+            product_id: Uuid::new_v4(),
+            variant_id: None,
+            product_name: "Item".to_string(),
+            variant_title: None,
+            category_id,
+            product_price: price,
+            product_image_url: None,
+            quantity,
+            subtotal: price.checked_mul(quantity as i64).unwrap(),
+        }
+    }
+
+    fn coupon(kind: CouponKind) -> Coupon {
+        Coupon {
+            id: Uuid::new_v4(),
+            code: "SAVE".to_string(),
+            description: String::new(),
+            kind,
+            percent_off: None,
+            amount_off: None,
+            min_order_total: None,
+            product_ids: Vec::new(),
+            category_ids: Vec::new(),
+            max_uses: None,
+            max_uses_per_user: None,
+            times_used: 0,
+            starts_at: None,
+            ends_at: None,
+            is_active: true,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn usd(cents: i64) -> Money {
+        Money::new(cents, Currency::USD)
+    }
+
+    #[test]
+    fn percentage_rounds_down_per_item() {
+        let mut c = coupon(CouponKind::Percentage);
+        c.percent_off = Some(15);
+        let items = [item(999, 1, None), item(1001, 2, None)];
+
+        let applied = c.apply(&items, usd(0), &[], 0, Utc::now()).unwrap();
+        assert_eq!(applied.item_discounts, vec![usd(149), usd(300)]);
+        assert_eq!(applied.line.amount, usd(449));
+        assert_eq!(applied.line.description, "15% off");
+    }
+
+    #[test]
+    fn fixed_amount_is_split_and_capped() {
+        let mut c = coupon(CouponKind::FixedAmount);
+        c.amount_off = Some(usd(1000));
+        let items = [item(1000, 1, None), item(2000, 1, None)];
+
+        let applied = c.apply(&items, usd(0), &[], 0, Utc::now()).unwrap();
+        assert_eq!(applied.item_discounts, vec![usd(334), usd(666)]);
+        assert_eq!(applied.line.amount, usd(1000));
+
+        c.amount_off = Some(usd(5000));
+        let applied = c.apply(&items, usd(0), &[], 0, Utc::now()).unwrap();
+        assert_eq!(applied.line.amount, usd(3000));
+        assert_eq!(applied.item_discounts, vec![usd(1000), usd(2000)]);
+    }
+
+    #[test]
+    fn full_discount_leaves_a_zero_total() {
+        let items = [item(999, 1, None), item(1001, 2, None)];
+        let subtotal = usd(3001);
+
+        let mut c = coupon(CouponKind::Percentage);
+        c.percent_off = Some(100);
+        let applied = c.apply(&items, usd(0), &[], 0, Utc::now()).unwrap();
+        assert!(subtotal.checked_sub(applied.line.amount).unwrap().is_zero());
+
+        let mut c = coupon(CouponKind::FixedAmount);
+        c.amount_off = Some(usd(3001));
+        let applied = c.apply(&items, usd(0), &[], 0, Utc::now()).unwrap();
+        assert!(subtotal.checked_sub(applied.line.amount).unwrap().is_zero());
+    }
+
+    #[test]
+    fn restrictions_limit_the_discounted_items() {
+        let shoes = Uuid::new_v4();
+        let mut c = coupon(CouponKind::Percentage);
+        c.percent_off = Some(50);
+        c.category_ids = vec![Uuid::new_v4()];
+        let items = [item(1000, 1, Some(shoes)), item(1000, 1, None)];
+
+        assert_eq!(c.apply(&items, usd(0), &[], 0, Utc::now()), Err(CouponError::NoEligibleItems));
+
+        let applied = c.apply(&items, usd(0), &[shoes], 0, Utc::now()).unwrap();
+        assert_eq!(applied.item_discounts, vec![usd(500), usd(0)]);
+    }
+
+    #[test]
+    fn free_shipping_waives_the_fee() {
+        let c = coupon(CouponKind::FreeShipping);
+        let items = [item(1000, 1, None)];
+
+        let applied = c.apply(&items, usd(599), &[], 0, Utc::now()).unwrap();
+        assert_eq!(applied.line.amount, usd(599));
+        assert_eq!(applied.item_discounts, vec![usd(0)]);
+        assert_eq!(c.apply(&items, usd(0), &[], 0, Utc::now()), Err(CouponError::NothingToWaive));
+    }
+
+    #[test]
+    fn rejects_coupons_that_cannot_be_used() {
+        let now = Utc::now();
+        let items = [item(1000, 1, None)];
+        let base = {
+            let mut c = coupon(CouponKind::Percentage);
+            c.percent_off = Some(10);
+            c
+        };
+
+        let mut c = base.clone();
+        c.is_active = false;
+        assert_eq!(c.apply(&items, usd(0), &[], 0, now), Err(CouponError::Inactive));
+
+        let mut c = base.clone();
+        c.starts_at = Some(now + chrono::Duration::days(1));
+        assert_eq!(c.apply(&items, usd(0), &[], 0, now), Err(CouponError::NotStarted));
+
+        let mut c = base.clone();
+        c.ends_at = Some(now);
+        assert_eq!(c.apply(&items, usd(0), &[], 0, now), Err(CouponError::Expired));
+
+        let mut c = base.clone();
+        c.max_uses = Some(5);
+        c.times_used = 5;
+        assert_eq!(c.apply(&items, usd(0), &[], 0, now), Err(CouponError::UsedUp));
+
+        let mut c = base.clone();
+        c.max_uses_per_user = Some(1);
+        assert_eq!(c.apply(&items, usd(0), &[], 1, now), Err(CouponError::AlreadyUsed));
+
+        let mut c = base;
+        c.min_order_total = Some(usd(1001));
+        assert_eq!(c.apply(&items, usd(0), &[], 0, now), Err(CouponError::BelowMinimum(usd(1001))));
+    }
+
+    #[test]
+    fn test_kind_round_trip() {
+        for kind in [CouponKind::Percentage, CouponKind::FixedAmount, CouponKind::FreeShipping] {
+            let json = serde_json::to_string(&kind).unwrap();
+            assert_eq!(json, format!("\"{}\"", kind.as_str()));
+            assert_eq!(CouponKind::parse(kind.as_str()), Some(kind));
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+/// Why on-hand stock changed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StockMovementReason {
+    /// Units taken by a paid order
+    Sale,
+    /// Units put back when a paid order was cancelled
+    Cancellation,
+    /// Manual correction by an admin
+    Adjustment,
+    /// Returned units put back into stock
+    Return,
+    /// Stock brought in: opening balances, new products and variants
+    Import,
+}
+
+impl StockMovementReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StockMovementReason::Sale => "sale",
+            StockMovementReason::Cancellation => "cancellation",
+            StockMovementReason::Adjustment => "adjustment",
+            StockMovementReason::Return => "return",
+            StockMovementReason::Import => "import",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "sale" => Some(StockMovementReason::Sale),
+            "cancellation" => Some(StockMovementReason::Cancellation),
+            "adjustment" => Some(StockMovementReason::Adjustment),
+            "return" => Some(StockMovementReason::Return),
+            "import" => Some(StockMovementReason::Import),
+            _ => None,
+        }
+    }
+}
+
+/// One entry of the append-only stock ledger. On-hand stock of a product or
+/// variant is the sum of its movements.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StockMovement {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
+    /// Signed change in units
+    pub quantity: i32,
+    pub reason: StockMovementReason,
+    pub note: Option<String>,
+    /// User who caused the change; `None` for the system
+    pub actor_id: Option<Uuid>,
+    /// Order or return the change belongs to
+    pub reference_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A customer waiting to hear that an out-of-stock product is back
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StockSubscription {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub product_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    /// When the back-in-stock email was queued
+    pub notified_at: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reason_round_trip() {
+        for reason in [
+            StockMovementReason::Sale,
+            StockMovementReason::Cancellation,
+            StockMovementReason::Adjustment,
+            StockMovementReason::Return,
+            StockMovementReason::Import,
+        ] {
+            let json = serde_json::to_string(&reason).unwrap();
+            assert_eq!(json, format!("\"{}\"", reason.as_str()));
+            assert_eq!(StockMovementReason::parse(reason.as_str()), Some(reason));
+        }
+    }
+}
@@ -2,8 +2,22 @@ mod product;
 mod user;
 mod cart;
 mod order;
+mod address;
+mod money;
+mod payment;
+mod returns;
+mod inventory;
+mod notification;
+mod coupon;
 
 pub use product::*;
 pub use user::*;
 pub use cart::*;
 pub use order::*;
+pub use address::*;
+pub use money::*;
+pub use payment::*;
+pub use returns::*;
+pub use inventory::*;
+pub use notification::*;
+pub use coupon::*;
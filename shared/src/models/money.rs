@@ -0,0 +1,208 @@
+use std::fmt;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+/// ISO 4217 currency code, e.g. "USD"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Currency([u8; 3]);
+
+impl Currency {
+    pub const USD: Currency = Currency(*b"USD");
+    pub const EUR: Currency = Currency(*b"EUR");
+    pub const CNY: Currency = Currency(*b"CNY");
+    pub const JPY: Currency = Currency(*b"JPY");
+
+    /// Parse a three-letter uppercase code
+    pub fn new(code: &str) -> Option<Self> {
+        let bytes: [u8; 3] = code.as_bytes().try_into().ok()?;
+        bytes
+            .iter()
+            .all(|b| b.is_ascii_uppercase())
+            .then_some(Currency(bytes))
+    }
+
+    pub fn code(&self) -> &str {
+        // Only ever constructed from ASCII uppercase letters
+        std::str::from_utf8(&self.0).unwrap_or("???")
+    }
+
+    /// Number of minor-unit digits (2 for cents, 0 for yen)
+    pub fn decimals(&self) -> u32 {
+        match &self.0 {
+            b"JPY" | b"KRW" | b"VND" | b"CLP" | b"ISK" => 0,
+            _ => 2,
+        }
+    }
+
+    fn symbol(&self) -> Option<&'static str> {
+        match &self.0 {
+            b"USD" => Some("$"),
+            b"EUR" => Some("€"),
+            b"GBP" => Some("£"),
+            b"CNY" | b"JPY" => Some("¥"),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Currency {
+    fn default() -> Self {
+        Currency::USD
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl Serialize for Currency {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for Currency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Currency::new(&code)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid currency code: {}", code)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum MoneyError {
+    #[error("Currency mismatch: {0} vs {1}")]
+    CurrencyMismatch(Currency, Currency),
+
+    #[error("Amount overflow")]
+    Overflow,
+}
+
+/// An exact monetary amount in integer minor units (e.g. cents)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Money {
+    pub amount: i64,
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount: i64, currency: Currency) -> Self {
+        Self { amount, currency }
+    }
+
+    pub fn zero(currency: Currency) -> Self {
+        Self::new(0, currency)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.amount == 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.amount < 0
+    }
+
+    pub fn checked_add(self, other: Money) -> Result<Money, MoneyError> {
+        self.same_currency(&other)?;
+        self.amount
+            .checked_add(other.amount)
+            .map(|amount| Money::new(amount, self.currency))
+            .ok_or(MoneyError::Overflow)
+    }
+
+    pub fn checked_sub(self, other: Money) -> Result<Money, MoneyError> {
+        self.same_currency(&other)?;
+        self.amount
+            .checked_sub(other.amount)
+            .map(|amount| Money::new(amount, self.currency))
+            .ok_or(MoneyError::Overflow)
+    }
+
+    /// Multiply by a quantity, e.g. unit price x items
+    pub fn checked_mul(self, quantity: i64) -> Result<Money, MoneyError> {
+        self.amount
+            .checked_mul(quantity)
+            .map(|amount| Money::new(amount, self.currency))
+            .ok_or(MoneyError::Overflow)
+    }
+
+    /// Sum amounts that must all be in `currency`
+    pub fn checked_sum<I>(currency: Currency, items: I) -> Result<Money, MoneyError>
+    where
+        I: IntoIterator<Item = Money>,
+    {
+        items
+            .into_iter()
+            .try_fold(Money::zero(currency), |acc, m| acc.checked_add(m))
+    }
+
+    fn same_currency(&self, other: &Money) -> Result<(), MoneyError> {
+        if self.currency == other.currency {
+            Ok(())
+        } else {
+            Err(MoneyError::CurrencyMismatch(self.currency, other.currency))
+        }
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.amount < 0 { "-" } else { "" };
+        let abs = self.amount.unsigned_abs();
+        let decimals = self.currency.decimals();
+        let scale = 10u64.pow(decimals);
+
+        let number = if decimals == 0 {
+            abs.to_string()
+        } else {
+            format!("{}.{:0width$}", abs / scale, abs % scale, width = decimals as usize)
+        };
+
+        match self.currency.symbol() {
+            Some(symbol) => write!(f, "{}{}{}", sign, symbol, number),
+            None => write!(f, "{}{} {}", sign, number, self.currency),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_addition() {
+        let a = Money::new(10, Currency::USD);
+        let b = Money::new(20, Currency::USD);
+        assert_eq!(a.checked_add(b).unwrap(), Money::new(30, Currency::USD));
+        assert_eq!(a.checked_sub(b).unwrap(), Money::new(-10, Currency::USD));
+        assert_eq!(Money::new(1999, Currency::USD).checked_mul(3).unwrap().amount, 5997);
+    }
+
+    #[test]
+    fn test_checked_errors() {
+        let usd = Money::new(100, Currency::USD);
+        let eur = Money::new(100, Currency::EUR);
+        assert!(matches!(usd.checked_add(eur), Err(MoneyError::CurrencyMismatch(_, _))));
+        assert_eq!(Money::new(i64::MAX, Currency::USD).checked_add(usd), Err(MoneyError::Overflow));
+        assert_eq!(Money::new(i64::MAX, Currency::USD).checked_mul(2), Err(MoneyError::Overflow));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Money::new(1999, Currency::USD).to_string(), "$19.99");
+        assert_eq!(Money::new(-5, Currency::EUR).to_string(), "-€0.05");
+        assert_eq!(Money::new(500, Currency::JPY).to_string(), "¥500");
+        assert_eq!(Money::new(1234, Currency::new("CHF").unwrap()).to_string(), "12.34 CHF");
+    }
+
+    #[test]
+    fn test_serde() {
+        let money = Money::new(1999, Currency::USD);
+        let json = serde_json::to_string(&money).unwrap();
+        assert_eq!(json, r#"{"amount":1999,"currency":"USD"}"#);
+        assert_eq!(serde_json::from_str::<Money>(&json).unwrap(), money);
+        assert!(serde_json::from_str::<Money>(r#"{"amount":1,"currency":"usd"}"#).is_err());
+    }
+}
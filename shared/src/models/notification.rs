@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminNotificationKind {
+    /// A product's available stock fell below its reorder threshold
+    LowStock,
+}
+
+impl AdminNotificationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminNotificationKind::LowStock => "low_stock",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "low_stock" => Some(AdminNotificationKind::LowStock),
+            _ => None,
+        }
+    }
+}
+
+/// Something an admin should look at, shown until marked read
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AdminNotification {
+    pub id: Uuid,
+    pub kind: AdminNotificationKind,
+    pub product_id: Option<Uuid>,
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+    pub read_at: Option<DateTime<Utc>>,
+}
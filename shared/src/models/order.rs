@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use super::{Address, DiscountLine, Money};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
@@ -10,6 +11,51 @@ pub enum OrderStatus {
     Shipped,
     Delivered,
     Cancelled,
+    /// Some delivered items were returned and refunded
+    #[serde(rename = "partially_refunded")]
+    PartiallyRefunded,
+    /// Every item was returned and refunded
+    Refunded,
+}
+
+impl OrderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::Pending => "pending",
+            OrderStatus::Paid => "paid",
+            OrderStatus::Shipped => "shipped",
+            OrderStatus::Delivered => "delivered",
+            OrderStatus::Cancelled => "cancelled",
+            OrderStatus::PartiallyRefunded => "partially_refunded",
+            OrderStatus::Refunded => "refunded",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(OrderStatus::Pending),
+            "paid" => Some(OrderStatus::Paid),
+            "shipped" => Some(OrderStatus::Shipped),
+            "delivered" => Some(OrderStatus::Delivered),
+            "cancelled" => Some(OrderStatus::Cancelled),
+            "partially_refunded" => Some(OrderStatus::PartiallyRefunded),
+            "refunded" => Some(OrderStatus::Refunded),
+            _ => None,
+        }
+    }
+
+    /// Order lifecycle: Pending -> Paid -> Shipped -> Delivered,
+    /// with cancellation only possible before payment. Refund statuses are
+    /// reached only through approved returns, never set directly.
+    pub fn can_transition_to(&self, next: &OrderStatus) -> bool {
+        matches!(
+            (self, next),
+            (OrderStatus::Pending, OrderStatus::Paid)
+                | (OrderStatus::Pending, OrderStatus::Cancelled)
+                | (OrderStatus::Paid, OrderStatus::Shipped)
+                | (OrderStatus::Shipped, OrderStatus::Delivered)
+        )
+    }
 }
 
 impl Default for OrderStatus {
@@ -23,30 +69,103 @@ pub struct Order {
     pub id: Uuid,
     pub user_id: Uuid,
     pub status: OrderStatus,
-    pub total: f64,
+    pub total: Money,
+    #[serde(default)]
+    pub shipping_address: Option<Address>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct OrderItem {
     pub id: Uuid,
     pub order_id: Uuid,
     pub product_id: Uuid,
+    #[serde(default)]
+    pub variant_id: Option<Uuid>,
     pub product_name: String,
+    /// Variant title at the time of the order
+    #[serde(default)]
+    pub variant_title: Option<String>,
     pub quantity: i32,
-    pub price: f64,
-    pub subtotal: f64,
+    pub price: Money,
+    pub subtotal: Money,
+    /// This line's share of the order's coupon discount
+    #[serde(default)]
+    pub discount: Option<Money>,
+}
+
+/// One entry in an order's status timeline; `from_status` is `None` for the
+/// event recorded when the order is placed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderStatusEvent {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub from_status: Option<OrderStatus>,
+    pub to_status: OrderStatus,
+    pub changed_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderWithItems {
     pub order: Order,
     pub items: Vec<OrderItem>,
+    #[serde(default)]
+    pub timeline: Vec<OrderStatusEvent>,
+    /// Stock is held for an unpaid order until then
+    #[serde(default)]
+    pub reserved_until: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub discounts: Vec<DiscountLine>,
+    /// Shipping fee included in the total
+    #[serde(default)]
+    pub shipping: Option<Money>,
 }
 
 impl Order {
     pub fn can_cancel(&self) -> bool {
         self.status == OrderStatus::Pending
     }
+
+    /// Returns can be requested once the goods have arrived
+    pub fn can_request_return(&self) -> bool {
+        matches!(self.status, OrderStatus::Delivered | OrderStatus::PartiallyRefunded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_transitions() {
+        assert!(OrderStatus::Pending.can_transition_to(&OrderStatus::Paid));
+        assert!(OrderStatus::Paid.can_transition_to(&OrderStatus::Shipped));
+        assert!(OrderStatus::Shipped.can_transition_to(&OrderStatus::Delivered));
+        assert!(OrderStatus::Pending.can_transition_to(&OrderStatus::Cancelled));
+
+        assert!(!OrderStatus::Delivered.can_transition_to(&OrderStatus::Pending));
+        assert!(!OrderStatus::Pending.can_transition_to(&OrderStatus::Shipped));
+        assert!(!OrderStatus::Shipped.can_transition_to(&OrderStatus::Cancelled));
+        assert!(!OrderStatus::Cancelled.can_transition_to(&OrderStatus::Paid));
+        assert!(!OrderStatus::Paid.can_transition_to(&OrderStatus::Paid));
+    }
+
+    #[test]
+    fn test_status_round_trip() {
+        for status in [
+            OrderStatus::Pending,
+            OrderStatus::Paid,
+            OrderStatus::Shipped,
+            OrderStatus::Delivered,
+            OrderStatus::Cancelled,
+            OrderStatus::PartiallyRefunded,
+            OrderStatus::Refunded,
+        ] {
+            let json = serde_json::to_string(&status).unwrap();
+            assert_eq!(json, format!("\"{}\"", status.as_str()));
+            assert_eq!(OrderStatus::parse(status.as_str()), Some(status));
+        }
+    }
 }
@@ -1,29 +1,119 @@
+use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use super::Money;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Product {
     pub id: Uuid,
     pub name: String,
     pub description: String,
-    pub price: f64,
+    pub price: Money,
+    /// Units on hand
     pub stock: i32,
+    /// Units held by unpaid orders
+    #[serde(default)]
+    pub reserved: i32,
+    /// Admins are notified when available stock falls below this
+    #[serde(default)]
+    pub reorder_threshold: Option<i32>,
     pub category: String,
+    /// Full-size primary image
     pub image_url: Option<String>,
+    /// Small version of the primary image for listings
+    #[serde(default)]
+    pub thumbnail_url: Option<String>,
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// One picture in a product's gallery, shown in `position` order
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProductImage {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub url: String,
+    pub thumbnail_url: String,
+    pub alt_text: String,
+    pub position: i32,
+    pub is_primary: bool,
+    /// Pixel size, known for uploaded images
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+/// A node of the category tree. `Product::category` holds its name.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ProductCategory {
-    pub id: String,
+    pub id: Uuid,
+    pub parent_id: Option<Uuid>,
     pub name: String,
+    pub slug: String,
+    pub description: String,
+    /// Position among siblings, ascending
+    pub sort_order: i32,
+    pub image_url: Option<String>,
+}
+
+/// A category with its subcategories, as served to the storefront
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CategoryNode {
+    #[serde(flatten)]
+    pub category: ProductCategory,
+    /// Active products in this category and all of its descendants
+    pub product_count: u32,
+    pub children: Vec<CategoryNode>,
 }
 
 impl Product {
+    /// Units that can still be bought
+    pub fn available_stock(&self) -> i32 {
+        (self.stock - self.reserved).max(0)
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.is_active && self.available_stock() > 0
+    }
+}
+
+/// A choice offered for a product, e.g. Size with values S, M, L
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProductOption {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+/// A purchasable combination of option values with its own SKU and stock.
+/// For products with variants, `Product::stock` is the sum over variants.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProductVariant {
+    pub id: Uuid,
+    pub product_id: Uuid,
+    pub sku: String,
+    /// Option name to chosen value
+    pub options: BTreeMap<String, String>,
+    /// Option values in option order, e.g. "M / Red"
+    pub title: String,
+    /// Overrides the product price when set
+    pub price: Option<Money>,
+    pub stock: i32,
+    #[serde(default)]
+    pub reserved: i32,
+    pub is_active: bool,
+}
+
+impl ProductVariant {
+    pub fn price_or(&self, product_price: Money) -> Money {
+        self.price.unwrap_or(product_price)
+    }
+
+    pub fn available_stock(&self) -> i32 {
+        (self.stock - self.reserved).max(0)
+    }
+
     pub fn is_available(&self) -> bool {
-        self.is_active && self.stock > 0
+        self.is_active && self.available_stock() > 0
     }
 }
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use super::Money;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReturnStatus {
+    Requested,
+    Approved,
+    Rejected,
+}
+
+impl ReturnStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReturnStatus::Requested => "requested",
+            ReturnStatus::Approved => "approved",
+            ReturnStatus::Rejected => "rejected",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "requested" => Some(ReturnStatus::Requested),
+            "approved" => Some(ReturnStatus::Approved),
+            "rejected" => Some(ReturnStatus::Rejected),
+            _ => None,
+        }
+    }
+}
+
+/// A quantity of one order item being sent back
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReturnItem {
+    pub order_item_id: Uuid,
+    pub product_id: Uuid,
+    pub product_name: String,
+    pub quantity: i32,
+    /// Unit price paid times the returned quantity
+    pub refund_amount: Money,
+}
+
+/// A customer's request to return items of a delivered order (RMA)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReturnRequest {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub user_id: Uuid,
+    pub status: ReturnStatus,
+    pub reason: String,
+    pub items: Vec<ReturnItem>,
+    pub refund_amount: Money,
+    /// Whether the returned items went back into stock on approval
+    pub restocked: bool,
+    /// The payment provider's refund id, if the refund went through a provider
+    pub refund_id: Option<String>,
+    /// When an approval's restock, payment and order updates were applied;
+    /// unset on an approved return means the approval has to be finished
+    pub completed_at: Option<DateTime<Utc>>,
+    pub admin_note: Option<String>,
+    pub reviewed_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
@@ -24,6 +24,8 @@ pub struct User {
     pub password_hash: String,
     pub role: UserRole,
     pub created_at: DateTime<Utc>,
+    /// Set once the user follows the verification link sent at registration
+    pub email_verified_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -32,6 +34,8 @@ pub struct UserProfile {
     pub username: String,
     pub email: String,
     pub role: UserRole,
+    #[serde(default)]
+    pub email_verified: bool,
 }
 
 impl From<User> for UserProfile {
@@ -41,6 +45,7 @@ impl From<User> for UserProfile {
             username: user.username,
             email: user.email,
             role: user.role,
+            email_verified: user.email_verified_at.is_some(),
         }
     }
 }